@@ -0,0 +1,110 @@
+// Timings of the hot paths of the solver and the simulation, run with
+// `cargo bench`. The simulation needs the strategies for 5 dice in the cache,
+// made with compute-strats-5, and is skipped without them.
+
+use std::{
+    collections::HashMap,
+    hint::black_box,
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use ndarray::Array3;
+use yatzy_solver::{
+    dice_distributions::{DICE_DISTR, DICE_PROBS},
+    dice_throw::DiceThrow,
+    macrosolver::outcore::Layer,
+    simulation::simulate_n_5,
+    solver::solve_layer_type2,
+};
+
+// Runs `f` n times and prints the time of each run.
+fn bench(name: &str, n: usize, mut f: impl FnMut()) -> Duration {
+    f();
+
+    let timer = Instant::now();
+    for _ in 0..n {
+        f();
+    }
+    let t = timer.elapsed() / n as u32;
+
+    println!("{name:<32} {t:>12.2?}");
+
+    t
+}
+
+// Compares finding the index of 10 000 random throws with the closed form in
+// `DiceThrow::get_index` to the hash map lookup it replaced, and times going
+// back from the indices. The same throws are looked up over and over, to keep
+// them in the cache like in the solver.
+fn bench_ranking<const N: usize, const M: usize>(distr: &[([u8; N], u32); M]) {
+    let map: HashMap<[u8; N], usize> = distr
+        .iter()
+        .enumerate()
+        .map(|(i, &(d, _))| (d, i))
+        .collect();
+
+    let throws: Vec<_> = (0..10_000).map(|_| DiceThrow::throw(N)).collect();
+    let indices: Vec<_> = throws.iter().map(|t| t.get_index()).collect();
+
+    let t_map = bench(&format!("rank {N} dice, hash map"), 100, || {
+        for t in &throws {
+            black_box(map[&black_box(t).collect_dice::<N>()]);
+        }
+    });
+
+    let t_rank = bench(&format!("rank {N} dice, closed form"), 100, || {
+        for t in &throws {
+            black_box(black_box(t).get_index());
+        }
+    });
+
+    println!(
+        "  {:.1}x faster",
+        t_map.as_secs_f64() / t_rank.as_secs_f64()
+    );
+
+    bench(&format!("unrank {N} dice"), 100, || {
+        for &i in &indices {
+            black_box(DiceThrow::from_index(N, black_box(i)));
+        }
+    });
+}
+
+// Times the reroll stage of the solvers without saved throws on a layer with
+// 64 states.
+fn bench_rerolls<const N: usize>() {
+    let n_throws = DICE_PROBS[N].len();
+    let prev_scores = Array3::from_shape_fn([8, 8, n_throws], |(a, b, t)| {
+        ((a * 1000 + b * 100 + t) as f32 * 0.618).sin() * 50.0
+    });
+
+    bench(&format!("rerolls {N} dice, 64 states"), 10, || {
+        black_box(solve_layer_type2::<N>(black_box(&prev_scores)));
+    });
+}
+
+fn bench_simulate_5() {
+    let dir = Layer::<5, false>::empty().dir();
+
+    if !Path::new(&dir).exists() {
+        println!("simulate_n_5 skipped, there are no strategies in {dir}");
+        return;
+    }
+
+    let mut scores = vec![0; 10_000];
+
+    bench("simulate_n_5, 10 000 games", 5, || {
+        simulate_n_5(black_box(&mut scores));
+    });
+}
+
+fn main() {
+    bench_ranking(&DICE_DISTR.5);
+    bench_ranking(&DICE_DISTR.6);
+
+    bench_rerolls::<5>();
+    bench_rerolls::<6>();
+
+    bench_simulate_5();
+}
@@ -0,0 +1,287 @@
+// This is the state for the American rules, Yahtzee, played with 5 dice and
+// 13 cells: ones to sixes above the line, and 3 of a kind, 4 of a kind, full
+// house, small straight, large straight, yahtzee and chance below it.
+//
+// The differences from the Scandinavian rules are the fixed scores for full
+// house and straights, a bonus of 35 at 63 points above the line, and the
+// yahtzee bonus and joker rules. When the yahtzee box is filled, every new
+// yahtzee is a joker, which gives 100 extra points if the yahtzee box holds
+// 50, and has to be put in the matching upper cell if it is open, and
+// otherwise in any lower cell, where it scores as a full house and both
+// straights. Only if all lower cells are filled, it goes into an upper cell.
+
+use arrayvec::ArrayVec;
+
+use crate::{
+    dice_throw::DiceThrow,
+    level_ordering::{above_index, below_index_american},
+    rules::{RuleSet, Yahtzee},
+    util::parse_binary,
+};
+
+pub const AMERICAN_CELLS: usize = Yahtzee::CELLS;
+pub const YAHTZEE_CELL: usize = 11;
+
+const YAHTZEE_BONUS: usize = 100;
+
+// The filled cells are the bits of `cells`, as for `State`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct AmericanState {
+    cells: u32,
+    pub points_above: usize,
+    // Whether the yahtzee box is filled with 50 points.
+    pub yahtzee_bonus: bool,
+}
+
+impl From<([bool; 6], [bool; 7], usize, bool)> for AmericanState {
+    fn from(
+        (above, below, points_above, yahtzee_bonus): (
+            [bool; 6],
+            [bool; 7],
+            usize,
+            bool,
+        ),
+    ) -> Self {
+        Self {
+            cells: (parse_binary(&below) << 6 | parse_binary(&above)) as u32,
+            points_above,
+            yahtzee_bonus,
+        }
+    }
+}
+
+impl Default for AmericanState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AmericanState {
+    pub fn new() -> Self {
+        Self {
+            cells: 0,
+            points_above: 0,
+            yahtzee_bonus: false,
+        }
+    }
+
+    pub fn from_dyn(
+        dyn_cells: &[bool],
+        points_above: usize,
+        yahtzee_bonus: bool,
+    ) -> Self {
+        assert!(dyn_cells.len() == AMERICAN_CELLS);
+
+        Self {
+            cells: parse_binary(dyn_cells) as u32,
+            points_above,
+            yahtzee_bonus,
+        }
+    }
+
+    pub fn is_filled(&self, i: usize) -> bool {
+        self.cells & 1 << i != 0
+    }
+
+    pub fn get_above_cells(&self) -> u32 {
+        self.cells & 0b111111
+    }
+
+    pub fn get_below_cells(&self) -> u32 {
+        self.cells >> 6
+    }
+
+    pub fn get_n_above(&self) -> usize {
+        self.get_above_cells().count_ones() as usize
+    }
+
+    pub fn get_n_below(&self) -> usize {
+        self.get_below_cells().count_ones() as usize
+    }
+
+    pub fn is_joker(&self, throw: &DiceThrow) -> bool {
+        self.is_filled(YAHTZEE_CELL) && throw.yatzy() > 0
+    }
+
+    // The open cells the throw is allowed to be put in, which is all of them
+    // unless the throw is a joker.
+    pub fn allowed_cells(
+        &self,
+        throw: &DiceThrow,
+    ) -> ArrayVec<usize, AMERICAN_CELLS> {
+        let open = (0..AMERICAN_CELLS).filter(|&i| !self.is_filled(i));
+
+        if !self.is_joker(throw) {
+            return open.collect();
+        }
+
+        let upper = (1..=6).find(|&i| throw[i] > 0).unwrap() - 1;
+
+        if !self.is_filled(upper) {
+            return [upper].into_iter().collect();
+        }
+
+        let lower: ArrayVec<_, AMERICAN_CELLS> =
+            open.clone().filter(|&i| i >= 6).collect();
+
+        if lower.is_empty() {
+            open.collect()
+        } else {
+            lower
+        }
+    }
+
+    // A joker scores as a full house and as both straights.
+    pub fn cell_score(&self, i: usize, throw: &DiceThrow) -> usize {
+        match i {
+            8 if self.is_joker(throw) => 25,
+            9 if self.is_joker(throw) => 30,
+            10 if self.is_joker(throw) => 40,
+            _ => Yahtzee::cell_score(throw, i),
+        }
+    }
+
+    // Puts the throw in cell i, which has to be allowed, and gives the new
+    // state and the points scored, including any bonuses.
+    pub fn set_cell(&self, i: usize, throw: DiceThrow) -> (Self, usize) {
+        let mut state = *self;
+
+        let mut points = self.cell_score(i, &throw);
+        let mut extra = 0;
+
+        if self.yahtzee_bonus && throw.yatzy() > 0 {
+            extra += YAHTZEE_BONUS;
+        }
+
+        if i < 6 {
+            extra += Yahtzee::bonus(self.points_above + points)
+                - Yahtzee::bonus(self.points_above);
+
+            state.points_above += points;
+        }
+
+        if i == YAHTZEE_CELL && points > 0 {
+            state.yahtzee_bonus = true;
+        }
+
+        state.cells |= 1 << i;
+        points += extra;
+
+        (state, points)
+    }
+
+    // The above levels are shared with 5 dice, which have the same goal.
+    pub fn get_above_index(&self) -> usize {
+        let pts = Yahtzee::BONUS_THRESHOLD.min(self.points_above);
+
+        above_index::<5>(pts, self.get_above_cells())
+    }
+
+    pub fn get_below_index(&self) -> usize {
+        below_index_american(self.get_below_cells(), self.yahtzee_bonus)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The state with the yahtzee box filled, with 50 points if `bonus`, and
+    // the other cells given by `above` and `below`, which skips the yahtzee.
+    fn yahtzee_filled(
+        above: [bool; 6],
+        below: [bool; 6],
+        points_above: usize,
+        bonus: bool,
+    ) -> AmericanState {
+        let [tk, fk, fh, ss, ls, ch] = below;
+
+        AmericanState::from((
+            above,
+            [tk, fk, fh, ss, ls, true, ch],
+            points_above,
+            bonus,
+        ))
+    }
+
+    #[test]
+    fn joker_forced_in_upper_box() {
+        let state = yahtzee_filled([false; 6], [false; 6], 0, true);
+        let throw = DiceThrow::from([4, 4, 4, 4, 4]);
+
+        assert!(state.is_joker(&throw));
+        assert_eq!(state.allowed_cells(&throw).as_slice(), &[3]);
+
+        let (new_state, points) = state.set_cell(3, throw);
+
+        assert_eq!(points, 20 + YAHTZEE_BONUS);
+        assert_eq!(new_state.points_above, 20);
+        assert!(new_state.is_filled(3));
+    }
+
+    #[test]
+    fn free_joker_scores_fixed_cells() {
+        let mut above = [false; 6];
+        above[3] = true;
+
+        let state = yahtzee_filled(above, [false; 6], 12, true);
+        let throw = DiceThrow::from([4, 4, 4, 4, 4]);
+
+        assert_eq!(
+            state.allowed_cells(&throw).as_slice(),
+            &[6, 7, 8, 9, 10, 12]
+        );
+
+        for (cell, score) in [(6, 20), (7, 20), (8, 25), (9, 30), (10, 40)] {
+            assert_eq!(state.cell_score(cell, &throw), score);
+            assert_eq!(state.set_cell(cell, throw).1, score + YAHTZEE_BONUS);
+        }
+
+        // Only when the lower cells are full can the joker go above the line,
+        // where it scores zero.
+        let state = yahtzee_filled(above, [true; 6], 12, true);
+
+        assert_eq!(state.allowed_cells(&throw).as_slice(), &[0, 1, 2, 4, 5]);
+        assert_eq!(state.set_cell(0, throw).1, YAHTZEE_BONUS);
+    }
+
+    #[test]
+    fn zero_yahtzee_box_gives_no_bonus() {
+        let (state, points) = AmericanState::new()
+            .set_cell(YAHTZEE_CELL, DiceThrow::from([1, 2, 3, 4, 6]));
+
+        assert_eq!(points, 0);
+        assert!(!state.yahtzee_bonus);
+
+        let throw = DiceThrow::from([2, 2, 2, 2, 2]);
+
+        assert!(state.is_joker(&throw));
+        assert_eq!(state.allowed_cells(&throw).as_slice(), &[1]);
+        assert_eq!(state.set_cell(1, throw).1, 10);
+
+        // A yahtzee with 50 points in the box gives the bonus from then on.
+        let (state, points) = AmericanState::new()
+            .set_cell(YAHTZEE_CELL, DiceThrow::from([5, 5, 5, 5, 5]));
+
+        assert_eq!(points, 50);
+        assert!(state.yahtzee_bonus);
+        assert_eq!(state.set_cell(1, throw).1, 10 + YAHTZEE_BONUS);
+    }
+
+    #[test]
+    fn upper_bonus_at_63() {
+        let throw = DiceThrow::from([3, 1, 2, 4, 5]);
+        let state = |points_above| {
+            AmericanState::from(([false; 6], [false; 7], points_above, false))
+        };
+
+        assert_eq!(state(59).set_cell(2, throw).1, 3);
+        assert_eq!(state(60).set_cell(2, throw).1, 3 + 35);
+
+        // The bonus is only given once.
+        let (new_state, _) = state(60).set_cell(2, throw);
+
+        assert_eq!(new_state.points_above, 63);
+        assert_eq!(new_state.set_cell(0, throw).1, 1);
+    }
+}
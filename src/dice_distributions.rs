@@ -1,1553 +1,270 @@
-use std::collections::HashMap;
+use std::array;
 
-use ndarray::Array3;
+use ndarray::{s, Array2, Array3};
 use once_cell::sync::Lazy;
 
-use crate::dice_throw::DiceThrow;
+use crate::{dice_throw::DiceThrow, util::fnv1a};
+
+// The largest amount of dice there are tables for.
+pub const MAX_DICE: usize = 8;
+
+// The amount of different throws of N dice, when the order of the dice does
+// not matter, which is binomial(N + 5, 5).
+pub const fn amt_dice_combinations<const N: usize>() -> usize {
+    assert!(N <= MAX_DICE, "Unsupported amount of dice!");
+
+    (N + 1) * (N + 2) * (N + 3) * (N + 4) * (N + 5) / 120
+}
+
+const fn factorial(n: usize) -> u32 {
+    let mut res = 1;
+    let mut i = 2;
+
+    while i <= n {
+        res *= i as u32;
+        i += 1;
+    }
+
+    res
+}
+
+// All throws of K dice as sorted dice, in lexicographic order, together with
+// the amount of ordered throws giving each of them.
+const fn dice_distr<const K: usize, const N: usize>() -> [([u8; K], u32); N] {
+    assert!(N == amt_dice_combinations::<K>());
+
+    let mut distr = [([1; K], 0); N];
+    let mut dice = [1; K];
+    let mut i = 0;
+
+    loop {
+        let mut counts = [0; 7];
+        let mut j = 0;
+
+        while j < K {
+            counts[dice[j] as usize] += 1;
+            j += 1;
+        }
+
+        let mut mult = factorial(K);
+        let mut face = 1;
+
+        while face <= 6 {
+            mult /= factorial(counts[face]);
+            face += 1;
+        }
+
+        distr[i] = (dice, mult);
+        i += 1;
+
+        // The next sorted dice are found by increasing the last die that is
+        // not a six, and setting all dice after it to the same value.
+        let mut j = K;
+
+        while j > 0 && dice[j - 1] == 6 {
+            j -= 1;
+        }
+
+        if j == 0 {
+            break;
+        }
+
+        dice[j - 1] += 1;
+
+        let value = dice[j - 1];
+
+        while j < K {
+            dice[j] = value;
+            j += 1;
+        }
+    }
+
+    distr
+}
 
 // To make clippy happy :)
 type DiceDistributionType = (
     (),
-    [([u8; 1], u32); 6],
-    [([u8; 2], u32); 21],
-    [([u8; 3], u32); 56],
-    [([u8; 4], u32); 126],
-    [([u8; 5], u32); 252],
-    [([u8; 6], u32); 462],
+    [([u8; 1], u32); amt_dice_combinations::<1>()],
+    [([u8; 2], u32); amt_dice_combinations::<2>()],
+    [([u8; 3], u32); amt_dice_combinations::<3>()],
+    [([u8; 4], u32); amt_dice_combinations::<4>()],
+    [([u8; 5], u32); amt_dice_combinations::<5>()],
+    [([u8; 6], u32); amt_dice_combinations::<6>()],
+    [([u8; 7], u32); amt_dice_combinations::<7>()],
+    [([u8; 8], u32); amt_dice_combinations::<8>()],
 );
 
 pub const DICE_DISTR: DiceDistributionType = (
     (),
-    [([1], 1), ([2], 1), ([3], 1), ([4], 1), ([5], 1), ([6], 1)],
-    [
-        ([1, 1], 1),
-        ([1, 2], 2),
-        ([1, 3], 2),
-        ([1, 4], 2),
-        ([1, 5], 2),
-        ([1, 6], 2),
-        ([2, 2], 1),
-        ([2, 3], 2),
-        ([2, 4], 2),
-        ([2, 5], 2),
-        ([2, 6], 2),
-        ([3, 3], 1),
-        ([3, 4], 2),
-        ([3, 5], 2),
-        ([3, 6], 2),
-        ([4, 4], 1),
-        ([4, 5], 2),
-        ([4, 6], 2),
-        ([5, 5], 1),
-        ([5, 6], 2),
-        ([6, 6], 1),
-    ],
-    [
-        ([1, 1, 1], 1),
-        ([1, 1, 2], 3),
-        ([1, 1, 3], 3),
-        ([1, 1, 4], 3),
-        ([1, 1, 5], 3),
-        ([1, 1, 6], 3),
-        ([1, 2, 2], 3),
-        ([1, 2, 3], 6),
-        ([1, 2, 4], 6),
-        ([1, 2, 5], 6),
-        ([1, 2, 6], 6),
-        ([1, 3, 3], 3),
-        ([1, 3, 4], 6),
-        ([1, 3, 5], 6),
-        ([1, 3, 6], 6),
-        ([1, 4, 4], 3),
-        ([1, 4, 5], 6),
-        ([1, 4, 6], 6),
-        ([1, 5, 5], 3),
-        ([1, 5, 6], 6),
-        ([1, 6, 6], 3),
-        ([2, 2, 2], 1),
-        ([2, 2, 3], 3),
-        ([2, 2, 4], 3),
-        ([2, 2, 5], 3),
-        ([2, 2, 6], 3),
-        ([2, 3, 3], 3),
-        ([2, 3, 4], 6),
-        ([2, 3, 5], 6),
-        ([2, 3, 6], 6),
-        ([2, 4, 4], 3),
-        ([2, 4, 5], 6),
-        ([2, 4, 6], 6),
-        ([2, 5, 5], 3),
-        ([2, 5, 6], 6),
-        ([2, 6, 6], 3),
-        ([3, 3, 3], 1),
-        ([3, 3, 4], 3),
-        ([3, 3, 5], 3),
-        ([3, 3, 6], 3),
-        ([3, 4, 4], 3),
-        ([3, 4, 5], 6),
-        ([3, 4, 6], 6),
-        ([3, 5, 5], 3),
-        ([3, 5, 6], 6),
-        ([3, 6, 6], 3),
-        ([4, 4, 4], 1),
-        ([4, 4, 5], 3),
-        ([4, 4, 6], 3),
-        ([4, 5, 5], 3),
-        ([4, 5, 6], 6),
-        ([4, 6, 6], 3),
-        ([5, 5, 5], 1),
-        ([5, 5, 6], 3),
-        ([5, 6, 6], 3),
-        ([6, 6, 6], 1),
-    ],
-    [
-        ([1, 1, 1, 1], 1),
-        ([1, 1, 1, 2], 4),
-        ([1, 1, 1, 3], 4),
-        ([1, 1, 1, 4], 4),
-        ([1, 1, 1, 5], 4),
-        ([1, 1, 1, 6], 4),
-        ([1, 1, 2, 2], 6),
-        ([1, 1, 2, 3], 12),
-        ([1, 1, 2, 4], 12),
-        ([1, 1, 2, 5], 12),
-        ([1, 1, 2, 6], 12),
-        ([1, 1, 3, 3], 6),
-        ([1, 1, 3, 4], 12),
-        ([1, 1, 3, 5], 12),
-        ([1, 1, 3, 6], 12),
-        ([1, 1, 4, 4], 6),
-        ([1, 1, 4, 5], 12),
-        ([1, 1, 4, 6], 12),
-        ([1, 1, 5, 5], 6),
-        ([1, 1, 5, 6], 12),
-        ([1, 1, 6, 6], 6),
-        ([1, 2, 2, 2], 4),
-        ([1, 2, 2, 3], 12),
-        ([1, 2, 2, 4], 12),
-        ([1, 2, 2, 5], 12),
-        ([1, 2, 2, 6], 12),
-        ([1, 2, 3, 3], 12),
-        ([1, 2, 3, 4], 24),
-        ([1, 2, 3, 5], 24),
-        ([1, 2, 3, 6], 24),
-        ([1, 2, 4, 4], 12),
-        ([1, 2, 4, 5], 24),
-        ([1, 2, 4, 6], 24),
-        ([1, 2, 5, 5], 12),
-        ([1, 2, 5, 6], 24),
-        ([1, 2, 6, 6], 12),
-        ([1, 3, 3, 3], 4),
-        ([1, 3, 3, 4], 12),
-        ([1, 3, 3, 5], 12),
-        ([1, 3, 3, 6], 12),
-        ([1, 3, 4, 4], 12),
-        ([1, 3, 4, 5], 24),
-        ([1, 3, 4, 6], 24),
-        ([1, 3, 5, 5], 12),
-        ([1, 3, 5, 6], 24),
-        ([1, 3, 6, 6], 12),
-        ([1, 4, 4, 4], 4),
-        ([1, 4, 4, 5], 12),
-        ([1, 4, 4, 6], 12),
-        ([1, 4, 5, 5], 12),
-        ([1, 4, 5, 6], 24),
-        ([1, 4, 6, 6], 12),
-        ([1, 5, 5, 5], 4),
-        ([1, 5, 5, 6], 12),
-        ([1, 5, 6, 6], 12),
-        ([1, 6, 6, 6], 4),
-        ([2, 2, 2, 2], 1),
-        ([2, 2, 2, 3], 4),
-        ([2, 2, 2, 4], 4),
-        ([2, 2, 2, 5], 4),
-        ([2, 2, 2, 6], 4),
-        ([2, 2, 3, 3], 6),
-        ([2, 2, 3, 4], 12),
-        ([2, 2, 3, 5], 12),
-        ([2, 2, 3, 6], 12),
-        ([2, 2, 4, 4], 6),
-        ([2, 2, 4, 5], 12),
-        ([2, 2, 4, 6], 12),
-        ([2, 2, 5, 5], 6),
-        ([2, 2, 5, 6], 12),
-        ([2, 2, 6, 6], 6),
-        ([2, 3, 3, 3], 4),
-        ([2, 3, 3, 4], 12),
-        ([2, 3, 3, 5], 12),
-        ([2, 3, 3, 6], 12),
-        ([2, 3, 4, 4], 12),
-        ([2, 3, 4, 5], 24),
-        ([2, 3, 4, 6], 24),
-        ([2, 3, 5, 5], 12),
-        ([2, 3, 5, 6], 24),
-        ([2, 3, 6, 6], 12),
-        ([2, 4, 4, 4], 4),
-        ([2, 4, 4, 5], 12),
-        ([2, 4, 4, 6], 12),
-        ([2, 4, 5, 5], 12),
-        ([2, 4, 5, 6], 24),
-        ([2, 4, 6, 6], 12),
-        ([2, 5, 5, 5], 4),
-        ([2, 5, 5, 6], 12),
-        ([2, 5, 6, 6], 12),
-        ([2, 6, 6, 6], 4),
-        ([3, 3, 3, 3], 1),
-        ([3, 3, 3, 4], 4),
-        ([3, 3, 3, 5], 4),
-        ([3, 3, 3, 6], 4),
-        ([3, 3, 4, 4], 6),
-        ([3, 3, 4, 5], 12),
-        ([3, 3, 4, 6], 12),
-        ([3, 3, 5, 5], 6),
-        ([3, 3, 5, 6], 12),
-        ([3, 3, 6, 6], 6),
-        ([3, 4, 4, 4], 4),
-        ([3, 4, 4, 5], 12),
-        ([3, 4, 4, 6], 12),
-        ([3, 4, 5, 5], 12),
-        ([3, 4, 5, 6], 24),
-        ([3, 4, 6, 6], 12),
-        ([3, 5, 5, 5], 4),
-        ([3, 5, 5, 6], 12),
-        ([3, 5, 6, 6], 12),
-        ([3, 6, 6, 6], 4),
-        ([4, 4, 4, 4], 1),
-        ([4, 4, 4, 5], 4),
-        ([4, 4, 4, 6], 4),
-        ([4, 4, 5, 5], 6),
-        ([4, 4, 5, 6], 12),
-        ([4, 4, 6, 6], 6),
-        ([4, 5, 5, 5], 4),
-        ([4, 5, 5, 6], 12),
-        ([4, 5, 6, 6], 12),
-        ([4, 6, 6, 6], 4),
-        ([5, 5, 5, 5], 1),
-        ([5, 5, 5, 6], 4),
-        ([5, 5, 6, 6], 6),
-        ([5, 6, 6, 6], 4),
-        ([6, 6, 6, 6], 1),
-    ],
-    [
-        ([1, 1, 1, 1, 1], 1),
-        ([1, 1, 1, 1, 2], 5),
-        ([1, 1, 1, 1, 3], 5),
-        ([1, 1, 1, 1, 4], 5),
-        ([1, 1, 1, 1, 5], 5),
-        ([1, 1, 1, 1, 6], 5),
-        ([1, 1, 1, 2, 2], 10),
-        ([1, 1, 1, 2, 3], 20),
-        ([1, 1, 1, 2, 4], 20),
-        ([1, 1, 1, 2, 5], 20),
-        ([1, 1, 1, 2, 6], 20),
-        ([1, 1, 1, 3, 3], 10),
-        ([1, 1, 1, 3, 4], 20),
-        ([1, 1, 1, 3, 5], 20),
-        ([1, 1, 1, 3, 6], 20),
-        ([1, 1, 1, 4, 4], 10),
-        ([1, 1, 1, 4, 5], 20),
-        ([1, 1, 1, 4, 6], 20),
-        ([1, 1, 1, 5, 5], 10),
-        ([1, 1, 1, 5, 6], 20),
-        ([1, 1, 1, 6, 6], 10),
-        ([1, 1, 2, 2, 2], 10),
-        ([1, 1, 2, 2, 3], 30),
-        ([1, 1, 2, 2, 4], 30),
-        ([1, 1, 2, 2, 5], 30),
-        ([1, 1, 2, 2, 6], 30),
-        ([1, 1, 2, 3, 3], 30),
-        ([1, 1, 2, 3, 4], 60),
-        ([1, 1, 2, 3, 5], 60),
-        ([1, 1, 2, 3, 6], 60),
-        ([1, 1, 2, 4, 4], 30),
-        ([1, 1, 2, 4, 5], 60),
-        ([1, 1, 2, 4, 6], 60),
-        ([1, 1, 2, 5, 5], 30),
-        ([1, 1, 2, 5, 6], 60),
-        ([1, 1, 2, 6, 6], 30),
-        ([1, 1, 3, 3, 3], 10),
-        ([1, 1, 3, 3, 4], 30),
-        ([1, 1, 3, 3, 5], 30),
-        ([1, 1, 3, 3, 6], 30),
-        ([1, 1, 3, 4, 4], 30),
-        ([1, 1, 3, 4, 5], 60),
-        ([1, 1, 3, 4, 6], 60),
-        ([1, 1, 3, 5, 5], 30),
-        ([1, 1, 3, 5, 6], 60),
-        ([1, 1, 3, 6, 6], 30),
-        ([1, 1, 4, 4, 4], 10),
-        ([1, 1, 4, 4, 5], 30),
-        ([1, 1, 4, 4, 6], 30),
-        ([1, 1, 4, 5, 5], 30),
-        ([1, 1, 4, 5, 6], 60),
-        ([1, 1, 4, 6, 6], 30),
-        ([1, 1, 5, 5, 5], 10),
-        ([1, 1, 5, 5, 6], 30),
-        ([1, 1, 5, 6, 6], 30),
-        ([1, 1, 6, 6, 6], 10),
-        ([1, 2, 2, 2, 2], 5),
-        ([1, 2, 2, 2, 3], 20),
-        ([1, 2, 2, 2, 4], 20),
-        ([1, 2, 2, 2, 5], 20),
-        ([1, 2, 2, 2, 6], 20),
-        ([1, 2, 2, 3, 3], 30),
-        ([1, 2, 2, 3, 4], 60),
-        ([1, 2, 2, 3, 5], 60),
-        ([1, 2, 2, 3, 6], 60),
-        ([1, 2, 2, 4, 4], 30),
-        ([1, 2, 2, 4, 5], 60),
-        ([1, 2, 2, 4, 6], 60),
-        ([1, 2, 2, 5, 5], 30),
-        ([1, 2, 2, 5, 6], 60),
-        ([1, 2, 2, 6, 6], 30),
-        ([1, 2, 3, 3, 3], 20),
-        ([1, 2, 3, 3, 4], 60),
-        ([1, 2, 3, 3, 5], 60),
-        ([1, 2, 3, 3, 6], 60),
-        ([1, 2, 3, 4, 4], 60),
-        ([1, 2, 3, 4, 5], 120),
-        ([1, 2, 3, 4, 6], 120),
-        ([1, 2, 3, 5, 5], 60),
-        ([1, 2, 3, 5, 6], 120),
-        ([1, 2, 3, 6, 6], 60),
-        ([1, 2, 4, 4, 4], 20),
-        ([1, 2, 4, 4, 5], 60),
-        ([1, 2, 4, 4, 6], 60),
-        ([1, 2, 4, 5, 5], 60),
-        ([1, 2, 4, 5, 6], 120),
-        ([1, 2, 4, 6, 6], 60),
-        ([1, 2, 5, 5, 5], 20),
-        ([1, 2, 5, 5, 6], 60),
-        ([1, 2, 5, 6, 6], 60),
-        ([1, 2, 6, 6, 6], 20),
-        ([1, 3, 3, 3, 3], 5),
-        ([1, 3, 3, 3, 4], 20),
-        ([1, 3, 3, 3, 5], 20),
-        ([1, 3, 3, 3, 6], 20),
-        ([1, 3, 3, 4, 4], 30),
-        ([1, 3, 3, 4, 5], 60),
-        ([1, 3, 3, 4, 6], 60),
-        ([1, 3, 3, 5, 5], 30),
-        ([1, 3, 3, 5, 6], 60),
-        ([1, 3, 3, 6, 6], 30),
-        ([1, 3, 4, 4, 4], 20),
-        ([1, 3, 4, 4, 5], 60),
-        ([1, 3, 4, 4, 6], 60),
-        ([1, 3, 4, 5, 5], 60),
-        ([1, 3, 4, 5, 6], 120),
-        ([1, 3, 4, 6, 6], 60),
-        ([1, 3, 5, 5, 5], 20),
-        ([1, 3, 5, 5, 6], 60),
-        ([1, 3, 5, 6, 6], 60),
-        ([1, 3, 6, 6, 6], 20),
-        ([1, 4, 4, 4, 4], 5),
-        ([1, 4, 4, 4, 5], 20),
-        ([1, 4, 4, 4, 6], 20),
-        ([1, 4, 4, 5, 5], 30),
-        ([1, 4, 4, 5, 6], 60),
-        ([1, 4, 4, 6, 6], 30),
-        ([1, 4, 5, 5, 5], 20),
-        ([1, 4, 5, 5, 6], 60),
-        ([1, 4, 5, 6, 6], 60),
-        ([1, 4, 6, 6, 6], 20),
-        ([1, 5, 5, 5, 5], 5),
-        ([1, 5, 5, 5, 6], 20),
-        ([1, 5, 5, 6, 6], 30),
-        ([1, 5, 6, 6, 6], 20),
-        ([1, 6, 6, 6, 6], 5),
-        ([2, 2, 2, 2, 2], 1),
-        ([2, 2, 2, 2, 3], 5),
-        ([2, 2, 2, 2, 4], 5),
-        ([2, 2, 2, 2, 5], 5),
-        ([2, 2, 2, 2, 6], 5),
-        ([2, 2, 2, 3, 3], 10),
-        ([2, 2, 2, 3, 4], 20),
-        ([2, 2, 2, 3, 5], 20),
-        ([2, 2, 2, 3, 6], 20),
-        ([2, 2, 2, 4, 4], 10),
-        ([2, 2, 2, 4, 5], 20),
-        ([2, 2, 2, 4, 6], 20),
-        ([2, 2, 2, 5, 5], 10),
-        ([2, 2, 2, 5, 6], 20),
-        ([2, 2, 2, 6, 6], 10),
-        ([2, 2, 3, 3, 3], 10),
-        ([2, 2, 3, 3, 4], 30),
-        ([2, 2, 3, 3, 5], 30),
-        ([2, 2, 3, 3, 6], 30),
-        ([2, 2, 3, 4, 4], 30),
-        ([2, 2, 3, 4, 5], 60),
-        ([2, 2, 3, 4, 6], 60),
-        ([2, 2, 3, 5, 5], 30),
-        ([2, 2, 3, 5, 6], 60),
-        ([2, 2, 3, 6, 6], 30),
-        ([2, 2, 4, 4, 4], 10),
-        ([2, 2, 4, 4, 5], 30),
-        ([2, 2, 4, 4, 6], 30),
-        ([2, 2, 4, 5, 5], 30),
-        ([2, 2, 4, 5, 6], 60),
-        ([2, 2, 4, 6, 6], 30),
-        ([2, 2, 5, 5, 5], 10),
-        ([2, 2, 5, 5, 6], 30),
-        ([2, 2, 5, 6, 6], 30),
-        ([2, 2, 6, 6, 6], 10),
-        ([2, 3, 3, 3, 3], 5),
-        ([2, 3, 3, 3, 4], 20),
-        ([2, 3, 3, 3, 5], 20),
-        ([2, 3, 3, 3, 6], 20),
-        ([2, 3, 3, 4, 4], 30),
-        ([2, 3, 3, 4, 5], 60),
-        ([2, 3, 3, 4, 6], 60),
-        ([2, 3, 3, 5, 5], 30),
-        ([2, 3, 3, 5, 6], 60),
-        ([2, 3, 3, 6, 6], 30),
-        ([2, 3, 4, 4, 4], 20),
-        ([2, 3, 4, 4, 5], 60),
-        ([2, 3, 4, 4, 6], 60),
-        ([2, 3, 4, 5, 5], 60),
-        ([2, 3, 4, 5, 6], 120),
-        ([2, 3, 4, 6, 6], 60),
-        ([2, 3, 5, 5, 5], 20),
-        ([2, 3, 5, 5, 6], 60),
-        ([2, 3, 5, 6, 6], 60),
-        ([2, 3, 6, 6, 6], 20),
-        ([2, 4, 4, 4, 4], 5),
-        ([2, 4, 4, 4, 5], 20),
-        ([2, 4, 4, 4, 6], 20),
-        ([2, 4, 4, 5, 5], 30),
-        ([2, 4, 4, 5, 6], 60),
-        ([2, 4, 4, 6, 6], 30),
-        ([2, 4, 5, 5, 5], 20),
-        ([2, 4, 5, 5, 6], 60),
-        ([2, 4, 5, 6, 6], 60),
-        ([2, 4, 6, 6, 6], 20),
-        ([2, 5, 5, 5, 5], 5),
-        ([2, 5, 5, 5, 6], 20),
-        ([2, 5, 5, 6, 6], 30),
-        ([2, 5, 6, 6, 6], 20),
-        ([2, 6, 6, 6, 6], 5),
-        ([3, 3, 3, 3, 3], 1),
-        ([3, 3, 3, 3, 4], 5),
-        ([3, 3, 3, 3, 5], 5),
-        ([3, 3, 3, 3, 6], 5),
-        ([3, 3, 3, 4, 4], 10),
-        ([3, 3, 3, 4, 5], 20),
-        ([3, 3, 3, 4, 6], 20),
-        ([3, 3, 3, 5, 5], 10),
-        ([3, 3, 3, 5, 6], 20),
-        ([3, 3, 3, 6, 6], 10),
-        ([3, 3, 4, 4, 4], 10),
-        ([3, 3, 4, 4, 5], 30),
-        ([3, 3, 4, 4, 6], 30),
-        ([3, 3, 4, 5, 5], 30),
-        ([3, 3, 4, 5, 6], 60),
-        ([3, 3, 4, 6, 6], 30),
-        ([3, 3, 5, 5, 5], 10),
-        ([3, 3, 5, 5, 6], 30),
-        ([3, 3, 5, 6, 6], 30),
-        ([3, 3, 6, 6, 6], 10),
-        ([3, 4, 4, 4, 4], 5),
-        ([3, 4, 4, 4, 5], 20),
-        ([3, 4, 4, 4, 6], 20),
-        ([3, 4, 4, 5, 5], 30),
-        ([3, 4, 4, 5, 6], 60),
-        ([3, 4, 4, 6, 6], 30),
-        ([3, 4, 5, 5, 5], 20),
-        ([3, 4, 5, 5, 6], 60),
-        ([3, 4, 5, 6, 6], 60),
-        ([3, 4, 6, 6, 6], 20),
-        ([3, 5, 5, 5, 5], 5),
-        ([3, 5, 5, 5, 6], 20),
-        ([3, 5, 5, 6, 6], 30),
-        ([3, 5, 6, 6, 6], 20),
-        ([3, 6, 6, 6, 6], 5),
-        ([4, 4, 4, 4, 4], 1),
-        ([4, 4, 4, 4, 5], 5),
-        ([4, 4, 4, 4, 6], 5),
-        ([4, 4, 4, 5, 5], 10),
-        ([4, 4, 4, 5, 6], 20),
-        ([4, 4, 4, 6, 6], 10),
-        ([4, 4, 5, 5, 5], 10),
-        ([4, 4, 5, 5, 6], 30),
-        ([4, 4, 5, 6, 6], 30),
-        ([4, 4, 6, 6, 6], 10),
-        ([4, 5, 5, 5, 5], 5),
-        ([4, 5, 5, 5, 6], 20),
-        ([4, 5, 5, 6, 6], 30),
-        ([4, 5, 6, 6, 6], 20),
-        ([4, 6, 6, 6, 6], 5),
-        ([5, 5, 5, 5, 5], 1),
-        ([5, 5, 5, 5, 6], 5),
-        ([5, 5, 5, 6, 6], 10),
-        ([5, 5, 6, 6, 6], 10),
-        ([5, 6, 6, 6, 6], 5),
-        ([6, 6, 6, 6, 6], 1),
-    ],
-    [
-        ([1, 1, 1, 1, 1, 1], 1),
-        ([1, 1, 1, 1, 1, 2], 6),
-        ([1, 1, 1, 1, 1, 3], 6),
-        ([1, 1, 1, 1, 1, 4], 6),
-        ([1, 1, 1, 1, 1, 5], 6),
-        ([1, 1, 1, 1, 1, 6], 6),
-        ([1, 1, 1, 1, 2, 2], 15),
-        ([1, 1, 1, 1, 2, 3], 30),
-        ([1, 1, 1, 1, 2, 4], 30),
-        ([1, 1, 1, 1, 2, 5], 30),
-        ([1, 1, 1, 1, 2, 6], 30),
-        ([1, 1, 1, 1, 3, 3], 15),
-        ([1, 1, 1, 1, 3, 4], 30),
-        ([1, 1, 1, 1, 3, 5], 30),
-        ([1, 1, 1, 1, 3, 6], 30),
-        ([1, 1, 1, 1, 4, 4], 15),
-        ([1, 1, 1, 1, 4, 5], 30),
-        ([1, 1, 1, 1, 4, 6], 30),
-        ([1, 1, 1, 1, 5, 5], 15),
-        ([1, 1, 1, 1, 5, 6], 30),
-        ([1, 1, 1, 1, 6, 6], 15),
-        ([1, 1, 1, 2, 2, 2], 20),
-        ([1, 1, 1, 2, 2, 3], 60),
-        ([1, 1, 1, 2, 2, 4], 60),
-        ([1, 1, 1, 2, 2, 5], 60),
-        ([1, 1, 1, 2, 2, 6], 60),
-        ([1, 1, 1, 2, 3, 3], 60),
-        ([1, 1, 1, 2, 3, 4], 120),
-        ([1, 1, 1, 2, 3, 5], 120),
-        ([1, 1, 1, 2, 3, 6], 120),
-        ([1, 1, 1, 2, 4, 4], 60),
-        ([1, 1, 1, 2, 4, 5], 120),
-        ([1, 1, 1, 2, 4, 6], 120),
-        ([1, 1, 1, 2, 5, 5], 60),
-        ([1, 1, 1, 2, 5, 6], 120),
-        ([1, 1, 1, 2, 6, 6], 60),
-        ([1, 1, 1, 3, 3, 3], 20),
-        ([1, 1, 1, 3, 3, 4], 60),
-        ([1, 1, 1, 3, 3, 5], 60),
-        ([1, 1, 1, 3, 3, 6], 60),
-        ([1, 1, 1, 3, 4, 4], 60),
-        ([1, 1, 1, 3, 4, 5], 120),
-        ([1, 1, 1, 3, 4, 6], 120),
-        ([1, 1, 1, 3, 5, 5], 60),
-        ([1, 1, 1, 3, 5, 6], 120),
-        ([1, 1, 1, 3, 6, 6], 60),
-        ([1, 1, 1, 4, 4, 4], 20),
-        ([1, 1, 1, 4, 4, 5], 60),
-        ([1, 1, 1, 4, 4, 6], 60),
-        ([1, 1, 1, 4, 5, 5], 60),
-        ([1, 1, 1, 4, 5, 6], 120),
-        ([1, 1, 1, 4, 6, 6], 60),
-        ([1, 1, 1, 5, 5, 5], 20),
-        ([1, 1, 1, 5, 5, 6], 60),
-        ([1, 1, 1, 5, 6, 6], 60),
-        ([1, 1, 1, 6, 6, 6], 20),
-        ([1, 1, 2, 2, 2, 2], 15),
-        ([1, 1, 2, 2, 2, 3], 60),
-        ([1, 1, 2, 2, 2, 4], 60),
-        ([1, 1, 2, 2, 2, 5], 60),
-        ([1, 1, 2, 2, 2, 6], 60),
-        ([1, 1, 2, 2, 3, 3], 90),
-        ([1, 1, 2, 2, 3, 4], 180),
-        ([1, 1, 2, 2, 3, 5], 180),
-        ([1, 1, 2, 2, 3, 6], 180),
-        ([1, 1, 2, 2, 4, 4], 90),
-        ([1, 1, 2, 2, 4, 5], 180),
-        ([1, 1, 2, 2, 4, 6], 180),
-        ([1, 1, 2, 2, 5, 5], 90),
-        ([1, 1, 2, 2, 5, 6], 180),
-        ([1, 1, 2, 2, 6, 6], 90),
-        ([1, 1, 2, 3, 3, 3], 60),
-        ([1, 1, 2, 3, 3, 4], 180),
-        ([1, 1, 2, 3, 3, 5], 180),
-        ([1, 1, 2, 3, 3, 6], 180),
-        ([1, 1, 2, 3, 4, 4], 180),
-        ([1, 1, 2, 3, 4, 5], 360),
-        ([1, 1, 2, 3, 4, 6], 360),
-        ([1, 1, 2, 3, 5, 5], 180),
-        ([1, 1, 2, 3, 5, 6], 360),
-        ([1, 1, 2, 3, 6, 6], 180),
-        ([1, 1, 2, 4, 4, 4], 60),
-        ([1, 1, 2, 4, 4, 5], 180),
-        ([1, 1, 2, 4, 4, 6], 180),
-        ([1, 1, 2, 4, 5, 5], 180),
-        ([1, 1, 2, 4, 5, 6], 360),
-        ([1, 1, 2, 4, 6, 6], 180),
-        ([1, 1, 2, 5, 5, 5], 60),
-        ([1, 1, 2, 5, 5, 6], 180),
-        ([1, 1, 2, 5, 6, 6], 180),
-        ([1, 1, 2, 6, 6, 6], 60),
-        ([1, 1, 3, 3, 3, 3], 15),
-        ([1, 1, 3, 3, 3, 4], 60),
-        ([1, 1, 3, 3, 3, 5], 60),
-        ([1, 1, 3, 3, 3, 6], 60),
-        ([1, 1, 3, 3, 4, 4], 90),
-        ([1, 1, 3, 3, 4, 5], 180),
-        ([1, 1, 3, 3, 4, 6], 180),
-        ([1, 1, 3, 3, 5, 5], 90),
-        ([1, 1, 3, 3, 5, 6], 180),
-        ([1, 1, 3, 3, 6, 6], 90),
-        ([1, 1, 3, 4, 4, 4], 60),
-        ([1, 1, 3, 4, 4, 5], 180),
-        ([1, 1, 3, 4, 4, 6], 180),
-        ([1, 1, 3, 4, 5, 5], 180),
-        ([1, 1, 3, 4, 5, 6], 360),
-        ([1, 1, 3, 4, 6, 6], 180),
-        ([1, 1, 3, 5, 5, 5], 60),
-        ([1, 1, 3, 5, 5, 6], 180),
-        ([1, 1, 3, 5, 6, 6], 180),
-        ([1, 1, 3, 6, 6, 6], 60),
-        ([1, 1, 4, 4, 4, 4], 15),
-        ([1, 1, 4, 4, 4, 5], 60),
-        ([1, 1, 4, 4, 4, 6], 60),
-        ([1, 1, 4, 4, 5, 5], 90),
-        ([1, 1, 4, 4, 5, 6], 180),
-        ([1, 1, 4, 4, 6, 6], 90),
-        ([1, 1, 4, 5, 5, 5], 60),
-        ([1, 1, 4, 5, 5, 6], 180),
-        ([1, 1, 4, 5, 6, 6], 180),
-        ([1, 1, 4, 6, 6, 6], 60),
-        ([1, 1, 5, 5, 5, 5], 15),
-        ([1, 1, 5, 5, 5, 6], 60),
-        ([1, 1, 5, 5, 6, 6], 90),
-        ([1, 1, 5, 6, 6, 6], 60),
-        ([1, 1, 6, 6, 6, 6], 15),
-        ([1, 2, 2, 2, 2, 2], 6),
-        ([1, 2, 2, 2, 2, 3], 30),
-        ([1, 2, 2, 2, 2, 4], 30),
-        ([1, 2, 2, 2, 2, 5], 30),
-        ([1, 2, 2, 2, 2, 6], 30),
-        ([1, 2, 2, 2, 3, 3], 60),
-        ([1, 2, 2, 2, 3, 4], 120),
-        ([1, 2, 2, 2, 3, 5], 120),
-        ([1, 2, 2, 2, 3, 6], 120),
-        ([1, 2, 2, 2, 4, 4], 60),
-        ([1, 2, 2, 2, 4, 5], 120),
-        ([1, 2, 2, 2, 4, 6], 120),
-        ([1, 2, 2, 2, 5, 5], 60),
-        ([1, 2, 2, 2, 5, 6], 120),
-        ([1, 2, 2, 2, 6, 6], 60),
-        ([1, 2, 2, 3, 3, 3], 60),
-        ([1, 2, 2, 3, 3, 4], 180),
-        ([1, 2, 2, 3, 3, 5], 180),
-        ([1, 2, 2, 3, 3, 6], 180),
-        ([1, 2, 2, 3, 4, 4], 180),
-        ([1, 2, 2, 3, 4, 5], 360),
-        ([1, 2, 2, 3, 4, 6], 360),
-        ([1, 2, 2, 3, 5, 5], 180),
-        ([1, 2, 2, 3, 5, 6], 360),
-        ([1, 2, 2, 3, 6, 6], 180),
-        ([1, 2, 2, 4, 4, 4], 60),
-        ([1, 2, 2, 4, 4, 5], 180),
-        ([1, 2, 2, 4, 4, 6], 180),
-        ([1, 2, 2, 4, 5, 5], 180),
-        ([1, 2, 2, 4, 5, 6], 360),
-        ([1, 2, 2, 4, 6, 6], 180),
-        ([1, 2, 2, 5, 5, 5], 60),
-        ([1, 2, 2, 5, 5, 6], 180),
-        ([1, 2, 2, 5, 6, 6], 180),
-        ([1, 2, 2, 6, 6, 6], 60),
-        ([1, 2, 3, 3, 3, 3], 30),
-        ([1, 2, 3, 3, 3, 4], 120),
-        ([1, 2, 3, 3, 3, 5], 120),
-        ([1, 2, 3, 3, 3, 6], 120),
-        ([1, 2, 3, 3, 4, 4], 180),
-        ([1, 2, 3, 3, 4, 5], 360),
-        ([1, 2, 3, 3, 4, 6], 360),
-        ([1, 2, 3, 3, 5, 5], 180),
-        ([1, 2, 3, 3, 5, 6], 360),
-        ([1, 2, 3, 3, 6, 6], 180),
-        ([1, 2, 3, 4, 4, 4], 120),
-        ([1, 2, 3, 4, 4, 5], 360),
-        ([1, 2, 3, 4, 4, 6], 360),
-        ([1, 2, 3, 4, 5, 5], 360),
-        ([1, 2, 3, 4, 5, 6], 720),
-        ([1, 2, 3, 4, 6, 6], 360),
-        ([1, 2, 3, 5, 5, 5], 120),
-        ([1, 2, 3, 5, 5, 6], 360),
-        ([1, 2, 3, 5, 6, 6], 360),
-        ([1, 2, 3, 6, 6, 6], 120),
-        ([1, 2, 4, 4, 4, 4], 30),
-        ([1, 2, 4, 4, 4, 5], 120),
-        ([1, 2, 4, 4, 4, 6], 120),
-        ([1, 2, 4, 4, 5, 5], 180),
-        ([1, 2, 4, 4, 5, 6], 360),
-        ([1, 2, 4, 4, 6, 6], 180),
-        ([1, 2, 4, 5, 5, 5], 120),
-        ([1, 2, 4, 5, 5, 6], 360),
-        ([1, 2, 4, 5, 6, 6], 360),
-        ([1, 2, 4, 6, 6, 6], 120),
-        ([1, 2, 5, 5, 5, 5], 30),
-        ([1, 2, 5, 5, 5, 6], 120),
-        ([1, 2, 5, 5, 6, 6], 180),
-        ([1, 2, 5, 6, 6, 6], 120),
-        ([1, 2, 6, 6, 6, 6], 30),
-        ([1, 3, 3, 3, 3, 3], 6),
-        ([1, 3, 3, 3, 3, 4], 30),
-        ([1, 3, 3, 3, 3, 5], 30),
-        ([1, 3, 3, 3, 3, 6], 30),
-        ([1, 3, 3, 3, 4, 4], 60),
-        ([1, 3, 3, 3, 4, 5], 120),
-        ([1, 3, 3, 3, 4, 6], 120),
-        ([1, 3, 3, 3, 5, 5], 60),
-        ([1, 3, 3, 3, 5, 6], 120),
-        ([1, 3, 3, 3, 6, 6], 60),
-        ([1, 3, 3, 4, 4, 4], 60),
-        ([1, 3, 3, 4, 4, 5], 180),
-        ([1, 3, 3, 4, 4, 6], 180),
-        ([1, 3, 3, 4, 5, 5], 180),
-        ([1, 3, 3, 4, 5, 6], 360),
-        ([1, 3, 3, 4, 6, 6], 180),
-        ([1, 3, 3, 5, 5, 5], 60),
-        ([1, 3, 3, 5, 5, 6], 180),
-        ([1, 3, 3, 5, 6, 6], 180),
-        ([1, 3, 3, 6, 6, 6], 60),
-        ([1, 3, 4, 4, 4, 4], 30),
-        ([1, 3, 4, 4, 4, 5], 120),
-        ([1, 3, 4, 4, 4, 6], 120),
-        ([1, 3, 4, 4, 5, 5], 180),
-        ([1, 3, 4, 4, 5, 6], 360),
-        ([1, 3, 4, 4, 6, 6], 180),
-        ([1, 3, 4, 5, 5, 5], 120),
-        ([1, 3, 4, 5, 5, 6], 360),
-        ([1, 3, 4, 5, 6, 6], 360),
-        ([1, 3, 4, 6, 6, 6], 120),
-        ([1, 3, 5, 5, 5, 5], 30),
-        ([1, 3, 5, 5, 5, 6], 120),
-        ([1, 3, 5, 5, 6, 6], 180),
-        ([1, 3, 5, 6, 6, 6], 120),
-        ([1, 3, 6, 6, 6, 6], 30),
-        ([1, 4, 4, 4, 4, 4], 6),
-        ([1, 4, 4, 4, 4, 5], 30),
-        ([1, 4, 4, 4, 4, 6], 30),
-        ([1, 4, 4, 4, 5, 5], 60),
-        ([1, 4, 4, 4, 5, 6], 120),
-        ([1, 4, 4, 4, 6, 6], 60),
-        ([1, 4, 4, 5, 5, 5], 60),
-        ([1, 4, 4, 5, 5, 6], 180),
-        ([1, 4, 4, 5, 6, 6], 180),
-        ([1, 4, 4, 6, 6, 6], 60),
-        ([1, 4, 5, 5, 5, 5], 30),
-        ([1, 4, 5, 5, 5, 6], 120),
-        ([1, 4, 5, 5, 6, 6], 180),
-        ([1, 4, 5, 6, 6, 6], 120),
-        ([1, 4, 6, 6, 6, 6], 30),
-        ([1, 5, 5, 5, 5, 5], 6),
-        ([1, 5, 5, 5, 5, 6], 30),
-        ([1, 5, 5, 5, 6, 6], 60),
-        ([1, 5, 5, 6, 6, 6], 60),
-        ([1, 5, 6, 6, 6, 6], 30),
-        ([1, 6, 6, 6, 6, 6], 6),
-        ([2, 2, 2, 2, 2, 2], 1),
-        ([2, 2, 2, 2, 2, 3], 6),
-        ([2, 2, 2, 2, 2, 4], 6),
-        ([2, 2, 2, 2, 2, 5], 6),
-        ([2, 2, 2, 2, 2, 6], 6),
-        ([2, 2, 2, 2, 3, 3], 15),
-        ([2, 2, 2, 2, 3, 4], 30),
-        ([2, 2, 2, 2, 3, 5], 30),
-        ([2, 2, 2, 2, 3, 6], 30),
-        ([2, 2, 2, 2, 4, 4], 15),
-        ([2, 2, 2, 2, 4, 5], 30),
-        ([2, 2, 2, 2, 4, 6], 30),
-        ([2, 2, 2, 2, 5, 5], 15),
-        ([2, 2, 2, 2, 5, 6], 30),
-        ([2, 2, 2, 2, 6, 6], 15),
-        ([2, 2, 2, 3, 3, 3], 20),
-        ([2, 2, 2, 3, 3, 4], 60),
-        ([2, 2, 2, 3, 3, 5], 60),
-        ([2, 2, 2, 3, 3, 6], 60),
-        ([2, 2, 2, 3, 4, 4], 60),
-        ([2, 2, 2, 3, 4, 5], 120),
-        ([2, 2, 2, 3, 4, 6], 120),
-        ([2, 2, 2, 3, 5, 5], 60),
-        ([2, 2, 2, 3, 5, 6], 120),
-        ([2, 2, 2, 3, 6, 6], 60),
-        ([2, 2, 2, 4, 4, 4], 20),
-        ([2, 2, 2, 4, 4, 5], 60),
-        ([2, 2, 2, 4, 4, 6], 60),
-        ([2, 2, 2, 4, 5, 5], 60),
-        ([2, 2, 2, 4, 5, 6], 120),
-        ([2, 2, 2, 4, 6, 6], 60),
-        ([2, 2, 2, 5, 5, 5], 20),
-        ([2, 2, 2, 5, 5, 6], 60),
-        ([2, 2, 2, 5, 6, 6], 60),
-        ([2, 2, 2, 6, 6, 6], 20),
-        ([2, 2, 3, 3, 3, 3], 15),
-        ([2, 2, 3, 3, 3, 4], 60),
-        ([2, 2, 3, 3, 3, 5], 60),
-        ([2, 2, 3, 3, 3, 6], 60),
-        ([2, 2, 3, 3, 4, 4], 90),
-        ([2, 2, 3, 3, 4, 5], 180),
-        ([2, 2, 3, 3, 4, 6], 180),
-        ([2, 2, 3, 3, 5, 5], 90),
-        ([2, 2, 3, 3, 5, 6], 180),
-        ([2, 2, 3, 3, 6, 6], 90),
-        ([2, 2, 3, 4, 4, 4], 60),
-        ([2, 2, 3, 4, 4, 5], 180),
-        ([2, 2, 3, 4, 4, 6], 180),
-        ([2, 2, 3, 4, 5, 5], 180),
-        ([2, 2, 3, 4, 5, 6], 360),
-        ([2, 2, 3, 4, 6, 6], 180),
-        ([2, 2, 3, 5, 5, 5], 60),
-        ([2, 2, 3, 5, 5, 6], 180),
-        ([2, 2, 3, 5, 6, 6], 180),
-        ([2, 2, 3, 6, 6, 6], 60),
-        ([2, 2, 4, 4, 4, 4], 15),
-        ([2, 2, 4, 4, 4, 5], 60),
-        ([2, 2, 4, 4, 4, 6], 60),
-        ([2, 2, 4, 4, 5, 5], 90),
-        ([2, 2, 4, 4, 5, 6], 180),
-        ([2, 2, 4, 4, 6, 6], 90),
-        ([2, 2, 4, 5, 5, 5], 60),
-        ([2, 2, 4, 5, 5, 6], 180),
-        ([2, 2, 4, 5, 6, 6], 180),
-        ([2, 2, 4, 6, 6, 6], 60),
-        ([2, 2, 5, 5, 5, 5], 15),
-        ([2, 2, 5, 5, 5, 6], 60),
-        ([2, 2, 5, 5, 6, 6], 90),
-        ([2, 2, 5, 6, 6, 6], 60),
-        ([2, 2, 6, 6, 6, 6], 15),
-        ([2, 3, 3, 3, 3, 3], 6),
-        ([2, 3, 3, 3, 3, 4], 30),
-        ([2, 3, 3, 3, 3, 5], 30),
-        ([2, 3, 3, 3, 3, 6], 30),
-        ([2, 3, 3, 3, 4, 4], 60),
-        ([2, 3, 3, 3, 4, 5], 120),
-        ([2, 3, 3, 3, 4, 6], 120),
-        ([2, 3, 3, 3, 5, 5], 60),
-        ([2, 3, 3, 3, 5, 6], 120),
-        ([2, 3, 3, 3, 6, 6], 60),
-        ([2, 3, 3, 4, 4, 4], 60),
-        ([2, 3, 3, 4, 4, 5], 180),
-        ([2, 3, 3, 4, 4, 6], 180),
-        ([2, 3, 3, 4, 5, 5], 180),
-        ([2, 3, 3, 4, 5, 6], 360),
-        ([2, 3, 3, 4, 6, 6], 180),
-        ([2, 3, 3, 5, 5, 5], 60),
-        ([2, 3, 3, 5, 5, 6], 180),
-        ([2, 3, 3, 5, 6, 6], 180),
-        ([2, 3, 3, 6, 6, 6], 60),
-        ([2, 3, 4, 4, 4, 4], 30),
-        ([2, 3, 4, 4, 4, 5], 120),
-        ([2, 3, 4, 4, 4, 6], 120),
-        ([2, 3, 4, 4, 5, 5], 180),
-        ([2, 3, 4, 4, 5, 6], 360),
-        ([2, 3, 4, 4, 6, 6], 180),
-        ([2, 3, 4, 5, 5, 5], 120),
-        ([2, 3, 4, 5, 5, 6], 360),
-        ([2, 3, 4, 5, 6, 6], 360),
-        ([2, 3, 4, 6, 6, 6], 120),
-        ([2, 3, 5, 5, 5, 5], 30),
-        ([2, 3, 5, 5, 5, 6], 120),
-        ([2, 3, 5, 5, 6, 6], 180),
-        ([2, 3, 5, 6, 6, 6], 120),
-        ([2, 3, 6, 6, 6, 6], 30),
-        ([2, 4, 4, 4, 4, 4], 6),
-        ([2, 4, 4, 4, 4, 5], 30),
-        ([2, 4, 4, 4, 4, 6], 30),
-        ([2, 4, 4, 4, 5, 5], 60),
-        ([2, 4, 4, 4, 5, 6], 120),
-        ([2, 4, 4, 4, 6, 6], 60),
-        ([2, 4, 4, 5, 5, 5], 60),
-        ([2, 4, 4, 5, 5, 6], 180),
-        ([2, 4, 4, 5, 6, 6], 180),
-        ([2, 4, 4, 6, 6, 6], 60),
-        ([2, 4, 5, 5, 5, 5], 30),
-        ([2, 4, 5, 5, 5, 6], 120),
-        ([2, 4, 5, 5, 6, 6], 180),
-        ([2, 4, 5, 6, 6, 6], 120),
-        ([2, 4, 6, 6, 6, 6], 30),
-        ([2, 5, 5, 5, 5, 5], 6),
-        ([2, 5, 5, 5, 5, 6], 30),
-        ([2, 5, 5, 5, 6, 6], 60),
-        ([2, 5, 5, 6, 6, 6], 60),
-        ([2, 5, 6, 6, 6, 6], 30),
-        ([2, 6, 6, 6, 6, 6], 6),
-        ([3, 3, 3, 3, 3, 3], 1),
-        ([3, 3, 3, 3, 3, 4], 6),
-        ([3, 3, 3, 3, 3, 5], 6),
-        ([3, 3, 3, 3, 3, 6], 6),
-        ([3, 3, 3, 3, 4, 4], 15),
-        ([3, 3, 3, 3, 4, 5], 30),
-        ([3, 3, 3, 3, 4, 6], 30),
-        ([3, 3, 3, 3, 5, 5], 15),
-        ([3, 3, 3, 3, 5, 6], 30),
-        ([3, 3, 3, 3, 6, 6], 15),
-        ([3, 3, 3, 4, 4, 4], 20),
-        ([3, 3, 3, 4, 4, 5], 60),
-        ([3, 3, 3, 4, 4, 6], 60),
-        ([3, 3, 3, 4, 5, 5], 60),
-        ([3, 3, 3, 4, 5, 6], 120),
-        ([3, 3, 3, 4, 6, 6], 60),
-        ([3, 3, 3, 5, 5, 5], 20),
-        ([3, 3, 3, 5, 5, 6], 60),
-        ([3, 3, 3, 5, 6, 6], 60),
-        ([3, 3, 3, 6, 6, 6], 20),
-        ([3, 3, 4, 4, 4, 4], 15),
-        ([3, 3, 4, 4, 4, 5], 60),
-        ([3, 3, 4, 4, 4, 6], 60),
-        ([3, 3, 4, 4, 5, 5], 90),
-        ([3, 3, 4, 4, 5, 6], 180),
-        ([3, 3, 4, 4, 6, 6], 90),
-        ([3, 3, 4, 5, 5, 5], 60),
-        ([3, 3, 4, 5, 5, 6], 180),
-        ([3, 3, 4, 5, 6, 6], 180),
-        ([3, 3, 4, 6, 6, 6], 60),
-        ([3, 3, 5, 5, 5, 5], 15),
-        ([3, 3, 5, 5, 5, 6], 60),
-        ([3, 3, 5, 5, 6, 6], 90),
-        ([3, 3, 5, 6, 6, 6], 60),
-        ([3, 3, 6, 6, 6, 6], 15),
-        ([3, 4, 4, 4, 4, 4], 6),
-        ([3, 4, 4, 4, 4, 5], 30),
-        ([3, 4, 4, 4, 4, 6], 30),
-        ([3, 4, 4, 4, 5, 5], 60),
-        ([3, 4, 4, 4, 5, 6], 120),
-        ([3, 4, 4, 4, 6, 6], 60),
-        ([3, 4, 4, 5, 5, 5], 60),
-        ([3, 4, 4, 5, 5, 6], 180),
-        ([3, 4, 4, 5, 6, 6], 180),
-        ([3, 4, 4, 6, 6, 6], 60),
-        ([3, 4, 5, 5, 5, 5], 30),
-        ([3, 4, 5, 5, 5, 6], 120),
-        ([3, 4, 5, 5, 6, 6], 180),
-        ([3, 4, 5, 6, 6, 6], 120),
-        ([3, 4, 6, 6, 6, 6], 30),
-        ([3, 5, 5, 5, 5, 5], 6),
-        ([3, 5, 5, 5, 5, 6], 30),
-        ([3, 5, 5, 5, 6, 6], 60),
-        ([3, 5, 5, 6, 6, 6], 60),
-        ([3, 5, 6, 6, 6, 6], 30),
-        ([3, 6, 6, 6, 6, 6], 6),
-        ([4, 4, 4, 4, 4, 4], 1),
-        ([4, 4, 4, 4, 4, 5], 6),
-        ([4, 4, 4, 4, 4, 6], 6),
-        ([4, 4, 4, 4, 5, 5], 15),
-        ([4, 4, 4, 4, 5, 6], 30),
-        ([4, 4, 4, 4, 6, 6], 15),
-        ([4, 4, 4, 5, 5, 5], 20),
-        ([4, 4, 4, 5, 5, 6], 60),
-        ([4, 4, 4, 5, 6, 6], 60),
-        ([4, 4, 4, 6, 6, 6], 20),
-        ([4, 4, 5, 5, 5, 5], 15),
-        ([4, 4, 5, 5, 5, 6], 60),
-        ([4, 4, 5, 5, 6, 6], 90),
-        ([4, 4, 5, 6, 6, 6], 60),
-        ([4, 4, 6, 6, 6, 6], 15),
-        ([4, 5, 5, 5, 5, 5], 6),
-        ([4, 5, 5, 5, 5, 6], 30),
-        ([4, 5, 5, 5, 6, 6], 60),
-        ([4, 5, 5, 6, 6, 6], 60),
-        ([4, 5, 6, 6, 6, 6], 30),
-        ([4, 6, 6, 6, 6, 6], 6),
-        ([5, 5, 5, 5, 5, 5], 1),
-        ([5, 5, 5, 5, 5, 6], 6),
-        ([5, 5, 5, 5, 6, 6], 15),
-        ([5, 5, 5, 6, 6, 6], 20),
-        ([5, 5, 6, 6, 6, 6], 15),
-        ([5, 6, 6, 6, 6, 6], 6),
-        ([6, 6, 6, 6, 6, 6], 1),
-    ],
+    dice_distr(),
+    dice_distr(),
+    dice_distr(),
+    dice_distr(),
+    dice_distr(),
+    dice_distr(),
+    dice_distr(),
+    dice_distr(),
 );
 
-pub const DICE_DIVISOR: [u32; 7] = [1, 6, 36, 216, 1296, 7776, 46656];
+const fn binomial(n: usize, k: usize) -> usize {
+    let mut res = 1;
+    let mut i = 0;
 
-type DiceOrderMapType = (
-    (),
-    HashMap<[u8; 1], usize>,
-    HashMap<[u8; 2], usize>,
-    HashMap<[u8; 3], usize>,
-    HashMap<[u8; 4], usize>,
-    HashMap<[u8; 5], usize>,
-    HashMap<[u8; 6], usize>,
-);
+    while i < k {
+        res = res * (n - i) / (i + 1);
+        i += 1;
+    }
+
+    res
+}
+
+// The amount of sorted throws with `left` dice after the first that come
+// before the ones starting with `face`. Since the dice after the first are at
+// least as large, each smaller first die f skips binomial(left + 6 - f, left)
+// throws. Adding up the throws skipped at each die gives the index of a throw
+// without any lookups, see `DiceThrow::get_index`.
+pub const DICE_RANK_OFFSETS: [[usize; 7]; MAX_DICE] = {
+    let mut offsets = [[0; 7]; MAX_DICE];
+    let mut left = 0;
+
+    while left < MAX_DICE {
+        let mut face = 2;
+
+        while face <= 6 {
+            offsets[left][face] =
+                offsets[left][face - 1] + binomial(left + 7 - face, left);
+            face += 1;
+        }
 
-pub static DICE_ORDER_MAP: Lazy<DiceOrderMapType> = Lazy::new(|| {
-    fn dice_distr_to_map<const M: usize, const N: usize>(
-        x: [([u8; M], u32); N],
-    ) -> HashMap<[u8; M], usize> {
-        x.iter().enumerate().map(|(i, &(d, _))| (d, i)).collect()
+        left += 1;
     }
 
-    let mut map = (
-        (),
-        HashMap::new(),
-        HashMap::new(),
-        HashMap::new(),
-        HashMap::new(),
-        HashMap::new(),
-        HashMap::new(),
+    offsets
+};
+
+pub const DICE_DIVISOR: [u32; MAX_DICE + 1] = {
+    let mut div = [1; MAX_DICE + 1];
+    let mut k = 1;
+
+    while k <= MAX_DICE {
+        div[k] = div[k - 1] * 6;
+        k += 1;
+    }
+
+    div
+};
+
+// The probability of each face of a die. The dice are fair unless YATZY_DICE
+// is set to six comma separated weights for the faces 1 to 6, which are then
+// normalized to sum to one.
+pub static FACE_PROBS: Lazy<[f64; 6]> = Lazy::new(|| {
+    let Ok(weights) = std::env::var("YATZY_DICE") else {
+        return [1.0 / 6.0; 6];
+    };
+
+    let weights: Vec<f64> = weights
+        .split(',')
+        .map(|w| w.trim().parse().expect("Invalid dice weight!"))
+        .collect();
+
+    assert!(
+        weights.len() == 6 && weights.iter().all(|&w| w > 0.0),
+        "There has to be six positive dice weights!"
     );
 
-    map.1 = dice_distr_to_map(DICE_DISTR.1);
-    map.2 = dice_distr_to_map(DICE_DISTR.2);
-    map.3 = dice_distr_to_map(DICE_DISTR.3);
-    map.4 = dice_distr_to_map(DICE_DISTR.4);
-    map.5 = dice_distr_to_map(DICE_DISTR.5);
-    map.6 = dice_distr_to_map(DICE_DISTR.6);
+    let total: f64 = weights.iter().sum();
 
-    map
+    array::from_fn(|i| weights[i] / total)
 });
 
-pub const fn amt_dice_combinations<const N: usize>() -> usize {
-    match N {
-        0 => 1,
-        1 => 6,
-        2 => 21,
-        3 => 56,
-        4 => 126,
-        5 => 252,
-        6 => 462,
-        _ => panic!("Unsupported amount of dice!"),
-    }
+pub fn fair_dice() -> bool {
+    FACE_PROBS.iter().all(|&p| p == 1.0 / 6.0)
 }
 
-pub fn dice_order_map_6(k: [u8; 6]) -> usize {
-    match k {
-        [1, 1, 1, 1, 1, 1] => 0,
-        [1, 1, 1, 1, 1, 2] => 1,
-        [1, 1, 1, 1, 1, 3] => 2,
-        [1, 1, 1, 1, 1, 4] => 3,
-        [1, 1, 1, 1, 1, 5] => 4,
-        [1, 1, 1, 1, 1, 6] => 5,
-        [1, 1, 1, 1, 2, 2] => 6,
-        [1, 1, 1, 1, 2, 3] => 7,
-        [1, 1, 1, 1, 2, 4] => 8,
-        [1, 1, 1, 1, 2, 5] => 9,
-        [1, 1, 1, 1, 2, 6] => 10,
-        [1, 1, 1, 1, 3, 3] => 11,
-        [1, 1, 1, 1, 3, 4] => 12,
-        [1, 1, 1, 1, 3, 5] => 13,
-        [1, 1, 1, 1, 3, 6] => 14,
-        [1, 1, 1, 1, 4, 4] => 15,
-        [1, 1, 1, 1, 4, 5] => 16,
-        [1, 1, 1, 1, 4, 6] => 17,
-        [1, 1, 1, 1, 5, 5] => 18,
-        [1, 1, 1, 1, 5, 6] => 19,
-        [1, 1, 1, 1, 6, 6] => 20,
-        [1, 1, 1, 2, 2, 2] => 21,
-        [1, 1, 1, 2, 2, 3] => 22,
-        [1, 1, 1, 2, 2, 4] => 23,
-        [1, 1, 1, 2, 2, 5] => 24,
-        [1, 1, 1, 2, 2, 6] => 25,
-        [1, 1, 1, 2, 3, 3] => 26,
-        [1, 1, 1, 2, 3, 4] => 27,
-        [1, 1, 1, 2, 3, 5] => 28,
-        [1, 1, 1, 2, 3, 6] => 29,
-        [1, 1, 1, 2, 4, 4] => 30,
-        [1, 1, 1, 2, 4, 5] => 31,
-        [1, 1, 1, 2, 4, 6] => 32,
-        [1, 1, 1, 2, 5, 5] => 33,
-        [1, 1, 1, 2, 5, 6] => 34,
-        [1, 1, 1, 2, 6, 6] => 35,
-        [1, 1, 1, 3, 3, 3] => 36,
-        [1, 1, 1, 3, 3, 4] => 37,
-        [1, 1, 1, 3, 3, 5] => 38,
-        [1, 1, 1, 3, 3, 6] => 39,
-        [1, 1, 1, 3, 4, 4] => 40,
-        [1, 1, 1, 3, 4, 5] => 41,
-        [1, 1, 1, 3, 4, 6] => 42,
-        [1, 1, 1, 3, 5, 5] => 43,
-        [1, 1, 1, 3, 5, 6] => 44,
-        [1, 1, 1, 3, 6, 6] => 45,
-        [1, 1, 1, 4, 4, 4] => 46,
-        [1, 1, 1, 4, 4, 5] => 47,
-        [1, 1, 1, 4, 4, 6] => 48,
-        [1, 1, 1, 4, 5, 5] => 49,
-        [1, 1, 1, 4, 5, 6] => 50,
-        [1, 1, 1, 4, 6, 6] => 51,
-        [1, 1, 1, 5, 5, 5] => 52,
-        [1, 1, 1, 5, 5, 6] => 53,
-        [1, 1, 1, 5, 6, 6] => 54,
-        [1, 1, 1, 6, 6, 6] => 55,
-        [1, 1, 2, 2, 2, 2] => 56,
-        [1, 1, 2, 2, 2, 3] => 57,
-        [1, 1, 2, 2, 2, 4] => 58,
-        [1, 1, 2, 2, 2, 5] => 59,
-        [1, 1, 2, 2, 2, 6] => 60,
-        [1, 1, 2, 2, 3, 3] => 61,
-        [1, 1, 2, 2, 3, 4] => 62,
-        [1, 1, 2, 2, 3, 5] => 63,
-        [1, 1, 2, 2, 3, 6] => 64,
-        [1, 1, 2, 2, 4, 4] => 65,
-        [1, 1, 2, 2, 4, 5] => 66,
-        [1, 1, 2, 2, 4, 6] => 67,
-        [1, 1, 2, 2, 5, 5] => 68,
-        [1, 1, 2, 2, 5, 6] => 69,
-        [1, 1, 2, 2, 6, 6] => 70,
-        [1, 1, 2, 3, 3, 3] => 71,
-        [1, 1, 2, 3, 3, 4] => 72,
-        [1, 1, 2, 3, 3, 5] => 73,
-        [1, 1, 2, 3, 3, 6] => 74,
-        [1, 1, 2, 3, 4, 4] => 75,
-        [1, 1, 2, 3, 4, 5] => 76,
-        [1, 1, 2, 3, 4, 6] => 77,
-        [1, 1, 2, 3, 5, 5] => 78,
-        [1, 1, 2, 3, 5, 6] => 79,
-        [1, 1, 2, 3, 6, 6] => 80,
-        [1, 1, 2, 4, 4, 4] => 81,
-        [1, 1, 2, 4, 4, 5] => 82,
-        [1, 1, 2, 4, 4, 6] => 83,
-        [1, 1, 2, 4, 5, 5] => 84,
-        [1, 1, 2, 4, 5, 6] => 85,
-        [1, 1, 2, 4, 6, 6] => 86,
-        [1, 1, 2, 5, 5, 5] => 87,
-        [1, 1, 2, 5, 5, 6] => 88,
-        [1, 1, 2, 5, 6, 6] => 89,
-        [1, 1, 2, 6, 6, 6] => 90,
-        [1, 1, 3, 3, 3, 3] => 91,
-        [1, 1, 3, 3, 3, 4] => 92,
-        [1, 1, 3, 3, 3, 5] => 93,
-        [1, 1, 3, 3, 3, 6] => 94,
-        [1, 1, 3, 3, 4, 4] => 95,
-        [1, 1, 3, 3, 4, 5] => 96,
-        [1, 1, 3, 3, 4, 6] => 97,
-        [1, 1, 3, 3, 5, 5] => 98,
-        [1, 1, 3, 3, 5, 6] => 99,
-        [1, 1, 3, 3, 6, 6] => 100,
-        [1, 1, 3, 4, 4, 4] => 101,
-        [1, 1, 3, 4, 4, 5] => 102,
-        [1, 1, 3, 4, 4, 6] => 103,
-        [1, 1, 3, 4, 5, 5] => 104,
-        [1, 1, 3, 4, 5, 6] => 105,
-        [1, 1, 3, 4, 6, 6] => 106,
-        [1, 1, 3, 5, 5, 5] => 107,
-        [1, 1, 3, 5, 5, 6] => 108,
-        [1, 1, 3, 5, 6, 6] => 109,
-        [1, 1, 3, 6, 6, 6] => 110,
-        [1, 1, 4, 4, 4, 4] => 111,
-        [1, 1, 4, 4, 4, 5] => 112,
-        [1, 1, 4, 4, 4, 6] => 113,
-        [1, 1, 4, 4, 5, 5] => 114,
-        [1, 1, 4, 4, 5, 6] => 115,
-        [1, 1, 4, 4, 6, 6] => 116,
-        [1, 1, 4, 5, 5, 5] => 117,
-        [1, 1, 4, 5, 5, 6] => 118,
-        [1, 1, 4, 5, 6, 6] => 119,
-        [1, 1, 4, 6, 6, 6] => 120,
-        [1, 1, 5, 5, 5, 5] => 121,
-        [1, 1, 5, 5, 5, 6] => 122,
-        [1, 1, 5, 5, 6, 6] => 123,
-        [1, 1, 5, 6, 6, 6] => 124,
-        [1, 1, 6, 6, 6, 6] => 125,
-        [1, 2, 2, 2, 2, 2] => 126,
-        [1, 2, 2, 2, 2, 3] => 127,
-        [1, 2, 2, 2, 2, 4] => 128,
-        [1, 2, 2, 2, 2, 5] => 129,
-        [1, 2, 2, 2, 2, 6] => 130,
-        [1, 2, 2, 2, 3, 3] => 131,
-        [1, 2, 2, 2, 3, 4] => 132,
-        [1, 2, 2, 2, 3, 5] => 133,
-        [1, 2, 2, 2, 3, 6] => 134,
-        [1, 2, 2, 2, 4, 4] => 135,
-        [1, 2, 2, 2, 4, 5] => 136,
-        [1, 2, 2, 2, 4, 6] => 137,
-        [1, 2, 2, 2, 5, 5] => 138,
-        [1, 2, 2, 2, 5, 6] => 139,
-        [1, 2, 2, 2, 6, 6] => 140,
-        [1, 2, 2, 3, 3, 3] => 141,
-        [1, 2, 2, 3, 3, 4] => 142,
-        [1, 2, 2, 3, 3, 5] => 143,
-        [1, 2, 2, 3, 3, 6] => 144,
-        [1, 2, 2, 3, 4, 4] => 145,
-        [1, 2, 2, 3, 4, 5] => 146,
-        [1, 2, 2, 3, 4, 6] => 147,
-        [1, 2, 2, 3, 5, 5] => 148,
-        [1, 2, 2, 3, 5, 6] => 149,
-        [1, 2, 2, 3, 6, 6] => 150,
-        [1, 2, 2, 4, 4, 4] => 151,
-        [1, 2, 2, 4, 4, 5] => 152,
-        [1, 2, 2, 4, 4, 6] => 153,
-        [1, 2, 2, 4, 5, 5] => 154,
-        [1, 2, 2, 4, 5, 6] => 155,
-        [1, 2, 2, 4, 6, 6] => 156,
-        [1, 2, 2, 5, 5, 5] => 157,
-        [1, 2, 2, 5, 5, 6] => 158,
-        [1, 2, 2, 5, 6, 6] => 159,
-        [1, 2, 2, 6, 6, 6] => 160,
-        [1, 2, 3, 3, 3, 3] => 161,
-        [1, 2, 3, 3, 3, 4] => 162,
-        [1, 2, 3, 3, 3, 5] => 163,
-        [1, 2, 3, 3, 3, 6] => 164,
-        [1, 2, 3, 3, 4, 4] => 165,
-        [1, 2, 3, 3, 4, 5] => 166,
-        [1, 2, 3, 3, 4, 6] => 167,
-        [1, 2, 3, 3, 5, 5] => 168,
-        [1, 2, 3, 3, 5, 6] => 169,
-        [1, 2, 3, 3, 6, 6] => 170,
-        [1, 2, 3, 4, 4, 4] => 171,
-        [1, 2, 3, 4, 4, 5] => 172,
-        [1, 2, 3, 4, 4, 6] => 173,
-        [1, 2, 3, 4, 5, 5] => 174,
-        [1, 2, 3, 4, 5, 6] => 175,
-        [1, 2, 3, 4, 6, 6] => 176,
-        [1, 2, 3, 5, 5, 5] => 177,
-        [1, 2, 3, 5, 5, 6] => 178,
-        [1, 2, 3, 5, 6, 6] => 179,
-        [1, 2, 3, 6, 6, 6] => 180,
-        [1, 2, 4, 4, 4, 4] => 181,
-        [1, 2, 4, 4, 4, 5] => 182,
-        [1, 2, 4, 4, 4, 6] => 183,
-        [1, 2, 4, 4, 5, 5] => 184,
-        [1, 2, 4, 4, 5, 6] => 185,
-        [1, 2, 4, 4, 6, 6] => 186,
-        [1, 2, 4, 5, 5, 5] => 187,
-        [1, 2, 4, 5, 5, 6] => 188,
-        [1, 2, 4, 5, 6, 6] => 189,
-        [1, 2, 4, 6, 6, 6] => 190,
-        [1, 2, 5, 5, 5, 5] => 191,
-        [1, 2, 5, 5, 5, 6] => 192,
-        [1, 2, 5, 5, 6, 6] => 193,
-        [1, 2, 5, 6, 6, 6] => 194,
-        [1, 2, 6, 6, 6, 6] => 195,
-        [1, 3, 3, 3, 3, 3] => 196,
-        [1, 3, 3, 3, 3, 4] => 197,
-        [1, 3, 3, 3, 3, 5] => 198,
-        [1, 3, 3, 3, 3, 6] => 199,
-        [1, 3, 3, 3, 4, 4] => 200,
-        [1, 3, 3, 3, 4, 5] => 201,
-        [1, 3, 3, 3, 4, 6] => 202,
-        [1, 3, 3, 3, 5, 5] => 203,
-        [1, 3, 3, 3, 5, 6] => 204,
-        [1, 3, 3, 3, 6, 6] => 205,
-        [1, 3, 3, 4, 4, 4] => 206,
-        [1, 3, 3, 4, 4, 5] => 207,
-        [1, 3, 3, 4, 4, 6] => 208,
-        [1, 3, 3, 4, 5, 5] => 209,
-        [1, 3, 3, 4, 5, 6] => 210,
-        [1, 3, 3, 4, 6, 6] => 211,
-        [1, 3, 3, 5, 5, 5] => 212,
-        [1, 3, 3, 5, 5, 6] => 213,
-        [1, 3, 3, 5, 6, 6] => 214,
-        [1, 3, 3, 6, 6, 6] => 215,
-        [1, 3, 4, 4, 4, 4] => 216,
-        [1, 3, 4, 4, 4, 5] => 217,
-        [1, 3, 4, 4, 4, 6] => 218,
-        [1, 3, 4, 4, 5, 5] => 219,
-        [1, 3, 4, 4, 5, 6] => 220,
-        [1, 3, 4, 4, 6, 6] => 221,
-        [1, 3, 4, 5, 5, 5] => 222,
-        [1, 3, 4, 5, 5, 6] => 223,
-        [1, 3, 4, 5, 6, 6] => 224,
-        [1, 3, 4, 6, 6, 6] => 225,
-        [1, 3, 5, 5, 5, 5] => 226,
-        [1, 3, 5, 5, 5, 6] => 227,
-        [1, 3, 5, 5, 6, 6] => 228,
-        [1, 3, 5, 6, 6, 6] => 229,
-        [1, 3, 6, 6, 6, 6] => 230,
-        [1, 4, 4, 4, 4, 4] => 231,
-        [1, 4, 4, 4, 4, 5] => 232,
-        [1, 4, 4, 4, 4, 6] => 233,
-        [1, 4, 4, 4, 5, 5] => 234,
-        [1, 4, 4, 4, 5, 6] => 235,
-        [1, 4, 4, 4, 6, 6] => 236,
-        [1, 4, 4, 5, 5, 5] => 237,
-        [1, 4, 4, 5, 5, 6] => 238,
-        [1, 4, 4, 5, 6, 6] => 239,
-        [1, 4, 4, 6, 6, 6] => 240,
-        [1, 4, 5, 5, 5, 5] => 241,
-        [1, 4, 5, 5, 5, 6] => 242,
-        [1, 4, 5, 5, 6, 6] => 243,
-        [1, 4, 5, 6, 6, 6] => 244,
-        [1, 4, 6, 6, 6, 6] => 245,
-        [1, 5, 5, 5, 5, 5] => 246,
-        [1, 5, 5, 5, 5, 6] => 247,
-        [1, 5, 5, 5, 6, 6] => 248,
-        [1, 5, 5, 6, 6, 6] => 249,
-        [1, 5, 6, 6, 6, 6] => 250,
-        [1, 6, 6, 6, 6, 6] => 251,
-        [2, 2, 2, 2, 2, 2] => 252,
-        [2, 2, 2, 2, 2, 3] => 253,
-        [2, 2, 2, 2, 2, 4] => 254,
-        [2, 2, 2, 2, 2, 5] => 255,
-        [2, 2, 2, 2, 2, 6] => 256,
-        [2, 2, 2, 2, 3, 3] => 257,
-        [2, 2, 2, 2, 3, 4] => 258,
-        [2, 2, 2, 2, 3, 5] => 259,
-        [2, 2, 2, 2, 3, 6] => 260,
-        [2, 2, 2, 2, 4, 4] => 261,
-        [2, 2, 2, 2, 4, 5] => 262,
-        [2, 2, 2, 2, 4, 6] => 263,
-        [2, 2, 2, 2, 5, 5] => 264,
-        [2, 2, 2, 2, 5, 6] => 265,
-        [2, 2, 2, 2, 6, 6] => 266,
-        [2, 2, 2, 3, 3, 3] => 267,
-        [2, 2, 2, 3, 3, 4] => 268,
-        [2, 2, 2, 3, 3, 5] => 269,
-        [2, 2, 2, 3, 3, 6] => 270,
-        [2, 2, 2, 3, 4, 4] => 271,
-        [2, 2, 2, 3, 4, 5] => 272,
-        [2, 2, 2, 3, 4, 6] => 273,
-        [2, 2, 2, 3, 5, 5] => 274,
-        [2, 2, 2, 3, 5, 6] => 275,
-        [2, 2, 2, 3, 6, 6] => 276,
-        [2, 2, 2, 4, 4, 4] => 277,
-        [2, 2, 2, 4, 4, 5] => 278,
-        [2, 2, 2, 4, 4, 6] => 279,
-        [2, 2, 2, 4, 5, 5] => 280,
-        [2, 2, 2, 4, 5, 6] => 281,
-        [2, 2, 2, 4, 6, 6] => 282,
-        [2, 2, 2, 5, 5, 5] => 283,
-        [2, 2, 2, 5, 5, 6] => 284,
-        [2, 2, 2, 5, 6, 6] => 285,
-        [2, 2, 2, 6, 6, 6] => 286,
-        [2, 2, 3, 3, 3, 3] => 287,
-        [2, 2, 3, 3, 3, 4] => 288,
-        [2, 2, 3, 3, 3, 5] => 289,
-        [2, 2, 3, 3, 3, 6] => 290,
-        [2, 2, 3, 3, 4, 4] => 291,
-        [2, 2, 3, 3, 4, 5] => 292,
-        [2, 2, 3, 3, 4, 6] => 293,
-        [2, 2, 3, 3, 5, 5] => 294,
-        [2, 2, 3, 3, 5, 6] => 295,
-        [2, 2, 3, 3, 6, 6] => 296,
-        [2, 2, 3, 4, 4, 4] => 297,
-        [2, 2, 3, 4, 4, 5] => 298,
-        [2, 2, 3, 4, 4, 6] => 299,
-        [2, 2, 3, 4, 5, 5] => 300,
-        [2, 2, 3, 4, 5, 6] => 301,
-        [2, 2, 3, 4, 6, 6] => 302,
-        [2, 2, 3, 5, 5, 5] => 303,
-        [2, 2, 3, 5, 5, 6] => 304,
-        [2, 2, 3, 5, 6, 6] => 305,
-        [2, 2, 3, 6, 6, 6] => 306,
-        [2, 2, 4, 4, 4, 4] => 307,
-        [2, 2, 4, 4, 4, 5] => 308,
-        [2, 2, 4, 4, 4, 6] => 309,
-        [2, 2, 4, 4, 5, 5] => 310,
-        [2, 2, 4, 4, 5, 6] => 311,
-        [2, 2, 4, 4, 6, 6] => 312,
-        [2, 2, 4, 5, 5, 5] => 313,
-        [2, 2, 4, 5, 5, 6] => 314,
-        [2, 2, 4, 5, 6, 6] => 315,
-        [2, 2, 4, 6, 6, 6] => 316,
-        [2, 2, 5, 5, 5, 5] => 317,
-        [2, 2, 5, 5, 5, 6] => 318,
-        [2, 2, 5, 5, 6, 6] => 319,
-        [2, 2, 5, 6, 6, 6] => 320,
-        [2, 2, 6, 6, 6, 6] => 321,
-        [2, 3, 3, 3, 3, 3] => 322,
-        [2, 3, 3, 3, 3, 4] => 323,
-        [2, 3, 3, 3, 3, 5] => 324,
-        [2, 3, 3, 3, 3, 6] => 325,
-        [2, 3, 3, 3, 4, 4] => 326,
-        [2, 3, 3, 3, 4, 5] => 327,
-        [2, 3, 3, 3, 4, 6] => 328,
-        [2, 3, 3, 3, 5, 5] => 329,
-        [2, 3, 3, 3, 5, 6] => 330,
-        [2, 3, 3, 3, 6, 6] => 331,
-        [2, 3, 3, 4, 4, 4] => 332,
-        [2, 3, 3, 4, 4, 5] => 333,
-        [2, 3, 3, 4, 4, 6] => 334,
-        [2, 3, 3, 4, 5, 5] => 335,
-        [2, 3, 3, 4, 5, 6] => 336,
-        [2, 3, 3, 4, 6, 6] => 337,
-        [2, 3, 3, 5, 5, 5] => 338,
-        [2, 3, 3, 5, 5, 6] => 339,
-        [2, 3, 3, 5, 6, 6] => 340,
-        [2, 3, 3, 6, 6, 6] => 341,
-        [2, 3, 4, 4, 4, 4] => 342,
-        [2, 3, 4, 4, 4, 5] => 343,
-        [2, 3, 4, 4, 4, 6] => 344,
-        [2, 3, 4, 4, 5, 5] => 345,
-        [2, 3, 4, 4, 5, 6] => 346,
-        [2, 3, 4, 4, 6, 6] => 347,
-        [2, 3, 4, 5, 5, 5] => 348,
-        [2, 3, 4, 5, 5, 6] => 349,
-        [2, 3, 4, 5, 6, 6] => 350,
-        [2, 3, 4, 6, 6, 6] => 351,
-        [2, 3, 5, 5, 5, 5] => 352,
-        [2, 3, 5, 5, 5, 6] => 353,
-        [2, 3, 5, 5, 6, 6] => 354,
-        [2, 3, 5, 6, 6, 6] => 355,
-        [2, 3, 6, 6, 6, 6] => 356,
-        [2, 4, 4, 4, 4, 4] => 357,
-        [2, 4, 4, 4, 4, 5] => 358,
-        [2, 4, 4, 4, 4, 6] => 359,
-        [2, 4, 4, 4, 5, 5] => 360,
-        [2, 4, 4, 4, 5, 6] => 361,
-        [2, 4, 4, 4, 6, 6] => 362,
-        [2, 4, 4, 5, 5, 5] => 363,
-        [2, 4, 4, 5, 5, 6] => 364,
-        [2, 4, 4, 5, 6, 6] => 365,
-        [2, 4, 4, 6, 6, 6] => 366,
-        [2, 4, 5, 5, 5, 5] => 367,
-        [2, 4, 5, 5, 5, 6] => 368,
-        [2, 4, 5, 5, 6, 6] => 369,
-        [2, 4, 5, 6, 6, 6] => 370,
-        [2, 4, 6, 6, 6, 6] => 371,
-        [2, 5, 5, 5, 5, 5] => 372,
-        [2, 5, 5, 5, 5, 6] => 373,
-        [2, 5, 5, 5, 6, 6] => 374,
-        [2, 5, 5, 6, 6, 6] => 375,
-        [2, 5, 6, 6, 6, 6] => 376,
-        [2, 6, 6, 6, 6, 6] => 377,
-        [3, 3, 3, 3, 3, 3] => 378,
-        [3, 3, 3, 3, 3, 4] => 379,
-        [3, 3, 3, 3, 3, 5] => 380,
-        [3, 3, 3, 3, 3, 6] => 381,
-        [3, 3, 3, 3, 4, 4] => 382,
-        [3, 3, 3, 3, 4, 5] => 383,
-        [3, 3, 3, 3, 4, 6] => 384,
-        [3, 3, 3, 3, 5, 5] => 385,
-        [3, 3, 3, 3, 5, 6] => 386,
-        [3, 3, 3, 3, 6, 6] => 387,
-        [3, 3, 3, 4, 4, 4] => 388,
-        [3, 3, 3, 4, 4, 5] => 389,
-        [3, 3, 3, 4, 4, 6] => 390,
-        [3, 3, 3, 4, 5, 5] => 391,
-        [3, 3, 3, 4, 5, 6] => 392,
-        [3, 3, 3, 4, 6, 6] => 393,
-        [3, 3, 3, 5, 5, 5] => 394,
-        [3, 3, 3, 5, 5, 6] => 395,
-        [3, 3, 3, 5, 6, 6] => 396,
-        [3, 3, 3, 6, 6, 6] => 397,
-        [3, 3, 4, 4, 4, 4] => 398,
-        [3, 3, 4, 4, 4, 5] => 399,
-        [3, 3, 4, 4, 4, 6] => 400,
-        [3, 3, 4, 4, 5, 5] => 401,
-        [3, 3, 4, 4, 5, 6] => 402,
-        [3, 3, 4, 4, 6, 6] => 403,
-        [3, 3, 4, 5, 5, 5] => 404,
-        [3, 3, 4, 5, 5, 6] => 405,
-        [3, 3, 4, 5, 6, 6] => 406,
-        [3, 3, 4, 6, 6, 6] => 407,
-        [3, 3, 5, 5, 5, 5] => 408,
-        [3, 3, 5, 5, 5, 6] => 409,
-        [3, 3, 5, 5, 6, 6] => 410,
-        [3, 3, 5, 6, 6, 6] => 411,
-        [3, 3, 6, 6, 6, 6] => 412,
-        [3, 4, 4, 4, 4, 4] => 413,
-        [3, 4, 4, 4, 4, 5] => 414,
-        [3, 4, 4, 4, 4, 6] => 415,
-        [3, 4, 4, 4, 5, 5] => 416,
-        [3, 4, 4, 4, 5, 6] => 417,
-        [3, 4, 4, 4, 6, 6] => 418,
-        [3, 4, 4, 5, 5, 5] => 419,
-        [3, 4, 4, 5, 5, 6] => 420,
-        [3, 4, 4, 5, 6, 6] => 421,
-        [3, 4, 4, 6, 6, 6] => 422,
-        [3, 4, 5, 5, 5, 5] => 423,
-        [3, 4, 5, 5, 5, 6] => 424,
-        [3, 4, 5, 5, 6, 6] => 425,
-        [3, 4, 5, 6, 6, 6] => 426,
-        [3, 4, 6, 6, 6, 6] => 427,
-        [3, 5, 5, 5, 5, 5] => 428,
-        [3, 5, 5, 5, 5, 6] => 429,
-        [3, 5, 5, 5, 6, 6] => 430,
-        [3, 5, 5, 6, 6, 6] => 431,
-        [3, 5, 6, 6, 6, 6] => 432,
-        [3, 6, 6, 6, 6, 6] => 433,
-        [4, 4, 4, 4, 4, 4] => 434,
-        [4, 4, 4, 4, 4, 5] => 435,
-        [4, 4, 4, 4, 4, 6] => 436,
-        [4, 4, 4, 4, 5, 5] => 437,
-        [4, 4, 4, 4, 5, 6] => 438,
-        [4, 4, 4, 4, 6, 6] => 439,
-        [4, 4, 4, 5, 5, 5] => 440,
-        [4, 4, 4, 5, 5, 6] => 441,
-        [4, 4, 4, 5, 6, 6] => 442,
-        [4, 4, 4, 6, 6, 6] => 443,
-        [4, 4, 5, 5, 5, 5] => 444,
-        [4, 4, 5, 5, 5, 6] => 445,
-        [4, 4, 5, 5, 6, 6] => 446,
-        [4, 4, 5, 6, 6, 6] => 447,
-        [4, 4, 6, 6, 6, 6] => 448,
-        [4, 5, 5, 5, 5, 5] => 449,
-        [4, 5, 5, 5, 5, 6] => 450,
-        [4, 5, 5, 5, 6, 6] => 451,
-        [4, 5, 5, 6, 6, 6] => 452,
-        [4, 5, 6, 6, 6, 6] => 453,
-        [4, 6, 6, 6, 6, 6] => 454,
-        [5, 5, 5, 5, 5, 5] => 455,
-        [5, 5, 5, 5, 5, 6] => 456,
-        [5, 5, 5, 5, 6, 6] => 457,
-        [5, 5, 5, 6, 6, 6] => 458,
-        [5, 5, 6, 6, 6, 6] => 459,
-        [5, 6, 6, 6, 6, 6] => 460,
-        [6, 6, 6, 6, 6, 6] => 461,
-        _ => panic!(),
+// The probability of each throw of K dice, in the order of DICE_DISTR[K].
+// For fair dice these are exactly the multiplicities over DICE_DIVISOR.
+pub static DICE_PROBS: Lazy<[Vec<f64>; MAX_DICE + 1]> = Lazy::new(|| {
+    fn probs<const K: usize, const N: usize>(
+        distr: &[([u8; K], u32); N],
+    ) -> Vec<f64> {
+        distr
+            .iter()
+            .map(|&(dice, mult)| {
+                if fair_dice() {
+                    mult as f64 / DICE_DIVISOR[K] as f64
+                } else {
+                    dice.iter()
+                        .map(|&d| FACE_PROBS[d as usize - 1])
+                        .product::<f64>()
+                        * mult as f64
+                }
+            })
+            .collect()
     }
-}
 
-pub static DICE_REROLL_MATRICES: Lazy<[Array3<f32>; 6]> = Lazy::new(|| {
-    fn loop_rerolls<const M: usize, const N: usize, const K: usize>(
-        distr: &[([u8; K], u32); N],
-        dice: &DiceThrow,
-        reroll: u8,
-        map: &HashMap<[u8; M], usize>,
-        probs: &mut [f32],
-    ) {
-        let div = 1.0 / DICE_DIVISOR[K] as f32;
+    [
+        vec![1.0],
+        probs(&DICE_DISTR.1),
+        probs(&DICE_DISTR.2),
+        probs(&DICE_DISTR.3),
+        probs(&DICE_DISTR.4),
+        probs(&DICE_DISTR.5),
+        probs(&DICE_DISTR.6),
+        probs(&DICE_DISTR.7),
+        probs(&DICE_DISTR.8),
+    ]
+});
 
-        for &(rethrow, prob) in distr.iter() {
-            let new_throw = dice.overwrite_reroll::<M, K>(reroll, rethrow);
+fn loop_rerolls<const M: usize, const N: usize, const K: usize>(
+    distr: &[([u8; K], u32); N],
+    dice: &DiceThrow,
+    reroll: u8,
+    probs: &mut [f32],
+) {
+    for (&(rethrow, _), &prob) in distr.iter().zip(&DICE_PROBS[K]) {
+        let new_throw = dice.overwrite_reroll::<M, K>(reroll, rethrow);
 
-            let ti = map[&new_throw.collect_dice()];
+        probs[new_throw.get_index()] += prob as f32;
+    }
+}
 
-            probs[ti] = div.mul_add(prob as f32, probs[ti]);
-        }
+// Adds the probabilities of the throws of M dice after rerolling the dice in
+// the mask to probs.
+fn reroll_probs<const M: usize>(
+    dice: &DiceThrow,
+    reroll: u8,
+    probs: &mut [f32],
+) {
+    match reroll.count_ones() {
+        0 => probs[dice.get_index()] += 1.0,
+        1 => loop_rerolls::<M, _, _>(&DICE_DISTR.1, dice, reroll, probs),
+        2 => loop_rerolls::<M, _, _>(&DICE_DISTR.2, dice, reroll, probs),
+        3 => loop_rerolls::<M, _, _>(&DICE_DISTR.3, dice, reroll, probs),
+        4 => loop_rerolls::<M, _, _>(&DICE_DISTR.4, dice, reroll, probs),
+        5 => loop_rerolls::<M, _, _>(&DICE_DISTR.5, dice, reroll, probs),
+        6 => loop_rerolls::<M, _, _>(&DICE_DISTR.6, dice, reroll, probs),
+        _ => panic!(),
     }
+}
 
+// The reroll matrices are only made for up to 6 dice, since they grow as the
+// square of the amount of throws times the amount of rerolls. No rule set has
+// more dice, and the ones for 7 and 8 dice would take 321 MB and 1.7 GB. The
+// tables in DICE_DISTR go up to MAX_DICE for variants with more dice, which
+// then need these matrices and the reroll operators extended.
+pub static DICE_REROLL_MATRICES: Lazy<[Array3<f32>; 6]> = Lazy::new(|| {
     fn make_matrix<const M: usize, const N: usize>(
         distr: &[([u8; M], u32); N],
-        map: &HashMap<[u8; M], usize>,
     ) -> Array3<f32> {
         let mut mat =
             Array3::from_shape_simple_fn([N, 2usize.pow(M as u32), N], || 0.0);
 
-        for (i, (mut mat, &(dice, _))) in
-            mat.outer_iter_mut().zip(distr).enumerate()
-        {
+        for (mut mat, &(dice, _)) in mat.outer_iter_mut().zip(distr) {
             let dice = DiceThrow::from(dice);
 
-            mat[[0, i]] = 1.0;
-
-            for (i, mut row) in mat.rows_mut().into_iter().enumerate().skip(1) {
-                let reroll = i as u8;
-                match reroll.count_ones() {
-                    1 => loop_rerolls(
-                        &DICE_DISTR.1,
-                        &dice,
-                        reroll,
-                        map,
-                        row.as_slice_mut().unwrap(),
-                    ),
-                    2 => loop_rerolls(
-                        &DICE_DISTR.2,
-                        &dice,
-                        reroll,
-                        map,
-                        row.as_slice_mut().unwrap(),
-                    ),
-                    3 => loop_rerolls(
-                        &DICE_DISTR.3,
-                        &dice,
-                        reroll,
-                        map,
-                        row.as_slice_mut().unwrap(),
-                    ),
-                    4 => loop_rerolls(
-                        &DICE_DISTR.4,
-                        &dice,
-                        reroll,
-                        map,
-                        row.as_slice_mut().unwrap(),
-                    ),
-                    5 => loop_rerolls(
-                        &DICE_DISTR.5,
-                        &dice,
-                        reroll,
-                        map,
-                        row.as_slice_mut().unwrap(),
-                    ),
-                    6 => loop_rerolls(
-                        &DICE_DISTR.6,
-                        &dice,
-                        reroll,
-                        map,
-                        row.as_slice_mut().unwrap(),
-                    ),
-                    _ => panic!(),
-                };
+            for (i, mut row) in mat.rows_mut().into_iter().enumerate() {
+                reroll_probs::<M>(&dice, i as u8, row.as_slice_mut().unwrap());
             }
         }
 
@@ -1555,11 +272,195 @@ pub static DICE_REROLL_MATRICES: Lazy<[Array3<f32>; 6]> = Lazy::new(|| {
     }
 
     [
-        make_matrix(&DICE_DISTR.1, &DICE_ORDER_MAP.1),
-        make_matrix(&DICE_DISTR.2, &DICE_ORDER_MAP.2),
-        make_matrix(&DICE_DISTR.3, &DICE_ORDER_MAP.3),
-        make_matrix(&DICE_DISTR.4, &DICE_ORDER_MAP.4),
-        make_matrix(&DICE_DISTR.5, &DICE_ORDER_MAP.5),
-        make_matrix(&DICE_DISTR.6, &DICE_ORDER_MAP.6),
+        make_matrix(&DICE_DISTR.1),
+        make_matrix(&DICE_DISTR.2),
+        make_matrix(&DICE_DISTR.3),
+        make_matrix(&DICE_DISTR.4),
+        make_matrix(&DICE_DISTR.5),
+        make_matrix(&DICE_DISTR.6),
+    ]
+});
+
+// The reroll matrices without the rows that are the same. Which throws you
+// can end up with only depends on the dice you keep, so there is one row for
+// every set of kept dice instead of one for every throw and mask, e.g. 924
+// rows instead of 29 568 for 6 dice.
+pub struct RerollOperator {
+    // The probabilities of the throws after rerolling, for every set of kept
+    // dice ordered by the amount of dice and then as in `DICE_DISTR`.
+    pub matrix: Array2<f32>,
+    // The mask and the row of the kept dice of every distinct reroll of each
+    // throw, in increasing order of the masks. As `DiceThrow::get_mask`
+    // rerolls the first of the equal dice, the masks are the lowest ones of
+    // the rerolls they stand for.
+    pub rerolls: Vec<Vec<(u8, usize)>>,
+}
+
+impl RerollOperator {
+    // The row of the kept dice when rerolling `mask` of the throw ti of n
+    // dice. The mask does not have to be the lowest one of its rerolls.
+    pub fn row(&self, n: usize, ti: usize, mask: u8) -> usize {
+        let dice = DiceThrow::from_index(n, ti);
+        let reroll = dice.get_mask(dice.get_subthrow(mask));
+
+        let rerolls = &self.rerolls[ti];
+        let i = rerolls
+            .binary_search_by_key(&reroll, |&(x, _)| x)
+            .expect("A reroll is missing from the reroll operator!");
+
+        rerolls[i].1
+    }
+}
+
+pub static DICE_REROLL_OPERATORS: Lazy<[RerollOperator; 6]> = Lazy::new(|| {
+    fn make_operator<const M: usize, const N: usize>(
+        distr: &[([u8; M], u32); N],
+    ) -> RerollOperator {
+        let mut row_offsets = vec![0];
+        for k in 0..M {
+            row_offsets.push(row_offsets[k] + DICE_PROBS[k].len());
+        }
+
+        let mut matrix = Array2::zeros([row_offsets[M] + N, N]);
+        let mut done = vec![false; matrix.nrows()];
+
+        let rerolls = distr
+            .iter()
+            .map(|&(dice, _)| {
+                let dice = DiceThrow::from(dice);
+
+                let mut rerolls: Vec<_> = dice
+                    .into_sub_throw_iter()
+                    .map(|sub_throw| {
+                        let mut kept = dice;
+                        for face in 1..=6 {
+                            kept[face] -= sub_throw[face];
+                        }
+
+                        let mask = dice.get_mask(sub_throw);
+                        let row =
+                            row_offsets[kept.amt_dice()] + kept.get_index();
+
+                        if !done[row] {
+                            let mut probs = matrix.row_mut(row);
+                            reroll_probs::<M>(
+                                &dice,
+                                mask,
+                                probs.as_slice_mut().unwrap(),
+                            );
+                            done[row] = true;
+                        }
+
+                        (mask, row)
+                    })
+                    .collect();
+
+                rerolls.sort_unstable();
+
+                rerolls
+            })
+            .collect();
+
+        RerollOperator { matrix, rerolls }
+    }
+
+    [
+        make_operator(&DICE_DISTR.1),
+        make_operator(&DICE_DISTR.2),
+        make_operator(&DICE_DISTR.3),
+        make_operator(&DICE_DISTR.4),
+        make_operator(&DICE_DISTR.5),
+        make_operator(&DICE_DISTR.6),
     ]
 });
+
+// A checksum of the throws and multiplicities of a table, to check that the
+// generated tables stay the same as the ones the caches were made with.
+fn table_checksum<const K: usize, const N: usize>(
+    distr: &[([u8; K], u32); N],
+) -> u64 {
+    fnv1a(distr.iter().flat_map(|(dice, mult)| {
+        dice.iter().copied().chain(mult.to_le_bytes())
+    }))
+}
+
+fn check_table<const K: usize, const N: usize>(distr: &[([u8; K], u32); N]) {
+    assert!(
+        distr.windows(2).all(|w| w[0].0 < w[1].0),
+        "The throws of {K} dice are not in order!"
+    );
+    assert!(
+        distr
+            .iter()
+            .all(|(dice, _)| dice.windows(2).all(|w| w[0] <= w[1])),
+        "The throws of {K} dice are not sorted!"
+    );
+    assert_eq!(
+        distr.iter().map(|&(_, mult)| mult).sum::<u32>(),
+        DICE_DIVISOR[K],
+        "The multiplicities of {K} dice are wrong!"
+    );
+    assert!(
+        distr.iter().enumerate().all(|(i, &(dice, _))| {
+            let throw = DiceThrow::from(dice);
+            throw.get_index() == i && DiceThrow::from_index(K, i) == throw
+        }),
+        "The indexing of {K} dice is wrong!"
+    );
+}
+
+// Checks that every reroll of n dice has the same row in the reroll operator
+// as in the reroll matrix, where it is found by the mask rerolling the first
+// of the equal dice.
+fn check_reroll_operator(n: usize) {
+    let operator = &DICE_REROLL_OPERATORS[n - 1];
+    let matrix = &DICE_REROLL_MATRICES[n - 1];
+
+    for ti in 0..operator.rerolls.len() {
+        for mask in 0..1 << n {
+            assert_eq!(
+                operator.matrix.row(operator.row(n, ti, mask)),
+                matrix.slice(s![ti, mask as usize, ..]),
+                "The reroll operator of {n} dice is wrong!"
+            );
+        }
+    }
+}
+
+// Checks that the generated dice tables are consistent, and that the tables
+// for 5 and 6 dice are the same as the ones that used to be written out.
+pub fn check_dice_tables() {
+    check_table(&DICE_DISTR.1);
+    check_table(&DICE_DISTR.2);
+    check_table(&DICE_DISTR.3);
+    check_table(&DICE_DISTR.4);
+    check_table(&DICE_DISTR.5);
+    check_table(&DICE_DISTR.6);
+    check_table(&DICE_DISTR.7);
+    check_table(&DICE_DISTR.8);
+
+    assert_eq!(table_checksum(&DICE_DISTR.5), 0x7b1a8853be676a7d);
+    assert_eq!(table_checksum(&DICE_DISTR.6), 0x870c23a60e2379c3);
+}
+
+// Checks the reroll operators of every amount of dice they are made for.
+pub fn check_reroll_operators() {
+    for n in 1..=6 {
+        check_reroll_operator(n);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dice_tables() {
+        check_dice_tables();
+    }
+
+    #[test]
+    fn reroll_operators() {
+        check_reroll_operators();
+    }
+}
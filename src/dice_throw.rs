@@ -3,9 +3,17 @@ use std::{
     ops::{Index, IndexMut},
 };
 
-use rand::prelude::*;
+use once_cell::sync::Lazy;
+use rand::{distributions::WeightedIndex, prelude::*};
 
-use crate::dice_distributions::DICE_ORDER_MAP;
+use crate::{
+    dice_distributions::{fair_dice, DICE_RANK_OFFSETS, FACE_PROBS, MAX_DICE},
+    rules::RuleSet,
+    with_rules,
+};
+
+static FACE_DISTR: Lazy<WeightedIndex<f64>> =
+    Lazy::new(|| WeightedIndex::new(*FACE_PROBS).unwrap());
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct DiceThrow {
@@ -98,7 +106,11 @@ impl DiceThrow {
         let mut rng = rand::thread_rng();
 
         for _ in 0..n {
-            let eyes = rng.gen_range(1..=6);
+            let eyes = if fair_dice() {
+                rng.gen_range(1..=6)
+            } else {
+                FACE_DISTR.sample(&mut rng) + 1
+            };
 
             dice_throw[eyes] += 1;
         }
@@ -172,31 +184,32 @@ impl DiceThrow {
     }
 
     pub fn cell_score<const N: usize>(&self, cell_ind: usize) -> usize {
-        match (N, cell_ind) {
-            (_, 0) => self.ammount_of::<1>(),
-            (_, 1) => self.ammount_of::<2>(),
-            (_, 2) => self.ammount_of::<3>(),
-            (_, 3) => self.ammount_of::<4>(),
-            (_, 4) => self.ammount_of::<5>(),
-            (_, 5) => self.ammount_of::<6>(),
-            (_, 6) => self.pairs::<1>(),
-            (_, 7) => self.pairs::<2>(),
-            (6, 8) => self.pairs::<3>(),
-            (5, 8) | (6, 9) => self.n_of_a_kind::<3>(),
-            (5, 9) | (6, 10) => self.n_of_a_kind::<4>(),
-            (6, 11) => self.n_of_a_kind::<5>(),
-            (5, 10) | (6, 12) => self.straight::<1, 5>(),
-            (5, 11) | (6, 13) => self.straight::<2, 6>(),
-            (6, 14) => self.straight::<1, 6>(),
-            (5, 12) | (6, 15) => self.building::<3, 2>(),
-            (6, 16) => self.building::<3, 3>(),
-            (6, 17) => self.building::<4, 2>(),
-            (5, 13) | (6, 18) => self.chance(),
-            (5, 14) | (6, 19) => self.yatzy(),
-            _ => unreachable!(),
+        with_rules!(N, R => R::cell_score(self, cell_ind))
+    }
+
+    // The sum of all the dice if at least N of them are the same.
+    pub fn sum_of_a_kind<const N: usize>(&self) -> usize {
+        if (1..=6).any(|i| self[i] >= N) {
+            self.chance()
+        } else {
+            0
         }
     }
 
+    pub fn full_house(&self) -> bool {
+        (1..=6).any(|i| self[i] == 3) && (1..=6).any(|i| self[i] == 2)
+    }
+
+    pub fn longest_run(&self) -> usize {
+        (1..=6)
+            .scan(0, |run, i| {
+                *run = if self[i] > 0 { *run + 1 } else { 0 };
+                Some(*run)
+            })
+            .max()
+            .unwrap()
+    }
+
     pub fn test_all(&self) {
         println!("1:                {}", self.ammount_of::<1>());
         println!("2:                {}", self.ammount_of::<2>());
@@ -232,15 +245,17 @@ impl DiceThrow {
     pub fn probability(&self) -> f64 {
         let amt_dice = self.amt_dice();
 
-        let tot = 6usize.pow(amt_dice as u32);
-
         let perms: usize = factorial(amt_dice);
 
         let dup_perms: usize = (1..=6).map(|i| factorial(self[i])).product();
 
         let actual_perms = perms / dup_perms;
 
-        (actual_perms as f64) / (tot as f64)
+        let face_probs: f64 = (1..=6)
+            .map(|i| FACE_PROBS[i - 1].powi(self[i] as i32))
+            .product();
+
+        (actual_perms as f64) * face_probs
     }
 
     pub fn into_ordered_dice(&self) -> impl Iterator<Item = u8> + '_ {
@@ -260,35 +275,51 @@ impl DiceThrow {
         dice
     }
 
+    // The index of the throw among the sorted throws of as many dice, in the
+    // order of `DICE_DISTR`.
     pub fn get_index(&self) -> usize {
-        match self.amt_dice() {
-            0 => 0,
-            1 => {
-                let dice = self.into_ordered_dice_const::<1>();
-                DICE_ORDER_MAP.1[&dice]
-            }
-            2 => {
-                let dice = self.into_ordered_dice_const::<2>();
-                DICE_ORDER_MAP.2[&dice]
-            }
-            3 => {
-                let dice = self.into_ordered_dice_const::<3>();
-                DICE_ORDER_MAP.3[&dice]
-            }
-            4 => {
-                let dice = self.into_ordered_dice_const::<4>();
-                DICE_ORDER_MAP.4[&dice]
-            }
-            5 => {
-                let dice = self.into_ordered_dice_const::<5>();
-                DICE_ORDER_MAP.5[&dice]
-            }
-            6 => {
-                let dice = self.into_ordered_dice_const::<6>();
-                DICE_ORDER_MAP.6[&dice]
+        let mut left = self.amt_dice();
+
+        assert!(left <= MAX_DICE, "Unsupported amount of dice!");
+
+        let mut index = 0;
+        let mut prev = 1;
+
+        // Only the first die of each face skips any throws, as the next ones
+        // start from the same face.
+        for face in 1..=6 {
+            if self[face] > 0 {
+                index += DICE_RANK_OFFSETS[left - 1][face]
+                    - DICE_RANK_OFFSETS[left - 1][prev];
+                left -= self[face];
+                prev = face;
             }
-            _ => panic!("Unsupported amount of dice!"),
         }
+
+        index
+    }
+
+    // The inverse of `get_index`.
+    pub fn from_index(amt_dice: usize, mut index: usize) -> Self {
+        assert!(amt_dice <= MAX_DICE, "Unsupported amount of dice!");
+
+        let mut dice = Self::new();
+        let mut prev = 1;
+
+        for left in (0..amt_dice).rev() {
+            let offsets = &DICE_RANK_OFFSETS[left];
+
+            let face = (prev..=6)
+                .rev()
+                .find(|&face| offsets[face] - offsets[prev] <= index)
+                .unwrap();
+
+            index -= offsets[face] - offsets[prev];
+            dice[face] += 1;
+            prev = face;
+        }
+
+        dice
     }
 
     pub fn collect_dice<const N: usize>(&self) -> [u8; N] {
@@ -414,3 +445,37 @@ impl Iterator for SubThrowIter {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use itertools::Itertools;
+
+    use super::*;
+
+    // Every sorted throw of n dice gets its own index below the amount of
+    // throws, in increasing order, and `from_index` gives the throw back.
+    #[test]
+    fn rank_unrank_bijection() {
+        for n in 1..=MAX_DICE {
+            let throws: Vec<_> = (1..=6u8)
+                .combinations_with_replacement(n)
+                .map(|dice| {
+                    let mut throw = DiceThrow::new();
+                    for d in dice {
+                        throw[d as usize] += 1;
+                    }
+                    throw
+                })
+                .collect();
+
+            for (i, throw) in throws.iter().enumerate() {
+                assert_eq!(throw.get_index(), i, "Wrong rank of {n} dice!");
+                assert_eq!(
+                    DiceThrow::from_index(n, i),
+                    *throw,
+                    "Wrong unrank of {n} dice!"
+                );
+            }
+        }
+    }
+}
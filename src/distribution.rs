@@ -0,0 +1,395 @@
+// This is the module for finding the exact probability distribution of the
+// final score when following the strategy that maximizes the expected score.
+// Instead of simulating games, the probability mass for every state is
+// propagated forward through the layers, one turn at a time.
+//
+// Within a turn the dice do not care about how many points you already have,
+// so for each state (ai, bi) we first find the distribution of where the turn
+// ends up (which cell and how many points), and then shift the score
+// distribution of that state accordingly into the next layers.
+//
+// Note that the whole score distribution is kept for every state in the
+// layers currently in flight. For 5 dice this is around a GiB, while for
+// 6 dice it is only feasible towards the end of the game.
+
+use std::{
+    fs::OpenOptions,
+    io::{BufRead, BufReader, Write},
+};
+
+use ndarray::{
+    parallel::prelude::{IntoParallelIterator, ParallelIterator},
+    s, Array2, Array3, Axis,
+};
+
+use crate::{
+    dice_distributions::{DICE_PROBS, DICE_REROLL_MATRICES},
+    dice_throw::DiceThrow,
+    guide::get_state_indices,
+    macrosolver::outcore::{layer_shape, rerolls, Layer},
+    rules::RuleSet,
+    solver::{lookups, throw_probs},
+    with_rules,
+    yatzy::cell_from_dice,
+};
+
+pub struct ScoreDistribution {
+    pub pmf: Vec<f64>,
+}
+
+impl ScoreDistribution {
+    pub fn mean(&self) -> f64 {
+        self.pmf.iter().enumerate().map(|(s, p)| s as f64 * p).sum()
+    }
+
+    pub fn std(&self) -> f64 {
+        let mean = self.mean();
+
+        self.pmf
+            .iter()
+            .enumerate()
+            .map(|(s, p)| (s as f64 - mean).powi(2) * p)
+            .sum::<f64>()
+            .sqrt()
+    }
+
+    pub fn prob_at_least(&self, score: usize) -> f64 {
+        self.pmf.iter().skip(score).sum()
+    }
+
+    pub fn prob_at_most(&self, score: usize) -> f64 {
+        self.pmf.iter().take(score + 1).sum()
+    }
+
+    // The lowest score s such that P(final score <= s) >= q
+    pub fn quantile(&self, q: f64) -> usize {
+        let mut acc = 0.0;
+
+        for (s, p) in self.pmf.iter().enumerate() {
+            acc += p;
+            if acc >= q {
+                return s;
+            }
+        }
+
+        self.pmf.len() - 1
+    }
+
+    pub fn path<const N: usize>() -> String {
+        format!("{}/distribution.txt", Layer::<N, false>::empty().dir())
+    }
+
+    // Saves the histogram as lines of "<score> <probability>", skipping
+    // scores that are impossible.
+    pub fn save(&self, path: &str) {
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)
+            .unwrap();
+
+        for (s, &p) in self.pmf.iter().enumerate().filter(|(_, &p)| p > 0.0) {
+            writeln!(file, "{s} {p:e}").unwrap();
+        }
+    }
+
+    pub fn load(path: &str) -> Option<Self> {
+        let file = OpenOptions::new().read(true).open(path).ok()?;
+
+        let mut pmf = Vec::new();
+
+        for line in BufReader::new(file).lines() {
+            let line = line.ok()?;
+            let (s, p) = line.split_once(' ')?;
+            let s: usize = s.parse().ok()?;
+
+            if pmf.len() <= s {
+                pmf.resize(s + 1, 0.0);
+            }
+
+            pmf[s] = p.parse().ok()?;
+        }
+
+        Some(Self { pmf })
+    }
+}
+
+// The throws of N dice, in the order of DICE_DISTR.
+pub fn throws<const N: usize>() -> Vec<DiceThrow> {
+    (0..DICE_PROBS[N].len())
+        .map(|ti| DiceThrow::from_index(N, ti))
+        .collect()
+}
+
+// The highest possible final score, which bounds the length of the score
+// axis.
+pub fn max_score<const N: usize>() -> usize {
+    let bonus = with_rules!(N, R => R::BONUS);
+
+    let throws = throws::<N>();
+
+    (0..cell_from_dice::<N>())
+        .map(|cell| throws.iter().map(|t| t.cell_score::<N>(cell)).max())
+        .map(Option::unwrap)
+        .sum::<usize>()
+        + bonus
+}
+
+// Where a turn can end up when starting in state (ai, bi) of layer (na, nb)
+// with the throw distribution `probs` and `throws_left` throws left.
+// The entries are (filled above, new index, points, probability), where
+// the new index is ai in the layer above or bi in the layer below.
+fn turn_outcomes<const N: usize>(
+    na: usize,
+    nb: usize,
+    ai: usize,
+    bi: usize,
+    strats: &[Array3<u8>],
+    mut probs: Vec<f64>,
+    throws_left: usize,
+) -> Vec<(bool, usize, usize, f64)> {
+    let (above_lookup, below_lookup, below_pts_lookup) = lookups::<N>();
+    let reroll_matrices = &DICE_REROLL_MATRICES[N - 1];
+
+    for nt in (1..=throws_left).rev() {
+        let mut new_probs = vec![0.0; probs.len()];
+
+        for (ti, &p) in probs.iter().enumerate().filter(|(_, &p)| p > 0.0) {
+            let reroll = strats[nt][[ai, bi, ti]] as usize;
+
+            for (new_p, &r) in new_probs
+                .iter_mut()
+                .zip(reroll_matrices.slice(s![ti, reroll, ..]))
+            {
+                *new_p = p.mul_add(r as f64, *new_p);
+            }
+        }
+
+        probs = new_probs;
+    }
+
+    let mut outcomes: Vec<_> = probs
+        .iter()
+        .enumerate()
+        .filter(|(_, &p)| p > 0.0)
+        .map(|(ti, &p)| {
+            let cell = strats[0][[ai, bi, ti]] as usize;
+
+            if cell < 6 {
+                let [new_ai, points] =
+                    above_lookup[na][[ai, ti]][cell].unwrap();
+                (true, new_ai, points, p)
+            } else {
+                let new_bi = below_lookup[nb][[bi, cell - 6]].unwrap();
+                let points = below_pts_lookup[[ti, cell - 6]];
+                (false, new_bi, points, p)
+            }
+        })
+        .collect();
+
+    outcomes.sort_unstable_by_key(|&(above, i, points, _)| (above, i, points));
+
+    outcomes.dedup_by(|(a1, i1, pts1, p1), (a2, i2, pts2, p2)| {
+        if (a1, i1, pts1) == (a2, i2, pts2) {
+            *p2 += *p1;
+            true
+        } else {
+            false
+        }
+    });
+
+    outcomes
+}
+
+// Shifts the score distribution `from` by `points` and adds it with weight
+// `prob` into `to`.
+fn add_shifted(from: &[f32], to: &mut [f32], points: usize, prob: f64) {
+    let prob = prob as f32;
+
+    for (x, &y) in to[points..].iter_mut().zip(from) {
+        *x = prob.mul_add(y, *x);
+    }
+}
+
+fn propagate<const N: usize>(
+    na0: usize,
+    nb0: usize,
+    start_mass: Array3<f32>,
+    start: Option<(Vec<f64>, usize)>,
+) -> ScoreDistribution {
+    let n_below = cell_from_dice::<N>() - 6;
+    let n_scores = max_score::<N>() + 1;
+
+    let mut masses: Array2<Option<Array3<f32>>> =
+        Array2::from_elem([7, n_below + 1], None);
+
+    masses[[na0, nb0]] = Some(start_mass);
+
+    let mut start = start;
+
+    let mut final_pmf = vec![0.0; n_scores];
+
+    for na in na0..=6 {
+        for nb in nb0..=n_below {
+            let Some(mass) = masses[[na, nb]].take() else {
+                continue;
+            };
+
+            if na + nb == 6 + n_below {
+                let pmf = mass.sum_axis(Axis(0)).sum_axis(Axis(0));
+
+                for (p, &m) in final_pmf.iter_mut().zip(&pmf) {
+                    *p += m as f64;
+                }
+                continue;
+            }
+
+            println!("Propagating na: {na:2}, nb: {nb:2}");
+
+            let strats: Vec<_> = (0..=rerolls::<N>())
+                .map(|nt| {
+                    let mut layer = Layer::<N, false> {
+                        na,
+                        nb,
+                        nt,
+                        scores: None,
+                        strats: None,
+                    };
+                    layer.load_strats().expect("Strategy is not computed!");
+                    layer.strats.unwrap()
+                })
+                .collect();
+
+            let [n_ai, n_bi, _] = layer_shape::<N>(na, nb);
+
+            let mut above_mass = (na < 6).then(|| {
+                masses[[na + 1, nb]].take().unwrap_or_else(|| {
+                    Array3::zeros([
+                        layer_shape::<N>(na + 1, nb)[0],
+                        n_bi,
+                        n_scores,
+                    ])
+                })
+            });
+            let mut below_mass = (nb < n_below).then(|| {
+                masses[[na, nb + 1]].take().unwrap_or_else(|| {
+                    Array3::zeros([
+                        n_ai,
+                        layer_shape::<N>(na, nb + 1)[1],
+                        n_scores,
+                    ])
+                })
+            });
+
+            let turn_start = start.take();
+
+            // Finding where each state ends up after the turn is independent
+            // of the score, so this is done in parallel before moving the
+            // score distributions.
+            let outcomes: Vec<_> = (0..n_ai * n_bi)
+                .into_par_iter()
+                .map(|i| {
+                    let (ai, bi) = (i / n_bi, i % n_bi);
+
+                    if mass.slice(s![ai, bi, ..]).iter().all(|&x| x == 0.0) {
+                        return Vec::new();
+                    }
+
+                    let (probs, throws_left) = match &turn_start {
+                        Some((probs, throws_left)) => {
+                            (probs.clone(), *throws_left)
+                        }
+                        None => (throw_probs::<N>(), rerolls::<N>()),
+                    };
+
+                    turn_outcomes::<N>(
+                        na,
+                        nb,
+                        ai,
+                        bi,
+                        &strats,
+                        probs,
+                        throws_left,
+                    )
+                })
+                .collect();
+
+            for (i, outcomes) in outcomes.into_iter().enumerate() {
+                let (ai, bi) = (i / n_bi, i % n_bi);
+
+                let from = mass.slice(s![ai, bi, ..]);
+                let from = from.as_slice().unwrap();
+
+                // Only the range of scores with any mass needs to be moved.
+                let lo = from.iter().position(|&x| x != 0.0).unwrap_or(0);
+                let hi =
+                    from.iter().rposition(|&x| x != 0.0).map_or(0, |i| i + 1);
+                let from = &from[lo..hi.max(lo)];
+
+                for (above, new_i, points, p) in outcomes {
+                    let mut to = if above {
+                        above_mass.as_mut().unwrap().slice_mut(s![
+                            new_i,
+                            bi,
+                            ..
+                        ])
+                    } else {
+                        below_mass.as_mut().unwrap().slice_mut(s![
+                            ai,
+                            new_i,
+                            ..
+                        ])
+                    };
+
+                    add_shifted(
+                        from,
+                        to.as_slice_mut().unwrap(),
+                        points + lo,
+                        p,
+                    );
+                }
+            }
+
+            if na < 6 {
+                masses[[na + 1, nb]] = above_mass;
+            }
+            if nb < n_below {
+                masses[[na, nb + 1]] = below_mass;
+            }
+        }
+    }
+
+    ScoreDistribution { pmf: final_pmf }
+}
+
+// The final score distribution from the start of the game.
+pub fn distribution_from_start<const N: usize>() -> ScoreDistribution {
+    let mut mass = Array3::zeros([1, 1, max_score::<N>() + 1]);
+    mass[[0, 0, 0]] = 1.0;
+
+    propagate::<N>(0, 0, mass, None)
+}
+
+// The final score distribution from a state in the middle of the game, given
+// by the filled cells, the points above the line, the total score so far,
+// the current dice and the amount of throws left.
+pub fn distribution_from_state<const N: usize>(
+    cells: &[bool],
+    points_above: usize,
+    score: usize,
+    dice: &DiceThrow,
+    throws_left: usize,
+) -> ScoreDistribution {
+    let [na, nb, la, lb, ai, bi] = get_state_indices::<N>(cells, points_above);
+
+    let n_scores = max_score::<N>() + 1;
+
+    let mut mass = Array3::zeros([la, lb, n_scores]);
+    mass[[ai, bi, score.min(n_scores - 1)]] = 1.0;
+
+    let mut probs = vec![0.0; throw_probs::<N>().len()];
+    probs[dice.get_index()] = 1.0;
+
+    propagate::<N>(na, nb, mass, Some((probs, throws_left)))
+}
@@ -0,0 +1,357 @@
+// This is the module for the heads-up endgame, where two players both only
+// have a few open cells left and all that matters is who wins. Maximizing the
+// expected score is then a poor strategy, for example when you are far behind
+// and need the yatzy to have any chance at all.
+//
+// The joint state is the open cells and points above the line of both
+// players, together with the lead of the player to move. With only a handful
+// of open cells in total there are few enough joint states to solve them on
+// demand, and the win probabilities are memoized between queries.
+
+use std::{cmp::Ordering, collections::HashMap};
+
+use ndarray::{Array1, Array2};
+
+use crate::{
+    dice_distributions::DICE_REROLL_MATRICES,
+    dice_throw::DiceThrow,
+    distribution::throws,
+    level_ordering::points_above,
+    macrosolver::{outcore::rerolls, store::Strategy},
+    rules::RuleSet,
+    solver::throw_probs,
+    with_rules,
+    yatzy::{cell_from_dice, State},
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+struct Player {
+    open: u32,
+    points_above: usize,
+}
+
+// The best choices and win probabilities for every throw during a turn,
+// indexed by the amount of throws left.
+struct Turn {
+    values: Vec<Array1<f32>>,
+    cells: Vec<usize>,
+    rerolls: Vec<Vec<u8>>,
+}
+
+pub struct Endgame<const N: usize> {
+    memo: HashMap<(Player, Player, i32), f64>,
+    throws: Vec<DiceThrow>,
+    probs: Vec<f64>,
+}
+
+impl<const N: usize> Default for Endgame<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> Endgame<N> {
+    pub fn new() -> Self {
+        Self {
+            memo: HashMap::new(),
+            throws: throws::<N>(),
+            probs: throw_probs::<N>(),
+        }
+    }
+
+    fn player(state: &State<N>) -> Player {
+        let open = state.open_cells().fold(0, |open, i| open | 1 << i);
+
+        Player {
+            open,
+            points_above: state.points_above.min(points_above::<N>()),
+        }
+    }
+
+    // Fills `cell` with the dice `ti`, giving the new player and the points
+    // scored, including the bonus if it is reached.
+    fn fill(&self, player: Player, cell: usize, ti: usize) -> (Player, i32) {
+        let mut points = self.throws[ti].cell_score::<N>(cell);
+
+        let mut new_player = Player {
+            open: player.open & !(1 << cell),
+            ..player
+        };
+
+        if cell < 6 {
+            let goal = points_above::<N>();
+
+            new_player.points_above = (player.points_above + points).min(goal);
+
+            if player.points_above < goal && new_player.points_above == goal {
+                points += with_rules!(N, R => R::BONUS);
+            }
+        }
+
+        (new_player, points as i32)
+    }
+
+    // The probability that the player to move wins at the start of their
+    // turn, where a tie counts as half a win. A player with no open cells
+    // just passes the turn.
+    fn value(&mut self, mover: Player, other: Player, lead: i32) -> f64 {
+        if mover.open == 0 {
+            if other.open == 0 {
+                return match lead.cmp(&0) {
+                    Ordering::Greater => 1.0,
+                    Ordering::Equal => 0.5,
+                    Ordering::Less => 0.0,
+                };
+            }
+
+            return 1.0 - self.value(other, mover, -lead);
+        }
+
+        if let Some(&value) = self.memo.get(&(mover, other, lead)) {
+            return value;
+        }
+
+        let turn = self.turn(mover, other, lead);
+
+        let value = self
+            .probs
+            .iter()
+            .zip(&turn.values[rerolls::<N>()])
+            .map(|(&p, &x)| p * x as f64)
+            .sum();
+
+        self.memo.insert((mover, other, lead), value);
+
+        value
+    }
+
+    fn turn(&mut self, mover: Player, other: Player, lead: i32) -> Turn {
+        let n_throws = self.throws.len();
+        let n_rerolls = 1 << N;
+
+        let mut cell_values = Array1::from_elem(n_throws, f32::MIN);
+        let mut cells = vec![0; n_throws];
+
+        for cell in
+            (0..cell_from_dice::<N>()).filter(|c| mover.open >> c & 1 != 0)
+        {
+            for ti in 0..n_throws {
+                let (new_mover, points) = self.fill(mover, cell, ti);
+
+                let value =
+                    1.0 - self.value(other, new_mover, -(lead + points)) as f32;
+
+                if value > cell_values[ti] {
+                    cell_values[ti] = value;
+                    cells[ti] = cell;
+                }
+            }
+        }
+
+        let reroll_matrix = DICE_REROLL_MATRICES[N - 1]
+            .view()
+            .into_shape([n_throws * n_rerolls, n_throws])
+            .unwrap();
+
+        let mut values = vec![cell_values];
+        let mut reroll_masks = vec![Vec::new()];
+
+        for nt in 1..=rerolls::<N>() {
+            let expected: Array2<f32> = reroll_matrix
+                .dot(&values[nt - 1])
+                .into_shape([n_throws, n_rerolls])
+                .unwrap();
+
+            let (best, masks) = expected
+                .rows()
+                .into_iter()
+                .map(|row| {
+                    row.iter().enumerate().fold(
+                        (f32::MIN, 0),
+                        |(best, mask), (i, &x)| {
+                            if x > best {
+                                (x, i as u8)
+                            } else {
+                                (best, mask)
+                            }
+                        },
+                    )
+                })
+                .unzip::<_, _, Vec<_>, _>();
+
+            values.push(Array1::from(best));
+            reroll_masks.push(masks);
+        }
+
+        Turn {
+            values,
+            cells,
+            rerolls: reroll_masks,
+        }
+    }
+
+    // The move maximizing the probability that `me` beats `opponent`, given
+    // the current dice and throws left, along with that probability. `lead`
+    // is the total score of `me` minus that of `opponent`, and it is the
+    // turn of `me`. Ties count as half a win.
+    pub fn best_move(
+        &mut self,
+        me: &State<N>,
+        opponent: &State<N>,
+        lead: i32,
+        dice: &DiceThrow,
+        throws_left: usize,
+    ) -> (Strategy, f64) {
+        let me = Self::player(me);
+        let opponent = Self::player(opponent);
+
+        assert!(me.open != 0, "No open cells left!");
+
+        let turn = self.turn(me, opponent, lead);
+        let ti = dice.get_index();
+
+        let strategy = if throws_left == 0 {
+            Strategy::Cell(turn.cells[ti])
+        } else {
+            Strategy::Rethrow(turn.rerolls[throws_left][ti])
+        };
+
+        (strategy, turn.values[throws_left][ti] as f64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{array, collections::HashMap};
+
+    use itertools::Itertools;
+
+    use super::*;
+    use crate::dice_distributions::DICE_PROBS;
+
+    fn win(lead: i32) -> f64 {
+        match lead.cmp(&0) {
+            Ordering::Greater => 1.0,
+            Ordering::Equal => 0.5,
+            Ordering::Less => 0.0,
+        }
+    }
+
+    // The best probability of winning with `dice` and `throws_left` throws
+    // left, found by looping over the dice to keep and the throws of the
+    // rest, where `end` gives the probability of winning with the final dice.
+    // The probabilities after keeping some dice are memoized in `kept_memo`.
+    fn brute_turn(
+        dice: DiceThrow,
+        throws_left: usize,
+        end: &dyn Fn(&DiceThrow) -> f64,
+        kept_memo: &mut HashMap<(DiceThrow, usize), f64>,
+    ) -> f64 {
+        if throws_left == 0 {
+            return end(&dice);
+        }
+
+        dice.into_sub_throw_iter()
+            .map(|kept| {
+                if let Some(&value) = kept_memo.get(&(kept, throws_left)) {
+                    return value;
+                }
+
+                let n_rerolled = 5 - kept.amt_dice();
+
+                let value = DICE_PROBS[n_rerolled]
+                    .iter()
+                    .enumerate()
+                    .map(|(ri, &prob)| {
+                        let rethrow = DiceThrow::from_index(n_rerolled, ri);
+                        let new_throw =
+                            DiceThrow::from(array::from_fn::<_, 6, _>(|i| {
+                                kept[i + 1] + rethrow[i + 1]
+                            }));
+
+                        prob * brute_turn(
+                            new_throw,
+                            throws_left - 1,
+                            end,
+                            kept_memo,
+                        )
+                    })
+                    .sum::<f64>();
+
+                kept_memo.insert((kept, throws_left), value);
+
+                value
+            })
+            .fold(f64::NEG_INFINITY, f64::max)
+    }
+
+    // The probability that a player with only `cell` open wins from the start
+    // of the last turn of the game, when they lead by `lead`.
+    fn last_turn(cell: usize, lead: i32) -> f64 {
+        let end =
+            |dice: &DiceThrow| win(lead + dice.cell_score::<5>(cell) as i32);
+        let mut memo = HashMap::new();
+
+        DICE_PROBS[5]
+            .iter()
+            .enumerate()
+            .map(|(ti, &prob)| {
+                let dice = DiceThrow::from_index(5, ti);
+
+                prob * brute_turn(dice, rerolls::<5>(), &end, &mut memo)
+            })
+            .sum()
+    }
+
+    // Checks the endgame where the player to move only has `cell` open and
+    // the opponent only has `other_cell` open against looping over every
+    // choice of both players.
+    fn check_one_cell_each(cell: usize, other_cell: usize, lead: i32) {
+        let only_open = |cell: usize| {
+            State::<5>::from_masks(
+                0b111111,
+                0b111111111 & !(1 << (cell - 6)),
+                0,
+            )
+        };
+
+        let me = only_open(cell);
+        let opponent = only_open(other_cell);
+
+        let other_wins: HashMap<usize, f64> = (0..DICE_PROBS[5].len())
+            .map(|ti| DiceThrow::from_index(5, ti).cell_score::<5>(cell))
+            .unique()
+            .map(|points| {
+                (points, last_turn(other_cell, -(lead + points as i32)))
+            })
+            .collect();
+
+        let end =
+            |dice: &DiceThrow| 1.0 - other_wins[&dice.cell_score::<5>(cell)];
+        let mut memo = HashMap::new();
+
+        let mut endgame = Endgame::<5>::new();
+
+        for ti in (0..DICE_PROBS[5].len()).step_by(25) {
+            let dice = DiceThrow::from_index(5, ti);
+
+            for throws_left in 0..=rerolls::<5>() {
+                let (_, value) =
+                    endgame.best_move(&me, &opponent, lead, &dice, throws_left);
+                let expected = brute_turn(dice, throws_left, &end, &mut memo);
+
+                assert!((value - expected).abs() < 1e-5);
+            }
+        }
+    }
+
+    #[test]
+    fn four_of_a_kind_against_yatzy() {
+        check_one_cell_each(9, 14, -10);
+    }
+
+    #[test]
+    fn large_straight_against_four_of_a_kind() {
+        check_one_cell_each(11, 9, -5);
+    }
+}
@@ -1,15 +1,20 @@
-use std::{
-    fs::OpenOptions,
-    io::{stdin, stdout, Read, Seek, SeekFrom, Write},
-};
+use std::io::{stdin, stdout, Write};
 
 use crate::{
-    dice_distributions::amt_dice_combinations,
+    american::{AmericanState, AMERICAN_CELLS, YAHTZEE_CELL},
     dice_throw::DiceThrow,
-    level_ordering::{
-        ABOVE_LEVELS_5, ABOVE_LEVELS_6, BELOW_LEVELS_5, BELOW_LEVELS_6,
+    distribution::distribution_from_state,
+    endgame::Endgame,
+    macrosolver::{
+        american::AmericanLayer,
+        forced::ForcedLayer,
+        outcore::{layer_shape, next_turn_throws, rerolls, RISK},
+        store::{LayerStore, Strategy, StrategyStore},
+        target::TargetLayer,
     },
-    macrosolver::outcore::Layer,
+    rescore::Rescorer,
+    rules::{RuleSet, Yahtzee},
+    with_rules,
     yatzy::{cell_from_dice, State},
 };
 
@@ -26,8 +31,26 @@ clear points <cell>: clears points
 advise/a <dice-left> <dice>: gives advice on what to do with the dice
 throw <N>: prints a dice throw of <N> dice
 auto: automatically perform the next optimal move
+target <score>: play to maximize the probability of reaching a total of
+    <score> when endgame strategies are available (not for saved throws)
+target off: go back to maximizing the expected score
+spread/sd: expected remaining score with standard deviation and skewness
+distribution/dist <score>: exact chance of finishing with at least <score>
+    points from here (not for saved throws, slow early in the game)
+endgame <score> <above> <cells>: the move that maximizes the chance of
+    beating an opponent with total <score>, <above> points above the line
+    and the open cells <cells>, when you have few open cells in total
+endgame limit <n>: set the most open cells in total for the endgame
 "#;
 
+// The default for the most open cells of both players in total where the
+// guide offers the heads-up endgame solver.
+const ENDGAME_MAX_OPEN: usize = 6;
+
+// The most open cells where the guide finds the expected score from the
+// strategies when the scores are pruned from the cache.
+const RESCORE_MAX_OPEN: usize = 6;
+
 const HELP_CELL_NAMES: &str = r#"
 ones/enere...       => 1s - 6s
 pairs/par           => 1p - 3p
@@ -64,27 +87,10 @@ fn display_points<const N: usize>(
     println!("sixes             (6s) = {}", tostr(points, ind));
     println!("------------------------------------");
     let above: usize = points.iter().take(6).filter_map(|x| x.as_ref()).sum();
-    let bonus_objective = match N {
-        5 => 63,
-        6 => 84,
-        _ => unreachable!(),
-    };
-    let bonus_objective: usize = (0..bonus_objective).map(|_| 1).sum();
-
-    let bonus = if above >= bonus_objective {
-        match N {
-            5 => 50,
-            6 => 100,
-            _ => unreachable!(),
-        }
-    } else {
-        0
-    };
-
-    let bonus: usize = if let Some(b) = prec_bonus {
+    let bonus = if let Some(b) = prec_bonus {
         b
     } else {
-        (0..bonus).map(|_| 1).sum()
+        with_rules!(N, R => R::bonus(above))
     };
 
     println!("sum                    = {}", above);
@@ -123,87 +129,25 @@ fn display_points<const N: usize>(
 }
 
 fn get_yatzy_index<const N: usize>(name: &str) -> Option<usize> {
-    match (N, name) {
-        (_, "1s") => Some(0),
-        (_, "2s") => Some(1),
-        (_, "3s") => Some(2),
-        (_, "4s") => Some(3),
-        (_, "5s") => Some(4),
-        (_, "6s") => Some(5),
-        (_, "1p") => Some(6),
-        (_, "2p") => Some(7),
-        (6, "3p") => Some(8),
-        (5, "3l") => Some(8),
-        (6, "3l") => Some(9),
-        (5, "4l") => Some(9),
-        (6, "4l") => Some(10),
-        (6, "5l") => Some(11),
-        (5, "ls") => Some(10),
-        (6, "ls") => Some(12),
-        (5, "ss") => Some(11),
-        (6, "ss") => Some(13),
-        (6, "fs") => Some(14),
-        (5, "hs") => Some(12),
-        (6, "ht") => Some(15),
-        (6, "hs") => Some(16),
-        (6, "tr") => Some(17),
-        (5, "ch" | "sj") => Some(13),
-        (6, "ch" | "sj") => Some(18),
-        (5, "yz") => Some(14),
-        (6, "yz") => Some(19),
-        _ => None,
-    }
+    with_rules!(N, R => R::cell_index(name))
 }
 
 fn get_index_name<const N: usize>(ind: usize) -> &'static str {
-    match (N, ind) {
-        (_, 0) => "ones",
-        (_, 1) => "twos",
-        (_, 2) => "threes",
-        (_, 3) => "fours",
-        (_, 4) => "fives",
-        (_, 5) => "sixes",
-        (_, 6) => "1 pair",
-        (_, 7) => "2 pairs",
-        (6, 8) => "3 pairs",
-        (5, 8) | (6, 9) => "3 of a kind",
-        (5, 9) | (6, 10) => "4 of a kind",
-        (6, 11) => "5 of a kind",
-        (5, 10) | (6, 12) => "small straight",
-        (5, 11) | (6, 13) => "large straight",
-        (6, 14) => "full straight",
-        (6, 15) => "hut",
-        (5, 12) | (6, 16) => "house",
-        (6, 17) => "tower",
-        (5, 13) | (6, 18) => "chance",
-        (5, 14) | (6, 19) => "yatzy",
-        _ => unreachable!(),
-    }
-}
-
-pub fn get_state_indices5(cells: &[bool], points_above: usize) -> [usize; 6] {
-    let state = State::<15>::from_dyn(cells, points_above);
-
-    let na = state.get_n_above();
-    let nb = state.get_n_below();
-
-    let la = ABOVE_LEVELS_5[na].len();
-    let lb = BELOW_LEVELS_5[nb].len();
-
-    let ai = state.get_above_index();
-    let bi = state.get_below_index();
-
-    [na, nb, la, lb, ai, bi]
+    with_rules!(N, R => R::cell_name(ind))
 }
 
-pub fn get_state_indices6(cells: &[bool], points_above: usize) -> [usize; 6] {
-    let state = State::<20>::from_dyn(cells, points_above);
+// The filled cells above and below the line, the size of their levels and
+// the index of the state in each level.
+pub fn get_state_indices<const N: usize>(
+    cells: &[bool],
+    points_above: usize,
+) -> [usize; 6] {
+    let state = State::<N>::from_dyn(cells, points_above);
 
     let na = state.get_n_above();
     let nb = state.get_n_below();
 
-    let la = ABOVE_LEVELS_6[na].len();
-    let lb = BELOW_LEVELS_6[nb].len();
+    let [la, lb, _] = layer_shape::<N>(na, nb);
 
     let ai = state.get_above_index();
     let bi = state.get_below_index();
@@ -211,219 +155,166 @@ pub fn get_state_indices6(cells: &[bool], points_above: usize) -> [usize; 6] {
     [na, nb, la, lb, ai, bi]
 }
 
-fn get_byte_from_file(filename: &str, index: usize) -> Option<u8> {
-    let mut file = OpenOptions::new().read(true).open(filename).ok()?;
-
-    file.seek(SeekFrom::Start(index as u64)).ok()?;
-
-    let mut buf = [0];
-
-    file.read_exact(&mut buf).ok()?;
-
-    Some(buf[0])
-}
-
-fn get_float_from_file(filename: &str, index: usize) -> Option<f32> {
-    let mut file = OpenOptions::new().read(true).open(filename).ok()?;
-
-    file.seek(SeekFrom::Start((index * 4) as u64)).ok()?;
-
-    let mut buf = [0; 4];
-
-    file.read_exact(&mut buf).ok()?;
-
-    Some(f32::from_le_bytes(buf))
-}
-
-fn get_cell_strat<const N: usize>(
+// The target layer and the index of the state in it.
+fn get_target_index<const N: usize>(
     cells: &[bool],
     dice: &DiceThrow,
+    throws_left: usize,
     points_above: usize,
-) -> Option<usize>
-where
-    [(); cell_from_dice::<N>()]:,
-    [(); cell_from_dice::<N>() - 6]:,
-{
-    let [na, nb, _, lb, ai, bi] = match N {
-        5 => get_state_indices5(cells, points_above),
-        6 => get_state_indices6(cells, points_above),
-        _ => panic!(),
-    };
-
-    let lt = amt_dice_combinations::<N>();
-    let ti = dice.get_index();
-
-    let total_index = (ai * lb + bi) * lt + ti;
+    need: usize,
+) -> (TargetLayer<N>, [usize; 3]) {
+    let [na, nb, _, _, ai, bi] = get_state_indices::<N>(cells, points_above);
 
-    let layer = Layer::<N, false> {
+    let layer = TargetLayer::<N> {
         na,
         nb,
-        nt: 0,
-        scores: None,
+        nt: throws_left,
+        need,
+        probs: None,
         strats: None,
     };
 
-    get_byte_from_file(&layer.strats_path(), total_index).map(|x| x as usize)
+    (layer, [ai, bi, dice.get_index()])
 }
 
-fn get_rethrow_strat<const N: usize>(
+fn get_target_byte<const N: usize>(
+    store: &LayerStore,
     cells: &[bool],
     dice: &DiceThrow,
     throws_left: usize,
     points_above: usize,
+    need: usize,
 ) -> Option<u8> {
-    let [na, nb, _, lb, ai, bi] = match N {
-        5 => get_state_indices5(cells, points_above),
-        6 => get_state_indices6(cells, points_above),
-        _ => panic!(),
-    };
-
-    let lt = amt_dice_combinations::<N>();
-    let ti = dice.get_index();
-
-    let total_index = (ai * lb + bi) * lt + ti;
-
-    let layer = Layer::<N, false> {
-        na,
-        nb,
-        nt: throws_left,
-        scores: None,
-        strats: None,
-    };
+    let (layer, index) =
+        get_target_index::<N>(cells, dice, throws_left, points_above, need);
 
-    get_byte_from_file(&layer.strats_path(), total_index)
+    store.byte(layer.id().index, index)
 }
 
-pub enum Strategy {
-    Rethrow(u8),
-    Cell(usize),
-}
-
-fn get_combined_strat<const N: usize>(
+fn get_target_prob<const N: usize>(
+    store: &LayerStore,
     cells: &[bool],
     dice: &DiceThrow,
     throws_left: usize,
     points_above: usize,
-) -> Option<Strategy> {
-    let [na, nb, _, lb, ai, bi] = match N {
-        5 => get_state_indices5(cells, points_above),
-        6 => get_state_indices6(cells, points_above),
-        _ => panic!(),
-    };
+    need: usize,
+) -> Option<f32> {
+    let (layer, index) =
+        get_target_index::<N>(cells, dice, throws_left, points_above, need);
 
-    let lt = amt_dice_combinations::<N>();
-    let ti = dice.get_index();
+    store.float(layer.id().index, index)
+}
 
-    let total_index = (ai * lb + bi) * lt + ti;
+pub fn get_total_score<const N: usize>(points: &[Option<usize>]) -> usize {
+    let points_above: usize =
+        points.iter().take(6).filter_map(|x| x.as_ref()).sum();
 
-    let layer = Layer::<N, true> {
-        na,
-        nb,
-        nt: throws_left,
-        scores: None,
-        strats: None,
-    };
+    let bonus = with_rules!(N, R => R::bonus(points_above));
 
-    get_byte_from_file(&layer.strats_path(), total_index).map(|byte| {
-        if (byte & 128) != 0 {
-            Strategy::Rethrow(byte & !128)
-        } else {
-            Strategy::Cell(byte as usize)
-        }
-    })
+    let total = bonus + points.iter().filter_map(|x| x.as_ref()).sum::<usize>();
+
+    total
 }
 
-fn get_score<const N: usize, const X: bool>(
+// The expected remaining score, found from the strategies when the scores
+// are pruned and there are few enough open cells. With risk the scores are
+// certainty equivalents, which can not be found that way.
+fn get_expected_remaining<const N: usize, const X: bool>(
+    store: &StrategyStore<N, X>,
+    rescorer: &mut Rescorer<N, X>,
     cells: &[bool],
     dice: &DiceThrow,
     points_above: usize,
     throws_left: usize,
 ) -> Option<f32> {
-    let [na, nb, _, lb, ai, bi] = match N {
-        5 => get_state_indices5(cells, points_above),
-        6 => get_state_indices6(cells, points_above),
-        _ => panic!(),
-    };
-
-    let lt = amt_dice_combinations::<N>();
-    let ti = dice.get_index();
-
-    let total_index = (ai * lb + bi) * lt + ti;
-
-    let layer = Layer::<N, X> {
-        na,
-        nb,
-        nt: throws_left,
-        scores: None,
-        strats: None,
-    };
-
-    get_float_from_file(&layer.scores_path(), total_index)
-}
-
-pub fn get_total_score<const N: usize>(points: &[Option<usize>]) -> usize {
-    let points_above: usize =
-        points.iter().take(6).filter_map(|x| x.as_ref()).sum();
-
-    let bonus_objective = match N {
-        5 => 63,
-        6 => 84,
-        _ => unreachable!(),
-    };
-
-    let bonus = if points_above >= bonus_objective {
-        match N {
-            5 => 50,
-            6 => 100,
-            _ => unreachable!(),
-        }
-    } else {
-        0
-    };
+    store
+        .score(cells, dice, points_above, throws_left)
+        .or_else(|| {
+            let n_open = cells.iter().filter(|&&filled| !filled).count();
 
-    let total = bonus + points.iter().filter_map(|x| x.as_ref()).sum::<usize>();
+            if RISK.get().is_some() || n_open > RESCORE_MAX_OPEN {
+                return None;
+            }
 
-    total
+            rescorer.score(store, cells, dice, points_above, throws_left)
+        })
 }
 
-pub fn start<const N: usize, const X: bool>()
-where
-    [(); cell_from_dice::<N>()]:,
-    [(); cell_from_dice::<N>() - 6]:,
-{
+pub fn start<const N: usize, const X: bool>() {
     println!(
         "Welcome to the interactive guide of a free game with {} dice",
         N
     );
 
-    let mut points = vec![
-        None;
-        match N {
-            5 => 15,
-            6 => 20,
-            _ => unreachable!(),
-        }
-    ];
+    let mut points = vec![None; cell_from_dice::<N>()];
+
+    // The throws left at the start of a turn.
+    let rerolls = if X { 2 } else { rerolls::<N>() };
 
     let mut dice = DiceThrow::throw(N);
-    let mut throws_left = 2;
+    let mut throws_left = rerolls;
+    let mut target: Option<usize> = None;
+    let mut endgame = Endgame::<N>::new();
+    let mut endgame_max_open = ENDGAME_MAX_OPEN;
+
+    let store = StrategyStore::<N, X>::open();
+    let target_store = LayerStore::target::<N>();
+    let mut rescorer = Rescorer::<N, X>::new();
+
+    if let Some(lambda) = RISK.get() {
+        println!("Maximizing E[-exp(-λ·score)] with λ = {lambda}");
+        println!("Scores are certainty equivalents, not expected scores");
+    }
 
     println!("Starting throw:\n{}", dice);
 
     'outer: loop {
         println!("Throws left: {throws_left}");
 
+        // Points still needed to reach the target, if one is set and it is
+        // not already reached.
+        let need = target
+            .filter(|_| !X)
+            .map(|t| t.saturating_sub(get_total_score::<N>(&points)))
+            .filter(|&need| need > 0);
+
         let filled_cells: Vec<_> = points.iter().map(|x| x.is_some()).collect();
         let points_above =
             points.iter().take(6).filter_map(|x| x.as_ref()).sum();
-        if let Some(rem_score) =
-            get_score::<N, X>(&filled_cells, &dice, points_above, throws_left)
-        {
+        if let Some(rem_score) = get_expected_remaining(
+            &store,
+            &mut rescorer,
+            &filled_cells,
+            &dice,
+            points_above,
+            throws_left,
+        ) {
             let tot_score = get_total_score::<N>(&points) as f32 + rem_score;
 
             println!("expected total score is now {:.2}", tot_score);
         }
 
+        if let Some(need) = need {
+            let filled_cells: Vec<_> =
+                points.iter().map(|x| x.is_some()).collect();
+            let points_above =
+                points.iter().take(6).filter_map(|x| x.as_ref()).sum();
+            if let Some(prob) = get_target_prob::<N>(
+                &target_store,
+                &filled_cells,
+                &dice,
+                throws_left,
+                points_above,
+                need,
+            ) {
+                println!(
+                    "probability of reaching {} is now {:.2}%",
+                    target.unwrap(),
+                    prob * 100.0
+                );
+            }
+        }
+
         print!("> ");
         stdout().flush().unwrap();
         let mut buffer = String::new();
@@ -451,9 +342,9 @@ where
                     points[index] = Some(pts);
                     display_points::<N>(&points, None, None);
                     if X {
-                        throws_left += 2;
+                        throws_left = next_turn_throws(throws_left);
                     } else {
-                        throws_left = 2;
+                        throws_left = rerolls;
                     }
                     dice = DiceThrow::throw(N);
                     println!("Current dice:\n{}", dice);
@@ -482,11 +373,11 @@ where
                     points.iter().take(6).filter_map(|x| x.as_ref()).sum();
 
                 if X {
-                    match get_combined_strat::<N>(
+                    match store.strategy(
                         &filled_cells,
                         &dice,
-                        throws_left,
                         points_above,
+                        throws_left,
                     ) {
                         Some(Strategy::Cell(ind)) => {
                             let score = dice.cell_score::<N>(ind);
@@ -501,7 +392,7 @@ where
                             display_points::<N>(&points, None, None);
 
                             dice = DiceThrow::throw(N);
-                            throws_left += 2;
+                            throws_left = next_turn_throws(throws_left);
 
                             println!("New throw:\n{}", dice);
                         }
@@ -529,8 +420,21 @@ where
                         }
                     }
                 } else if throws_left == 0 {
+                    let target_cell = need.and_then(|need| {
+                        get_target_byte::<N>(
+                            &target_store,
+                            &filled_cells,
+                            &dice,
+                            throws_left,
+                            points_above,
+                            need,
+                        )
+                    });
+
                     if let Some(ind) =
-                        get_cell_strat::<N>(&filled_cells, &dice, points_above)
+                        target_cell.map(|x| x as usize).or_else(|| {
+                            store.cell(&filled_cells, &dice, points_above)
+                        })
                     {
                         let score = dice.cell_score::<N>(ind);
 
@@ -544,16 +448,30 @@ where
                         display_points::<N>(&points, None, None);
 
                         dice = DiceThrow::throw(N);
-                        throws_left = 2;
+                        throws_left = rerolls;
 
                         println!("New throw:\n{}", dice);
                     }
-                } else if let Some(reroll) = get_rethrow_strat::<N>(
-                    &filled_cells,
-                    &dice,
-                    throws_left,
-                    points_above,
-                ) {
+                } else if let Some(reroll) = need
+                    .and_then(|need| {
+                        get_target_byte::<N>(
+                            &target_store,
+                            &filled_cells,
+                            &dice,
+                            throws_left,
+                            points_above,
+                            need,
+                        )
+                    })
+                    .or_else(|| {
+                        store.rethrow(
+                            &filled_cells,
+                            &dice,
+                            points_above,
+                            throws_left,
+                        )
+                    })
+                {
                     println!("Rethrowing:\n{}", dice.get_subthrow(reroll));
 
                     let rethrow =
@@ -589,13 +507,24 @@ where
                 let points_above =
                     points.iter().take(6).filter_map(|x| x.as_ref()).sum();
 
+                let target_byte = need.and_then(|need| {
+                    get_target_byte::<N>(
+                        &target_store,
+                        &filled_cells,
+                        &throw,
+                        throws_left,
+                        points_above,
+                        need,
+                    )
+                });
+
                 match (throws_left, X) {
                     (0, false) => {
-                        if let Some(ind) = get_cell_strat::<N>(
-                            &filled_cells,
-                            &throw,
-                            points_above,
-                        ) {
+                        if let Some(ind) =
+                            target_byte.map(|x| x as usize).or_else(|| {
+                                store.cell(&filled_cells, &throw, points_above)
+                            })
+                        {
                             let score = throw.cell_score::<N>(ind);
 
                             println!(
@@ -608,12 +537,14 @@ where
                         }
                     }
                     (1 | 2, false) => {
-                        if let Some(reroll) = get_rethrow_strat::<N>(
-                            &filled_cells,
-                            &throw,
-                            throws_left,
-                            points_above,
-                        ) {
+                        if let Some(reroll) = target_byte.or_else(|| {
+                            store.rethrow(
+                                &filled_cells,
+                                &throw,
+                                points_above,
+                                throws_left,
+                            )
+                        }) {
                             println!(
                                 "Rethrow:\n{}",
                                 throw.get_subthrow(reroll)
@@ -622,11 +553,11 @@ where
                             println!("Strategy not available for state.");
                         }
                     }
-                    (_, true) => match get_combined_strat::<N>(
+                    (_, true) => match store.strategy(
                         &filled_cells,
                         &throw,
-                        throws_left,
                         points_above,
+                        throws_left,
                     ) {
                         Some(Strategy::Cell(ind)) => {
                             let score = throw.cell_score::<N>(ind);
@@ -650,7 +581,9 @@ where
                     points.iter().map(|x| x.is_some()).collect();
                 let points_above =
                     points.iter().take(6).filter_map(|x| x.as_ref()).sum();
-                if let Some(rem_score) = get_score::<N, X>(
+                if let Some(rem_score) = get_expected_remaining(
+                    &store,
+                    &mut rescorer,
                     &filled_cells,
                     &dice,
                     points_above,
@@ -666,7 +599,9 @@ where
                     points.iter().map(|x| x.is_some()).collect();
                 let points_above =
                     points.iter().take(6).filter_map(|x| x.as_ref()).sum();
-                if let Some(rem_score) = get_score::<N, X>(
+                if let Some(rem_score) = get_expected_remaining(
+                    &store,
+                    &mut rescorer,
                     &filled_cells,
                     &dice,
                     points_above,
@@ -680,17 +615,832 @@ where
                     println!("Failed to read scores file");
                 }
             }
-            ["reset"] => {
-                points = vec![
-                    None;
-                    match N {
-                        5 => 15,
-                        6 => 20,
-                        _ => unreachable!(),
+            ["spread" | "sd"] => {
+                let filled_cells: Vec<_> =
+                    points.iter().map(|x| x.is_some()).collect();
+                let points_above =
+                    points.iter().take(6).filter_map(|x| x.as_ref()).sum();
+                if let Some([m1, m2, m3]) = store.moments(
+                    &filled_cells,
+                    &dice,
+                    points_above,
+                    throws_left,
+                ) {
+                    let (m1, m2, m3) = (m1 as f64, m2 as f64, m3 as f64);
+
+                    let var = (m2 - m1 * m1).max(0.0);
+                    let sd = var.sqrt();
+
+                    println!("expected remaining {m1:.1} ± {sd:.1}");
+
+                    if sd > 0.0 {
+                        let skew =
+                            (m3 - 3.0 * m1 * var - m1.powi(3)) / sd.powi(3);
+                        println!("skewness {skew:.2}");
+                    }
+                } else {
+                    println!("Failed to read moments files");
+                }
+            }
+            ["distribution" | "dist", score] => {
+                if X {
+                    println!(
+                        "Distributions are not supported with saved throws!"
+                    );
+                    continue 'outer;
+                }
+
+                let Ok(score) = score.parse() else {
+                    println!("Invalid score!");
+                    continue 'outer;
+                };
+
+                let filled_cells: Vec<_> =
+                    points.iter().map(|x| x.is_some()).collect();
+                let points_above =
+                    points.iter().take(6).filter_map(|x| x.as_ref()).sum();
+
+                let distr = distribution_from_state::<N>(
+                    &filled_cells,
+                    points_above,
+                    get_total_score::<N>(&points),
+                    &dice,
+                    throws_left,
+                );
+
+                println!(
+                    "expected total score is {:.2} ± {:.2}",
+                    distr.mean(),
+                    distr.std()
+                );
+                println!(
+                    "chance of getting at least {} is {:.2}%",
+                    score,
+                    distr.prob_at_least(score) * 100.0
+                );
+            }
+            ["endgame", "limit", n] => {
+                if let Ok(n) = n.parse() {
+                    endgame_max_open = n;
+                    println!(
+                        "Endgame solver is used with at most {n} open cells."
+                    );
+                } else {
+                    println!("Invalid amount of cells!");
+                }
+            }
+            ["endgame", score, above, cells @ ..] => {
+                if X {
+                    println!("The endgame is not supported with saved throws!");
+                    continue 'outer;
+                }
+
+                let (Ok(score), Ok(above)) =
+                    (score.parse::<i32>(), above.parse())
+                else {
+                    println!("Invalid score!");
+                    continue 'outer;
+                };
+
+                let mut opponent_cells = vec![true; points.len()];
+                for cell in cells {
+                    let Some(index) = get_yatzy_index::<N>(cell) else {
+                        println!("Invalid cell name!");
+                        continue 'outer;
+                    };
+                    opponent_cells[index] = false;
+                }
+
+                let filled_cells: Vec<_> =
+                    points.iter().map(|x| x.is_some()).collect();
+                let points_above =
+                    points.iter().take(6).filter_map(|x| x.as_ref()).sum();
+
+                let me = State::<N>::from_dyn(&filled_cells, points_above);
+                let opponent = State::<N>::from_dyn(&opponent_cells, above);
+
+                let n_open = 2 * points.len()
+                    - me.get_n_cells()
+                    - opponent.get_n_cells();
+
+                if n_open > endgame_max_open {
+                    println!(
+                        "Too many open cells for the endgame ({n_open} > {endgame_max_open})!"
+                    );
+                    continue 'outer;
+                }
+
+                if me.get_n_cells() == points.len() {
+                    println!("No open cells left!");
+                    continue 'outer;
+                }
+
+                let lead = get_total_score::<N>(&points) as i32 - score;
+
+                let (strategy, prob) =
+                    endgame.best_move(&me, &opponent, lead, &dice, throws_left);
+
+                match strategy {
+                    Strategy::Cell(ind) => println!(
+                        "Put {} points in {}.",
+                        dice.cell_score::<N>(ind),
+                        get_index_name::<N>(ind)
+                    ),
+                    Strategy::Rethrow(reroll) => {
+                        println!("Rethrow:\n{}", dice.get_subthrow(reroll))
                     }
-                ];
+                }
+
+                println!("chance of winning is {:.2}%", prob * 100.0);
+            }
+            ["target", "off"] => {
+                target = None;
+                println!("Maximizing expected score.");
+            }
+            ["target", score] => {
+                if X {
+                    println!("Targets are not supported with saved throws!");
+                    continue 'outer;
+                }
+
+                if let Ok(score) = score.parse() {
+                    target = Some(score);
+                    println!("Maximizing probability of reaching {score}.");
+                } else {
+                    println!("Invalid target score!");
+                }
+            }
+            ["reset"] => {
+                points = vec![None; cell_from_dice::<N>()];
+                dice = DiceThrow::throw(N);
+                throws_left = rerolls;
+
+                println!("Starting throw:\n{}", dice);
+            }
+            ["rethrow" | "rt", mask_str] => {
+                if throws_left == 0 {
+                    println!("No throws left!");
+                    continue 'outer;
+                }
+
+                if mask_str.len() != N {
+                    println!("Invalid mask!");
+                    continue 'outer;
+                }
+
+                let mut mask: u8 = 0;
+                for c in mask_str.chars().rev() {
+                    let bit = match c {
+                        '0' => 0,
+                        '1' => 1,
+                        _ => {
+                            println!("Invalid mask!");
+                            continue 'outer;
+                        }
+                    };
+
+                    mask = mask << 1 | bit;
+                }
+
+                let rethrow: Vec<_> =
+                    DiceThrow::throw(mask.count_ones() as usize)
+                        .into_ordered_dice()
+                        .collect();
+
+                dice = dice.overwrite_reroll_dyn::<N>(mask, &rethrow);
+                throws_left -= 1;
+
+                println!("New throw:\n{}", dice);
+            }
+            ["set", "dice", dice_str] => {
+                if dice_str.len() != N {
+                    continue 'outer;
+                }
+                let mut throw = DiceThrow::from([0usize; 6]);
+                for c in dice_str.chars() {
+                    let i = (c as u8 - b'0') as usize;
+                    throw[i] += 1;
+                }
+
+                dice = throw;
+                println!("Current dice:\n{}", dice);
+            }
+            _ => println!("Invalid command! {:?}", command),
+        }
+    }
+}
+
+const HELP_MSG_AMERICAN: &str = r#"
+commands:
+help: displays this message
+help cell names: display the name legend for cells
+exit/q: exit
+display points: display your points
+set points/sp <cell> <points>: set a cell to a value. Get cell names by
+    help cell names
+set bonuses <n>: set the amount of yahtzee bonuses
+put dice <cell>: put the current dice into the cell, following the joker
+    rules
+clear points <cell>: clears points
+advise/a <dice-left> <dice>: gives advice on what to do with the dice
+throw <N>: prints a dice throw of <N> dice
+auto: automatically perform the next optimal move
+"#;
+
+const HELP_CELL_NAMES_AMERICAN: &str = r#"
+aces...sixes        => 1s - 6s
+3/4 of a kind       => 3k, 4k
+full house          => fh
+small straight      => ss
+large straight      => ls
+yahtzee             => yz
+chance              => ch
+"#;
+
+fn get_american_index(name: &str) -> Option<usize> {
+    Yahtzee::cell_index(name)
+}
+
+fn get_american_name(ind: usize) -> &'static str {
+    Yahtzee::cell_name(ind)
+}
+
+fn american_state(points: &[Option<usize>]) -> AmericanState {
+    let cells: Vec<_> = points.iter().map(|x| x.is_some()).collect();
+    let points_above = points.iter().take(6).filter_map(|x| x.as_ref()).sum();
+
+    AmericanState::from_dyn(
+        &cells,
+        points_above,
+        points[YAHTZEE_CELL] == Some(50),
+    )
+}
+
+fn get_american_total(points: &[Option<usize>], bonuses: usize) -> usize {
+    let points_above: usize =
+        points.iter().take(6).filter_map(|x| x.as_ref()).sum();
+
+    Yahtzee::bonus(points_above)
+        + bonuses * 100
+        + points.iter().filter_map(|x| x.as_ref()).sum::<usize>()
+}
+
+fn display_points_american(points: &[Option<usize>], bonuses: usize) {
+    let tostr = |x: Option<usize>| match x {
+        None => "".to_owned(),
+        Some(0) => "-".to_owned(),
+        Some(x) => format!("{}", x),
+    };
+
+    let above: usize = points.iter().take(6).filter_map(|x| x.as_ref()).sum();
+
+    println!("------------------------------------");
+    for (ind, name) in ["1s", "2s", "3s", "4s", "5s", "6s"].iter().enumerate() {
+        println!(
+            "{:<18}({}) = {}",
+            get_american_name(ind),
+            name,
+            tostr(points[ind])
+        );
+    }
+    println!("------------------------------------");
+    println!("sum                    = {}", above);
+    println!("bonus                  = {}", Yahtzee::bonus(above));
+    for (ind, name) in ["3k", "4k", "fh", "ss", "ls", "yz", "ch"]
+        .iter()
+        .enumerate()
+    {
+        println!(
+            "{:<18}({}) = {}",
+            get_american_name(ind + 6),
+            name,
+            tostr(points[ind + 6])
+        );
+    }
+    println!("yahtzee bonus          = {}", bonuses * 100);
+    println!("------------------------------------");
+    println!(
+        "Total                  = {}\n",
+        get_american_total(points, bonuses)
+    );
+}
+
+// The American layer and the index of the state in it.
+fn get_american_state_index(
+    state: &AmericanState,
+    dice: &DiceThrow,
+    throws_left: usize,
+) -> (AmericanLayer, [usize; 3]) {
+    let layer = AmericanLayer {
+        na: state.get_n_above(),
+        nb: state.get_n_below(),
+        nt: throws_left,
+        scores: None,
+        strats: None,
+    };
+
+    let index = [
+        state.get_above_index(),
+        state.get_below_index(),
+        dice.get_index(),
+    ];
+
+    (layer, index)
+}
+
+fn get_american_byte(
+    store: &LayerStore,
+    state: &AmericanState,
+    dice: &DiceThrow,
+    throws_left: usize,
+) -> Option<u8> {
+    let (layer, index) = get_american_state_index(state, dice, throws_left);
+
+    store.byte(layer.id().index, index)
+}
+
+fn get_american_score(
+    store: &LayerStore,
+    state: &AmericanState,
+    dice: &DiceThrow,
+    throws_left: usize,
+) -> Option<f32> {
+    let (layer, index) = get_american_state_index(state, dice, throws_left);
+
+    store.float(layer.id().index, index)
+}
+
+// Puts the dice into the cell if the joker rules allow it, and returns
+// whether it did.
+fn put_dice_american(
+    points: &mut [Option<usize>],
+    bonuses: &mut usize,
+    dice: DiceThrow,
+    ind: usize,
+) -> bool {
+    let state = american_state(points);
+
+    if !state.allowed_cells(&dice).contains(&ind) {
+        return false;
+    }
+
+    if state.yahtzee_bonus && dice.yatzy() > 0 {
+        *bonuses += 1;
+    }
+
+    points[ind] = Some(state.cell_score(ind, &dice));
+
+    true
+}
+
+pub fn start_american() {
+    println!("Welcome to the interactive guide of a game of Yahtzee");
+
+    let mut points = vec![None; AMERICAN_CELLS];
+    let mut bonuses = 0;
+
+    let mut dice = DiceThrow::throw(5);
+    let mut throws_left = rerolls::<5>();
+
+    let store = LayerStore::american();
+
+    println!("Starting throw:\n{}", dice);
+
+    'outer: loop {
+        println!("Throws left: {throws_left}");
+
+        let state = american_state(&points);
+
+        if let Some(rem_score) =
+            get_american_score(&store, &state, &dice, throws_left)
+        {
+            let tot_score =
+                get_american_total(&points, bonuses) as f32 + rem_score;
+
+            println!("expected total score is now {:.2}", tot_score);
+        }
+
+        print!("> ");
+        stdout().flush().unwrap();
+        let mut buffer = String::new();
+        stdin().read_line(&mut buffer).unwrap();
+
+        let command: Vec<_> = buffer.split_whitespace().collect();
+
+        match command.as_slice() {
+            ["help"] => println!("{}", HELP_MSG_AMERICAN),
+            ["help", "cell", "names"] => {
+                println!("{}", HELP_CELL_NAMES_AMERICAN)
+            }
+            ["exit" | "q"] => break,
+            ["display", "points"] => display_points_american(&points, bonuses),
+            ["set", "points", cell, pts] | ["sp", cell, pts] => {
+                if let Some(index) = get_american_index(cell) {
+                    let pts = pts.parse().unwrap();
+                    points[index] = Some(pts);
+                    display_points_american(&points, bonuses);
+                } else {
+                    println!("Invalid cell name!");
+                }
+            }
+            ["set", "bonuses", n] => {
+                bonuses = n.parse().unwrap();
+                display_points_american(&points, bonuses);
+            }
+            ["put", "dice", cell] => {
+                let Some(index) = get_american_index(cell) else {
+                    println!("Invalid cell name!");
+                    continue 'outer;
+                };
+
+                if put_dice_american(&mut points, &mut bonuses, dice, index) {
+                    display_points_american(&points, bonuses);
+                    throws_left = rerolls::<5>();
+                    dice = DiceThrow::throw(5);
+                    println!("Current dice:\n{}", dice);
+                } else {
+                    println!("Not allowed by the joker rules!");
+                }
+            }
+            ["clear", "points", cell] => {
+                if let Some(index) = get_american_index(cell) {
+                    points[index] = None;
+                } else {
+                    println!("Invalid cell name!");
+                }
+            }
+            ["throw", n] => {
+                let n = n.parse().unwrap();
+                let throw = DiceThrow::throw(n);
+                println!("{}", throw);
+                dice = throw;
+            }
+            ["auto"] | [] => {
+                let Some(byte) =
+                    get_american_byte(&store, &state, &dice, throws_left)
+                else {
+                    println!("Strategy not available for state.");
+                    continue 'outer;
+                };
+
+                if throws_left == 0 {
+                    let ind = byte as usize;
+                    let score = state.cell_score(ind, &dice);
+
+                    println!(
+                        "Putting {} points in {}.",
+                        score,
+                        get_american_name(ind)
+                    );
+
+                    put_dice_american(&mut points, &mut bonuses, dice, ind);
+                    display_points_american(&points, bonuses);
+
+                    dice = DiceThrow::throw(5);
+                    throws_left = rerolls::<5>();
+
+                    println!("New throw:\n{}", dice);
+                } else {
+                    println!("Rethrowing:\n{}", dice.get_subthrow(byte));
+
+                    let rethrow = DiceThrow::throw(byte.count_ones() as usize);
+
+                    dice = dice.overwrite_reroll_dyn::<5>(
+                        byte,
+                        &rethrow.into_ordered_dice().collect::<Vec<_>>(),
+                    );
+
+                    println!("To give:\n{}", dice);
+                    throws_left -= 1;
+                }
+            }
+            ["advise" | "a", dice_left, dice_str] => {
+                let throws_left: usize = dice_left.parse().unwrap();
+                if dice_str.len() != 5 {
+                    continue 'outer;
+                }
+                let mut throw = DiceThrow::from([0usize; 6]);
+                for c in dice_str.chars() {
+                    let i = (c as u8 - b'0') as usize;
+                    throw[i] += 1;
+                }
+
+                println!("You entered:\n{}\n", throw);
+
+                match get_american_byte(&store, &state, &throw, throws_left) {
+                    Some(byte) if throws_left == 0 => {
+                        let ind = byte as usize;
+
+                        println!(
+                            "Put {} points in {}.",
+                            state.cell_score(ind, &throw),
+                            get_american_name(ind)
+                        );
+                    }
+                    Some(byte) => {
+                        println!("Rethrow:\n{}", throw.get_subthrow(byte))
+                    }
+                    None => println!("Strategy not available for state."),
+                }
+            }
+            ["expected-remaining" | "ex-r"] => {
+                if let Some(rem_score) =
+                    get_american_score(&store, &state, &dice, throws_left)
+                {
+                    println!("expected remaining score is {}", rem_score);
+                } else {
+                    println!("Failed to read scores file");
+                }
+            }
+            ["expected-total" | "ex-t"] => {
+                if let Some(rem_score) =
+                    get_american_score(&store, &state, &dice, throws_left)
+                {
+                    let tot_score =
+                        get_american_total(&points, bonuses) as f32 + rem_score;
+
+                    println!("expected total score is {}", tot_score);
+                } else {
+                    println!("Failed to read scores file");
+                }
+            }
+            ["reset"] => {
+                points = vec![None; AMERICAN_CELLS];
+                bonuses = 0;
+                dice = DiceThrow::throw(5);
+                throws_left = rerolls::<5>();
+
+                println!("Starting throw:\n{}", dice);
+            }
+            ["rethrow" | "rt", mask_str] => {
+                if throws_left == 0 {
+                    println!("No throws left!");
+                    continue 'outer;
+                }
+
+                if mask_str.len() != 5 {
+                    println!("Invalid mask!");
+                    continue 'outer;
+                }
+
+                let mut mask: u8 = 0;
+                for c in mask_str.chars().rev() {
+                    let bit = match c {
+                        '0' => 0,
+                        '1' => 1,
+                        _ => {
+                            println!("Invalid mask!");
+                            continue 'outer;
+                        }
+                    };
+
+                    mask = mask << 1 | bit;
+                }
+
+                let rethrow: Vec<_> =
+                    DiceThrow::throw(mask.count_ones() as usize)
+                        .into_ordered_dice()
+                        .collect();
+
+                dice = dice.overwrite_reroll_dyn::<5>(mask, &rethrow);
+                throws_left -= 1;
+
+                println!("New throw:\n{}", dice);
+            }
+            ["set", "dice", dice_str] => {
+                if dice_str.len() != 5 {
+                    continue 'outer;
+                }
+                let mut throw = DiceThrow::from([0usize; 6]);
+                for c in dice_str.chars() {
+                    let i = (c as u8 - b'0') as usize;
+                    throw[i] += 1;
+                }
+
+                dice = throw;
+                println!("Current dice:\n{}", dice);
+            }
+            _ => println!("Invalid command! {:?}", command),
+        }
+    }
+}
+
+const HELP_MSG_FORCED: &str = r#"
+commands:
+help: displays this message
+exit/q: exit
+display points: display your points
+put dice: put the current dice into the next cell
+advise/a <dice-left> <dice>: gives advice on what to do with the dice
+throw <N>: prints a dice throw of <N> dice
+auto: automatically perform the next optimal move
+rethrow/rt <mask>: rethrow the dice marked by 1 in the mask
+set dice <dice>: set the current dice
+expected-remaining/ex-r: the expected remaining score
+expected-total/ex-t: the expected total score
+reset: start a new game
+"#;
+
+fn get_forced_index<const N: usize>(
+    points: &[Option<usize>],
+    dice: &DiceThrow,
+    throws_left: usize,
+) -> Option<(ForcedLayer<N>, [usize; 3])> {
+    let cell = points.iter().position(|x| x.is_none())?;
+
+    let points_above: usize =
+        points.iter().take(6).filter_map(|x| x.as_ref()).sum();
+
+    let [_, la, _] = ForcedLayer::<N>::shape();
+    let index = [throws_left, points_above.min(la - 1), dice.get_index()];
+
+    Some((ForcedLayer::new(cell), index))
+}
+
+fn get_forced_byte<const N: usize>(
+    store: &LayerStore,
+    points: &[Option<usize>],
+    dice: &DiceThrow,
+    throws_left: usize,
+) -> Option<u8> {
+    let (layer, index) = get_forced_index::<N>(points, dice, throws_left)?;
+
+    store.byte(layer.id().index, index)
+}
+
+fn get_forced_score<const N: usize>(
+    store: &LayerStore,
+    points: &[Option<usize>],
+    dice: &DiceThrow,
+    throws_left: usize,
+) -> Option<f32> {
+    let (layer, index) = get_forced_index::<N>(points, dice, throws_left)?;
+
+    store.float(layer.id().index, index)
+}
+
+// Puts the dice into the next cell and returns whether the game is over.
+fn put_dice_forced<const N: usize>(
+    points: &mut [Option<usize>],
+    dice: DiceThrow,
+) -> bool {
+    let cell = points.iter().position(|x| x.is_none()).unwrap();
+    let score = dice.cell_score::<N>(cell);
+
+    println!("Putting {} points in {}.", score, get_index_name::<N>(cell));
+
+    points[cell] = Some(score);
+    display_points::<N>(points, None, None);
+
+    cell + 1 == points.len()
+}
+
+pub fn start_forced<const N: usize>() {
+    println!(
+        "Welcome to the interactive guide of a forced game with {} dice",
+        N
+    );
+
+    let mut points = vec![None; cell_from_dice::<N>()];
+
+    let mut dice = DiceThrow::throw(N);
+    let mut throws_left = rerolls::<N>();
+
+    let store = LayerStore::forced::<N>();
+
+    println!("Starting throw:\n{}", dice);
+
+    'outer: loop {
+        if let Some(cell) = points.iter().position(|x| x.is_none()) {
+            println!("Next cell: {}", get_index_name::<N>(cell));
+        }
+        println!("Throws left: {throws_left}");
+
+        if let Some(rem_score) =
+            get_forced_score::<N>(&store, &points, &dice, throws_left)
+        {
+            let tot_score = get_total_score::<N>(&points) as f32 + rem_score;
+
+            println!("expected total score is now {:.2}", tot_score);
+        }
+
+        print!("> ");
+        stdout().flush().unwrap();
+        let mut buffer = String::new();
+        stdin().read_line(&mut buffer).unwrap();
+
+        let command: Vec<_> = buffer.split_whitespace().collect();
+
+        match command.as_slice() {
+            ["help"] => println!("{}", HELP_MSG_FORCED),
+            ["exit" | "q"] => break,
+            ["display", "points"] => display_points::<N>(&points, None, None),
+            ["put", "dice"] => {
+                if points.iter().all(|x| x.is_some()) {
+                    println!("The game is over!");
+                    continue 'outer;
+                }
+
+                put_dice_forced::<N>(&mut points, dice);
+
+                throws_left = rerolls::<N>();
+                dice = DiceThrow::throw(N);
+                println!("Current dice:\n{}", dice);
+            }
+            ["throw", n] => {
+                let n = n.parse().unwrap();
+                let throw = DiceThrow::throw(n);
+                println!("{}", throw);
+                dice = throw;
+            }
+            ["auto"] | [] => {
+                if throws_left == 0 {
+                    if points.iter().all(|x| x.is_some()) {
+                        println!("The game is over!");
+                        continue 'outer;
+                    }
+
+                    if put_dice_forced::<N>(&mut points, dice) {
+                        println!("The game is over!");
+                    }
+
+                    dice = DiceThrow::throw(N);
+                    throws_left = rerolls::<N>();
+
+                    println!("New throw:\n{}", dice);
+                    continue 'outer;
+                }
+
+                let Some(byte) =
+                    get_forced_byte::<N>(&store, &points, &dice, throws_left)
+                else {
+                    println!("Strategy not available for state.");
+                    continue 'outer;
+                };
+
+                println!("Rethrowing:\n{}", dice.get_subthrow(byte));
+
+                let rethrow = DiceThrow::throw(byte.count_ones() as usize);
+
+                dice = dice.overwrite_reroll_dyn::<N>(
+                    byte,
+                    &rethrow.into_ordered_dice().collect::<Vec<_>>(),
+                );
+
+                println!("To give:\n{}", dice);
+                throws_left -= 1;
+            }
+            ["advise" | "a", dice_left, dice_str] => {
+                let throws_left: usize = dice_left.parse().unwrap();
+                if dice_str.len() != N {
+                    continue 'outer;
+                }
+                let mut throw = DiceThrow::from([0usize; 6]);
+                for c in dice_str.chars() {
+                    let i = (c as u8 - b'0') as usize;
+                    throw[i] += 1;
+                }
+
+                println!("You entered:\n{}\n", throw);
+
+                if throws_left == 0 {
+                    println!("Put the dice in the next cell.");
+                    continue 'outer;
+                }
+
+                match get_forced_byte::<N>(&store, &points, &throw, throws_left)
+                {
+                    Some(byte) => {
+                        println!("Rethrow:\n{}", throw.get_subthrow(byte))
+                    }
+                    None => println!("Strategy not available for state."),
+                }
+            }
+            ["expected-remaining" | "ex-r"] => {
+                if let Some(rem_score) =
+                    get_forced_score::<N>(&store, &points, &dice, throws_left)
+                {
+                    println!("expected remaining score is {}", rem_score);
+                } else {
+                    println!("Failed to read scores file");
+                }
+            }
+            ["expected-total" | "ex-t"] => {
+                if let Some(rem_score) =
+                    get_forced_score::<N>(&store, &points, &dice, throws_left)
+                {
+                    let tot_score =
+                        get_total_score::<N>(&points) as f32 + rem_score;
+
+                    println!("expected total score is {}", tot_score);
+                } else {
+                    println!("Failed to read scores file");
+                }
+            }
+            ["reset"] => {
+                points = vec![None; cell_from_dice::<N>()];
                 dice = DiceThrow::throw(N);
-                throws_left = 2;
+                throws_left = rerolls::<N>();
 
                 println!("Starting throw:\n{}", dice);
             }
@@ -1,18 +1,13 @@
-use std::{
-    collections::{HashMap, HashSet},
-    hash::Hash,
-};
+use std::collections::HashSet;
 
 use arrayvec::ArrayVec;
 use itertools::iproduct;
 use once_cell::sync::Lazy;
 
+use crate::{rules::RuleSet, util::parse_binary, with_rules};
+
 pub const fn points_above<const N: usize>() -> usize {
-    match N {
-        5 => 63,
-        6 => 84,
-        _ => panic!("Amount of dice not supported!"),
-    }
+    with_rules!(N, R => R::BONUS_THRESHOLD)
 }
 
 fn a_levels<const N: usize>() -> AboveLevelsType {
@@ -54,22 +49,6 @@ fn a_levels<const N: usize>() -> AboveLevelsType {
     })
 }
 
-fn extend_map<T: Eq + Hash + Copy>(map: &mut HashMap<T, usize>, level: &[T]) {
-    map.extend(level.iter().enumerate().map(|(i, &x)| (x, i)))
-}
-
-fn make_map<const N: usize, T: Eq + Hash + Copy>(
-    levels: &[Vec<T>; N],
-) -> HashMap<T, usize> {
-    let mut map = HashMap::new();
-
-    for l in levels {
-        extend_map(&mut map, l);
-    }
-
-    map
-}
-
 fn next_state<const N: usize>(state: &mut [bool; N]) -> bool {
     for b in state.iter_mut() {
         *b = !*b;
@@ -81,8 +60,11 @@ fn next_state<const N: usize>(state: &mut [bool; N]) -> bool {
     [false; N] == *state
 }
 
-fn b_levels<const N: usize>() -> [Vec<[bool; N]>; N + 1] {
-    let mut levels = [(); N + 1].map(|_| Vec::new());
+// L is the amount of levels, N + 1.
+fn b_levels<const N: usize, const L: usize>() -> [Vec<[bool; N]>; L] {
+    assert!(L == N + 1, "Wrong amount of levels!");
+
+    let mut levels = [(); L].map(|_| Vec::new());
 
     let mut state = [false; N];
 
@@ -99,25 +81,211 @@ fn b_levels<const N: usize>() -> [Vec<[bool; N]>; N + 1] {
     levels
 }
 
+// For the American rules the below levels also hold whether the yahtzee box
+// (index 5 below the line) is filled with 50 points, as later yahtzees then
+// give a bonus. This can only be the case when the yahtzee box is filled.
+fn b_levels_american() -> BelowLevelsAmericanType {
+    b_levels::<7, 8>().map(|level| {
+        level
+            .into_iter()
+            .flat_map(|cells| {
+                [(cells, false)]
+                    .into_iter()
+                    .chain(cells[5].then_some((cells, true)))
+            })
+            .collect()
+    })
+}
+
 // Defining lookup tables:
 
-type AboveLevelsType = [Vec<(usize, [bool; 6])>; 7];
+pub type AboveLevelsType = [Vec<(usize, [bool; 6])>; 7];
 
 pub static ABOVE_LEVELS_5: Lazy<AboveLevelsType> = Lazy::new(a_levels::<5>);
 pub static ABOVE_LEVELS_6: Lazy<AboveLevelsType> = Lazy::new(a_levels::<6>);
 
-type AboveLevelsMapType = HashMap<(usize, [bool; 6]), usize>;
+pub static BELOW_LEVELS_5: Lazy<[Vec<[bool; 9]>; 10]> = Lazy::new(b_levels);
+pub static BELOW_LEVELS_6: Lazy<[Vec<[bool; 14]>; 15]> = Lazy::new(b_levels);
+
+type BelowLevelsAmericanType = [Vec<([bool; 7], bool)>; 8];
 
-pub static ABOVE_LEVELS_5_MAP: Lazy<AboveLevelsMapType> =
-    Lazy::new(|| make_map(&ABOVE_LEVELS_5));
+pub static BELOW_LEVELS_A: Lazy<BelowLevelsAmericanType> =
+    Lazy::new(b_levels_american);
 
-pub static ABOVE_LEVELS_6_MAP: Lazy<AboveLevelsMapType> =
-    Lazy::new(|| make_map(&ABOVE_LEVELS_6));
+// Indexing into the levels:
 
-pub static BELOW_LEVELS_5: Lazy<[Vec<[bool; 9]>; 10]> = Lazy::new(b_levels);
-pub static BELOW_LEVELS_6: Lazy<[Vec<[bool; 14]>; 15]> = Lazy::new(b_levels);
+// The above levels are sorted by the points, so there is no simple formula for
+// the index of a state. Instead there is a dense table over all the points and
+// filled cells (as a bitmask), which has room for every state that can exist.
+type AboveIndexType = Vec<[usize; 64]>;
+
+fn a_index<const N: usize>(levels: &AboveLevelsType) -> AboveIndexType {
+    let mut index = vec![[usize::MAX; 64]; points_above::<N>() + 1];
+
+    for level in levels {
+        for (i, &(pts, cells)) in level.iter().enumerate() {
+            index[pts][parse_binary(&cells)] = i;
+        }
+    }
+
+    index
+}
+
+static ABOVE_INDEX_5: Lazy<AboveIndexType> =
+    Lazy::new(|| a_index::<5>(&ABOVE_LEVELS_5));
+static ABOVE_INDEX_6: Lazy<AboveIndexType> =
+    Lazy::new(|| a_index::<6>(&ABOVE_LEVELS_6));
+
+// The index of a state in its above level, where the points are capped at the
+// goal and the filled cells are given as a bitmask.
+pub fn above_index<const N: usize>(points: usize, cells: u32) -> usize {
+    let index = match N {
+        5 => &ABOVE_INDEX_5,
+        6 => &ABOVE_INDEX_6,
+        _ => panic!("Unsupported amount of dice!"),
+    };
+
+    index[points][cells as usize]
+}
+
+const BINOMIALS: [[usize; 15]; 15] = {
+    let mut binomials = [[0; 15]; 15];
+    let mut n = 0;
+
+    while n < 15 {
+        binomials[n][0] = 1;
+
+        let mut k = 1;
+
+        while k <= n {
+            binomials[n][k] = binomials[n - 1][k - 1] + binomials[n - 1][k];
+            k += 1;
+        }
+
+        n += 1;
+    }
+
+    binomials
+};
+
+// The below levels are in the order of the cells read as a binary number, so
+// the index of some cells in their level is given by the combinatorial number
+// system: the sum of binomial(i, j) over the j:th filled cell i.
+pub fn below_index(mut cells: u32) -> usize {
+    let mut index = 0;
+    let mut j = 1;
+
+    while cells != 0 {
+        index += BINOMIALS[cells.trailing_zeros() as usize][j];
+        cells &= cells - 1;
+        j += 1;
+    }
 
-pub static BELOW_LEVELS_5_MAP: Lazy<HashMap<[bool; 9], usize>> =
-    Lazy::new(|| make_map(&BELOW_LEVELS_5));
-pub static BELOW_LEVELS_6_MAP: Lazy<HashMap<[bool; 14], usize>> =
-    Lazy::new(|| make_map(&BELOW_LEVELS_6));
+    index
+}
+
+// The inverse of `below_index`, for the cells at index `index` in level `n`.
+pub fn below_cells(n: usize, mut index: usize) -> u32 {
+    let mut cells = 0;
+
+    for j in (1..=n).rev() {
+        let i = (j - 1..BINOMIALS.len())
+            .rev()
+            .find(|&i| BINOMIALS[i][j] <= index)
+            .unwrap();
+
+        index -= BINOMIALS[i][j];
+        cells |= 1 << i;
+    }
+
+    cells
+}
+
+// For the American rules the filled cells with the yahtzee box take up two
+// places in the level, which is simplest to keep track of with a table over
+// the bitmasks of the cells.
+const BELOW_INDEX_A: [usize; 128] = {
+    let mut index = [0; 128];
+    let mut level_len = [0; 8];
+    let mut mask = 0;
+
+    while mask < 128 {
+        let n = (mask as u8).count_ones() as usize;
+
+        index[mask] = level_len[n];
+        level_len[n] += 1 + (mask >> 5 & 1);
+        mask += 1;
+    }
+
+    index
+};
+
+pub fn below_index_american(cells: u32, yahtzee_bonus: bool) -> usize {
+    BELOW_INDEX_A[cells as usize] + yahtzee_bonus as usize
+}
+
+fn check_above<const N: usize>(levels: &AboveLevelsType) {
+    for level in levels {
+        for (i, (pts, cells)) in level.iter().enumerate() {
+            assert_eq!(above_index::<N>(*pts, parse_binary(cells) as u32), i);
+        }
+    }
+}
+
+fn check_below<const N: usize>(levels: &[Vec<[bool; N]>]) {
+    for (n, level) in levels.iter().enumerate() {
+        for (i, cells) in level.iter().enumerate() {
+            let mask = parse_binary(cells) as u32;
+
+            assert_eq!(below_index(mask), i);
+            assert_eq!(below_cells(n, i), mask);
+        }
+    }
+}
+
+fn check_below_american() {
+    for level in BELOW_LEVELS_A.iter() {
+        for (i, (cells, yahtzee_bonus)) in level.iter().enumerate() {
+            assert_eq!(
+                below_index_american(
+                    parse_binary(cells) as u32,
+                    *yahtzee_bonus
+                ),
+                i
+            );
+        }
+    }
+}
+
+// Checks that indexing agrees with the levels for every state.
+pub fn check_level_indexing() {
+    check_above::<5>(&ABOVE_LEVELS_5);
+    check_above::<6>(&ABOVE_LEVELS_6);
+
+    check_below(&*BELOW_LEVELS_5);
+    check_below(&*BELOW_LEVELS_6);
+
+    check_below_american();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn above_index_round_trip() {
+        check_above::<5>(&ABOVE_LEVELS_5);
+        check_above::<6>(&ABOVE_LEVELS_6);
+    }
+
+    #[test]
+    fn below_index_round_trip() {
+        check_below(&*BELOW_LEVELS_5);
+        check_below(&*BELOW_LEVELS_6);
+    }
+
+    #[test]
+    fn below_index_american_round_trip() {
+        check_below_american();
+    }
+}
@@ -0,0 +1,278 @@
+// This is the out of core solver for the American rules. It works the same
+// way as the one for 5 dice, but with the below levels that also keep track
+// of whether the yahtzee box is filled with 50 points.
+
+use std::{
+    fs::create_dir_all,
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use ndarray::Array3;
+
+use crate::{
+    dice_distributions::DICE_DISTR,
+    level_ordering::{ABOVE_LEVELS_5, BELOW_LEVELS_A},
+    macrosolver::{
+        layer_file::{Game, LayerId},
+        outcore::{
+            load_bytes, load_floats, rerolls, save_bytes, save_floats, PREFIX,
+        },
+    },
+    rules::{RuleSet, Yahtzee},
+    solver::{solve_layer_type1, solve_layer_type2},
+};
+
+pub struct AmericanLayer {
+    pub na: usize,
+    pub nb: usize,
+    pub nt: usize,
+    pub scores: Option<Array3<f32>>,
+    pub strats: Option<Array3<u8>>,
+}
+
+impl AmericanLayer {
+    pub fn empty() -> Self {
+        Self {
+            na: 0,
+            nb: 0,
+            nt: 0,
+            scores: Some(Array3::zeros([0; 3])),
+            strats: Some(Array3::zeros([0; 3])),
+        }
+    }
+
+    pub fn name(&self) -> String {
+        format!("{}_{}_{}.dat", self.na, self.nb, self.nt)
+    }
+
+    pub fn dir() -> String {
+        let mut dir = format!("{}/a", *PREFIX);
+
+        let rerolls = rerolls::<5>();
+        if rerolls != Yahtzee::THROWS - 1 {
+            dir += &format!("_rerolls{rerolls}");
+        }
+
+        dir
+    }
+
+    pub fn scores_path(&self) -> String {
+        format!("{}/scores/{}", Self::dir(), self.name())
+    }
+
+    pub fn strats_path(&self) -> String {
+        format!("{}/strats/{}", Self::dir(), self.name())
+    }
+
+    pub fn id(&self) -> LayerId {
+        LayerId {
+            game: Game::American,
+            dice: 5,
+            x: false,
+            index: [self.na, self.nb, self.nt, 0],
+        }
+    }
+
+    pub fn shape(&self) -> [usize; 3] {
+        [
+            ABOVE_LEVELS_5[self.na].len(),
+            BELOW_LEVELS_A[self.nb].len(),
+            DICE_DISTR.5.len(),
+        ]
+    }
+
+    pub fn save_scores(&self) {
+        save_floats(
+            &self.scores_path(),
+            self.id(),
+            self.scores.as_ref().unwrap(),
+        );
+    }
+
+    pub fn save_strats(&self) {
+        save_bytes(
+            &self.strats_path(),
+            self.id(),
+            self.strats.as_ref().unwrap(),
+        );
+    }
+
+    pub fn is_done(&self) -> bool {
+        Path::new(&self.scores_path()).exists()
+            && Path::new(&self.strats_path()).exists()
+    }
+
+    pub fn load_scores(&mut self) -> Option<()> {
+        if self.scores.is_none() {
+            self.scores = Some(load_floats(
+                &self.scores_path(),
+                self.id(),
+                self.shape(),
+            )?);
+        }
+
+        Some(())
+    }
+
+    pub fn load_strats(&mut self) -> Option<()> {
+        if self.strats.is_none() {
+            self.strats =
+                Some(load_bytes(&self.strats_path(), self.id(), self.shape())?);
+        }
+
+        Some(())
+    }
+}
+
+pub fn make_thin_layers_american() -> Array3<Option<AmericanLayer>> {
+    Array3::from_shape_fn([7, 8, rerolls::<5>() + 1], |(na, nb, nt)| {
+        Some(AmericanLayer {
+            na,
+            nb,
+            nt,
+            scores: None,
+            strats: None,
+        })
+    })
+}
+
+pub fn solve_american() {
+    create_dir_all(format!("{}/scores/", AmericanLayer::dir())).unwrap();
+    create_dir_all(format!("{}/strats/", AmericanLayer::dir())).unwrap();
+
+    let mut layers = make_thin_layers_american();
+    let n_rerolls = rerolls::<5>();
+
+    let global_timer = Instant::now();
+    let mut load_timer = Duration::ZERO;
+    let mut save_timer = Duration::ZERO;
+    let mut compute_timer = Duration::ZERO;
+
+    for na in (0..7).rev() {
+        for nb in (0..8).rev() {
+            println!("=============================");
+            println!("na: {na:2}, nb: {nb:2}, nt: 0");
+
+            let mut layer: AmericanLayer = layers[[na, nb, 0]].take().unwrap();
+
+            if layer.is_done() {
+                println!("Already done!");
+            } else {
+                let mut prev_above_layer = layers
+                    .get_mut([na + 1, nb, n_rerolls])
+                    .unwrap_or(&mut Some(AmericanLayer::empty()))
+                    .take()
+                    .unwrap();
+                let mut prev_below_layer = layers
+                    .get_mut([na, nb + 1, n_rerolls])
+                    .unwrap_or(&mut Some(AmericanLayer::empty()))
+                    .take()
+                    .unwrap();
+
+                let timer = Instant::now();
+
+                prev_above_layer.load_scores();
+                prev_below_layer.load_scores();
+
+                let t = timer.elapsed();
+                println!("Loading took {t:.2?}");
+                load_timer += t;
+
+                let timer = Instant::now();
+
+                let (scores, strats) = solve_layer_type1::<Yahtzee>(
+                    na,
+                    nb,
+                    &prev_above_layer.scores.unwrap(),
+                    &prev_below_layer.scores.unwrap(),
+                );
+
+                let t = timer.elapsed();
+                println!("Solving took {t:.2?}");
+                compute_timer += t;
+
+                prev_above_layer.scores = None;
+                prev_below_layer.scores = None;
+
+                if let Some(x) = layers.get_mut([na + 1, nb, n_rerolls]) {
+                    *x = Some(prev_above_layer);
+                }
+
+                if let Some(x) = layers.get_mut([na, nb + 1, n_rerolls]) {
+                    *x = Some(prev_below_layer);
+                }
+
+                layer.scores = Some(scores);
+                layer.strats = Some(strats);
+
+                let timer = Instant::now();
+
+                layer.save_scores();
+                layer.save_strats();
+
+                let t = timer.elapsed();
+                println!("Saving took  {t:.2?}");
+                save_timer += t;
+            }
+
+            layer.strats = None;
+            layers[[na, nb, 0]] = Some(layer);
+
+            for nt in 1..=n_rerolls {
+                println!("--------------------------------");
+                println!("na: {na:2}, nb: {nb:2}, nt: {nt}");
+
+                let mut layer = layers[[na, nb, nt]].take().unwrap();
+
+                if layer.is_done() {
+                    println!("Already done!");
+                } else {
+                    let mut prev_layer =
+                        layers[[na, nb, nt - 1]].take().unwrap();
+
+                    let timer = Instant::now();
+
+                    prev_layer.load_scores();
+
+                    let t = timer.elapsed();
+                    println!("Loading took {t:.2?}");
+                    load_timer += t;
+
+                    let timer = Instant::now();
+
+                    let (scores, strats) =
+                        solve_layer_type2::<5>(&prev_layer.scores.unwrap());
+
+                    let t = timer.elapsed();
+                    println!("Solving took {t:.2?}");
+                    compute_timer += t;
+
+                    prev_layer.scores = None;
+
+                    layers[[na, nb, nt - 1]] = Some(prev_layer);
+
+                    layer.scores = Some(scores);
+                    layer.strats = Some(strats);
+
+                    let timer = Instant::now();
+
+                    layer.save_scores();
+                    layer.save_strats();
+
+                    let t = timer.elapsed();
+                    println!("Saving took  {t:.2?}");
+                    save_timer += t;
+                }
+
+                layer.strats = None;
+                layers[[na, nb, nt]] = Some(layer);
+            }
+        }
+    }
+
+    println!("\n\nTotal   time: {:.2?}", global_timer.elapsed());
+    println!("Compute time: {compute_timer:.2?}");
+    println!("Loading time: {load_timer:.2?}");
+    println!("Saving  time: {save_timer:.2?}");
+}
@@ -0,0 +1,179 @@
+// This is the solver for forced yatzy ("tvungen yatzy"), where the cells are
+// filled from top to bottom. There is one layer for every cell, holding all
+// the states of the turn where that cell is filled, so the whole game is
+// solved in a fraction of a second, which also makes it a quick sanity check
+// of the reroll tables.
+
+use std::{fs::create_dir_all, path::Path, time::Instant};
+
+use ndarray::Array3;
+
+use crate::{
+    dice_distributions::amt_dice_combinations,
+    level_ordering::points_above,
+    macrosolver::{
+        layer_file::{Game, LayerId},
+        outcore::{
+            load_bytes, load_floats, rerolls, save_bytes, save_floats, PREFIX,
+        },
+    },
+    rules::RuleSet,
+    solver::{solve_layer_forced, throw_probs},
+    with_rules,
+    yatzy::cell_from_dice,
+};
+
+pub struct ForcedLayer<const N: usize> {
+    pub cell: usize,
+    pub scores: Option<Array3<f32>>,
+    pub strats: Option<Array3<u8>>,
+}
+
+impl<const N: usize> ForcedLayer<N> {
+    pub fn new(cell: usize) -> Self {
+        Self {
+            cell,
+            scores: None,
+            strats: None,
+        }
+    }
+
+    pub fn name(&self) -> String {
+        format!("{}.dat", self.cell)
+    }
+
+    // The layers for house rules with another amount of rerolls live in a
+    // separate directory, like those of the free game.
+    pub fn dir() -> String {
+        let mut dir = format!("{}/{N}f", *PREFIX);
+
+        let rerolls = rerolls::<N>();
+        if rerolls != with_rules!(N, R => R::THROWS) - 1 {
+            dir += &format!("_rerolls{rerolls}");
+        }
+
+        dir
+    }
+
+    pub fn scores_path(&self) -> String {
+        format!("{}/scores/{}", Self::dir(), self.name())
+    }
+
+    pub fn strats_path(&self) -> String {
+        format!("{}/strats/{}", Self::dir(), self.name())
+    }
+
+    pub fn id(&self) -> LayerId {
+        LayerId {
+            game: Game::Forced,
+            dice: N,
+            x: false,
+            index: [self.cell, 0, 0, 0],
+        }
+    }
+
+    // The shape of the (nt, points above, ti) arrays.
+    pub fn shape() -> [usize; 3] {
+        [
+            rerolls::<N>() + 1,
+            points_above::<N>() + 1,
+            amt_dice_combinations::<N>(),
+        ]
+    }
+
+    pub fn save_scores(&self) {
+        save_floats(
+            &self.scores_path(),
+            self.id(),
+            self.scores.as_ref().unwrap(),
+        );
+    }
+
+    pub fn save_strats(&self) {
+        save_bytes(
+            &self.strats_path(),
+            self.id(),
+            self.strats.as_ref().unwrap(),
+        );
+    }
+
+    pub fn is_done(&self) -> bool {
+        Path::new(&self.scores_path()).exists()
+            && Path::new(&self.strats_path()).exists()
+    }
+
+    pub fn load_scores(&mut self) -> Option<()> {
+        if self.scores.is_none() {
+            self.scores = Some(load_floats(
+                &self.scores_path(),
+                self.id(),
+                Self::shape(),
+            )?);
+        }
+
+        Some(())
+    }
+
+    pub fn load_strats(&mut self) -> Option<()> {
+        if self.strats.is_none() {
+            self.strats = Some(load_bytes(
+                &self.strats_path(),
+                self.id(),
+                Self::shape(),
+            )?);
+        }
+
+        Some(())
+    }
+}
+
+pub fn solve_forced<const N: usize>() {
+    create_dir_all(format!("{}/scores/", ForcedLayer::<N>::dir())).unwrap();
+    create_dir_all(format!("{}/strats/", ForcedLayer::<N>::dir())).unwrap();
+
+    let global_timer = Instant::now();
+
+    let mut next_layer: Option<ForcedLayer<N>> = None;
+
+    for cell in (0..cell_from_dice::<N>()).rev() {
+        let mut layer = ForcedLayer::<N>::new(cell);
+
+        if layer.is_done() {
+            println!("cell: {cell:2}, already done!");
+            layer.load_scores().unwrap();
+        } else {
+            let timer = Instant::now();
+
+            let (scores, strats) = solve_layer_forced::<N>(
+                cell,
+                next_layer
+                    .as_ref()
+                    .map(|l| l.scores.as_ref().unwrap().view()),
+            );
+
+            println!("cell: {cell:2}, solving took {:.2?}", timer.elapsed());
+
+            layer.scores = Some(scores);
+            layer.strats = Some(strats);
+
+            layer.save_scores();
+            layer.save_strats();
+
+            layer.strats = None;
+        }
+
+        next_layer = Some(layer);
+    }
+
+    let scores = next_layer.unwrap().scores.unwrap();
+    let rerolls = rerolls::<N>();
+
+    let score: f64 = throw_probs::<N>()
+        .iter()
+        .enumerate()
+        .map(|(ti, p)| scores[[rerolls, 0, ti]] as f64 * p)
+        .sum();
+
+    println!("\nTotal time: {:.2?}", global_timer.elapsed());
+    println!("Expected score for forced yatzy with {N} dice: {score:.2}");
+}
@@ -5,7 +5,10 @@ use std::time::Instant;
 
 use ndarray::Array3;
 
-use crate::solver::{solve_layer_type1_5dice, solve_layer_type2_5dice};
+use crate::{
+    rules::Yatzy,
+    solver::{solve_layer_type1, solve_layer_type2},
+};
 
 type SolvedType = (Array3<Option<Array3<f32>>>, Array3<Option<Array3<u8>>>);
 
@@ -33,7 +36,7 @@ pub fn solve_5dice() -> SolvedType {
             println!("\n\nna: {na:2}, nb: {nb:2}, nt: 0");
             let timer = Instant::now();
 
-            let (l_scores, l_strats) = solve_layer_type1_5dice(
+            let (l_scores, l_strats) = solve_layer_type1::<Yatzy>(
                 na,
                 nb,
                 prev_above_layer_scores,
@@ -54,7 +57,7 @@ pub fn solve_5dice() -> SolvedType {
                     scores.get([na, nb, nt - 1]).unwrap().as_ref().unwrap();
 
                 let (l_scores, l_strats) =
-                    solve_layer_type2_5dice(na, nb, prev_layer_scores);
+                    solve_layer_type2::<5>(prev_layer_scores);
 
                 scores[[na, nb, nt]] = Some(l_scores);
                 strats[[na, nb, nt]] = Some(l_strats);
@@ -0,0 +1,1191 @@
+// This is the module for the format of the layer files. Every file starts with
+// a header saying which layer it holds, so that a file made for another layer
+// or by another version, or one cut short when the solver was stopped, is
+// caught when loading it instead of giving wrong strategies.
+//
+// The header is 64 bytes, with the numbers in little-endian:
+//    0  magic "YZLF"
+//    4  format version (u16)
+//    6  game (u8), see `Game`
+//    7  amount of dice (u8)
+//    8  whether throws can be saved (u8)
+//    9  type of the data (u8), 0 for f32 and 1 for u8
+//   10  encoding of the data (u8), see `Encoding`
+//   12  index of the layer (4 x u16), see `LayerId`
+//   20  shape of the data (3 x u32)
+//   32  checksum of the data (u64), see `checksum`
+//   40  offset and step of fixed point scores (2 x f32), see `Encoding`
+// The rest is zero. The data follows in row-major order, in the byte order of
+// the machine like before there were headers, unless it is encoded.
+
+use std::{
+    fmt::{self, Display, Formatter},
+    fs::{read_dir, rename, File},
+    io::{self, Read, Write},
+    path::Path,
+};
+
+use miniz_oxide::{deflate::compress_to_vec, inflate::decompress_to_vec};
+use ndarray::{
+    parallel::prelude::{IntoParallelIterator, ParallelIterator},
+    Array3, ArrayView3,
+};
+
+use crate::macrosolver::{
+    american::AmericanLayer,
+    forced::ForcedLayer,
+    outcore::{layer_shape, PREFIX},
+};
+
+const MAGIC: &[u8; 4] = b"YZLF";
+const VERSION: u16 = 1;
+pub const HEADER_LEN: usize = 64;
+
+// The compressed strategies are cut into blocks of this many bytes, which are
+// compressed on their own so that reading a byte only inflates its block.
+const BLOCK_LEN: usize = 1 << 14;
+
+// The game a layer is solved for. The rule set is given by the game and the
+// amount of dice.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Game {
+    // Yatzy with 5 dice and Maxi Yatzy with 6.
+    Scandinavian = 0,
+    American = 1,
+    // Forced yatzy, where the cells are filled from top to bottom.
+    Forced = 2,
+    // Maximizing the probability of getting at least some amount of points.
+    Target = 3,
+}
+
+impl Game {
+    fn from_code(code: u8) -> Option<Self> {
+        [
+            Self::Scandinavian,
+            Self::American,
+            Self::Forced,
+            Self::Target,
+        ]
+        .into_iter()
+        .find(|&game| game as u8 == code)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DataType {
+    F32 = 0,
+    U8 = 1,
+}
+
+impl DataType {
+    fn size(self) -> usize {
+        match self {
+            Self::F32 => 4,
+            Self::U8 => 1,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Encoding {
+    Raw = 0,
+    // Compressed in blocks, see `write_compressed_layer`.
+    Blocks = 1,
+    // Scores as 16 bit fixed point, where q stands for offset + q * step with
+    // the offset and step in the header, see `write_fixed_layer`.
+    Fixed16 = 2,
+}
+
+// Which layer a file holds. The index is (na, nb, nt) and the points needed
+// for target layers, and just the cell for forced layers, with the rest zero.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LayerId {
+    pub game: Game,
+    pub dice: usize,
+    pub x: bool,
+    pub index: [usize; 4],
+}
+
+impl Display for LayerId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let x = if self.x { "x" } else { "" };
+        write!(f, "{:?} {}{x} layer {:?}", self.game, self.dice, self.index)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LayerHeader {
+    pub id: LayerId,
+    pub dtype: DataType,
+    pub encoding: Encoding,
+    pub shape: [usize; 3],
+    pub checksum: u64,
+    // The offset and step of fixed point scores, zero for other encodings.
+    pub fixed: [f32; 2],
+}
+
+impl LayerHeader {
+    // The length of the data as stored, which is only known from the index for
+    // compressed data.
+    fn data_len(&self) -> usize {
+        let size = match self.encoding {
+            Encoding::Fixed16 => 2,
+            _ => self.dtype.size(),
+        };
+
+        self.shape.iter().product::<usize>() * size
+    }
+
+    // The score a fixed point value stands for.
+    pub fn from_fixed(&self, q: u16) -> f32 {
+        let [offset, step] = self.fixed;
+
+        offset + q as f32 * step
+    }
+
+    fn to_bytes(self) -> [u8; HEADER_LEN] {
+        let mut bytes = [0; HEADER_LEN];
+
+        bytes[0..4].copy_from_slice(MAGIC);
+        bytes[4..6].copy_from_slice(&VERSION.to_le_bytes());
+        bytes[6] = self.id.game as u8;
+        bytes[7] = self.id.dice as u8;
+        bytes[8] = self.id.x as u8;
+        bytes[9] = self.dtype as u8;
+        bytes[10] = self.encoding as u8;
+
+        for (i, &x) in self.id.index.iter().enumerate() {
+            bytes[12 + 2 * i..14 + 2 * i]
+                .copy_from_slice(&(x as u16).to_le_bytes());
+        }
+
+        for (i, &x) in self.shape.iter().enumerate() {
+            bytes[20 + 4 * i..24 + 4 * i]
+                .copy_from_slice(&(x as u32).to_le_bytes());
+        }
+
+        bytes[32..40].copy_from_slice(&self.checksum.to_le_bytes());
+
+        for (i, &x) in self.fixed.iter().enumerate() {
+            bytes[40 + 4 * i..44 + 4 * i].copy_from_slice(&x.to_le_bytes());
+        }
+
+        bytes
+    }
+
+    fn from_bytes(bytes: &[u8; HEADER_LEN]) -> Result<Self, LayerFileError> {
+        let u16_at = |i: usize| u16::from_le_bytes([bytes[i], bytes[i + 1]]);
+        let u32_at =
+            |i: usize| u32::from_le_bytes(bytes[i..i + 4].try_into().unwrap());
+
+        if &bytes[0..4] != MAGIC {
+            return Err(LayerFileError::NoHeader);
+        }
+
+        let version = u16_at(4);
+        if version != VERSION {
+            return Err(LayerFileError::Version(version));
+        }
+
+        let game = Game::from_code(bytes[6])
+            .ok_or(LayerFileError::Corrupt("unknown game"))?;
+        let dtype = match bytes[9] {
+            0 => DataType::F32,
+            1 => DataType::U8,
+            _ => return Err(LayerFileError::Corrupt("unknown data type")),
+        };
+        let encoding = match bytes[10] {
+            0 => Encoding::Raw,
+            1 => Encoding::Blocks,
+            2 => Encoding::Fixed16,
+            _ => return Err(LayerFileError::Corrupt("unknown encoding")),
+        };
+
+        Ok(Self {
+            id: LayerId {
+                game,
+                dice: bytes[7] as usize,
+                x: bytes[8] != 0,
+                index: [0, 1, 2, 3].map(|i| u16_at(12 + 2 * i) as usize),
+            },
+            dtype,
+            encoding,
+            shape: [0, 1, 2].map(|i| u32_at(20 + 4 * i) as usize),
+            checksum: u64::from_le_bytes(bytes[32..40].try_into().unwrap()),
+            fixed: [0, 1].map(|i| f32::from_bits(u32_at(40 + 4 * i))),
+        })
+    }
+}
+
+#[derive(Debug)]
+pub enum LayerFileError {
+    Io(io::Error),
+    // The file does not start with the magic, so it is probably from before
+    // there were headers.
+    NoHeader,
+    Version(u16),
+    Corrupt(&'static str),
+    // The header is for another layer, type of data or shape.
+    Mismatch {
+        expected: Box<LayerHeader>,
+        found: Box<LayerHeader>,
+    },
+    // The size of the file is not that of the header and the data.
+    Truncated {
+        expected: u64,
+        found: u64,
+    },
+    Checksum,
+    // The data is compressed, which only lookups of single states can read.
+    Compressed,
+}
+
+impl Display for LayerFileError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "{err}"),
+            Self::NoHeader => write!(
+                f,
+                "the file has no header, run upgrade-cache to add headers to \
+                 the files of an old cache"
+            ),
+            Self::Version(version) => write!(
+                f,
+                "the file has format version {version}, expected {VERSION}"
+            ),
+            Self::Corrupt(what) => write!(f, "the header is corrupt: {what}"),
+            Self::Mismatch { expected, found } => write!(
+                f,
+                "the file holds the {:?} data of shape {:?} of the {}, \
+                 expected the {:?} data of shape {:?} of the {}",
+                found.dtype,
+                found.shape,
+                found.id,
+                expected.dtype,
+                expected.shape,
+                expected.id,
+            ),
+            Self::Truncated { expected, found } => write!(
+                f,
+                "the file is {found} bytes but should be {expected}, it was \
+                 probably not written to the end"
+            ),
+            Self::Checksum => write!(f, "the checksum of the data is wrong"),
+            Self::Compressed => write!(
+                f,
+                "the strategies are compressed, only the guide and the \
+                 simulations can read them"
+            ),
+        }
+    }
+}
+
+impl From<io::Error> for LayerFileError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+// FNV-1a over the data as 64 bit words, where the last one is padded with
+// zeros. Going byte by byte took longer than writing the layer to disk.
+fn checksum(data: &[u8]) -> u64 {
+    let chunks = data.chunks_exact(8);
+
+    let rest = chunks.remainder();
+
+    let mut last = [0; 8];
+    last[..rest.len()].copy_from_slice(rest);
+
+    chunks
+        .map(|word| word.try_into().unwrap())
+        .chain((!rest.is_empty()).then_some(last))
+        .fold(0xcbf29ce484222325, |hash, word| {
+            (hash ^ u64::from_le_bytes(word)).wrapping_mul(0x100000001b3)
+        })
+}
+
+// Writes the parts of a layer file next to `path` and renames it over the old
+// file, so that the files in the cache are never written in place. A solver
+// stopped halfway then never leaves a broken file behind, and the maps of the
+// strategy store stay valid while a layer is rewritten.
+fn write_file(path: &str, parts: &[&[u8]]) {
+    let tmp = format!("{path}.tmp");
+
+    let mut file = File::create(&tmp).unwrap();
+    for part in parts {
+        file.write_all(part).unwrap();
+    }
+    drop(file);
+
+    rename(&tmp, path).unwrap();
+}
+
+pub fn write_layer(
+    path: &str,
+    id: LayerId,
+    dtype: DataType,
+    shape: [usize; 3],
+    data: &[u8],
+) {
+    let header = LayerHeader {
+        id,
+        dtype,
+        encoding: Encoding::Raw,
+        shape,
+        checksum: checksum(data),
+        fixed: [0.0; 2],
+    };
+
+    write_file(path, &[&header.to_bytes(), data]);
+}
+
+// Checks that the file starts with the header expected and is as long as the
+// header says, and returns the header found. `bytes` is at least the start of
+// the file and `file_len` the size of all of it. The length of compressed
+// data is checked by `check_blocks`.
+pub fn check_header(
+    bytes: &[u8],
+    file_len: u64,
+    id: LayerId,
+    dtype: DataType,
+    shape: [usize; 3],
+) -> Result<LayerHeader, LayerFileError> {
+    let Some(bytes) = bytes.first_chunk::<HEADER_LEN>() else {
+        return Err(LayerFileError::NoHeader);
+    };
+
+    let found = LayerHeader::from_bytes(bytes)?;
+    let expected = LayerHeader {
+        id,
+        dtype,
+        encoding: found.encoding,
+        shape,
+        checksum: found.checksum,
+        fixed: found.fixed,
+    };
+
+    if found != expected {
+        return Err(LayerFileError::Mismatch {
+            expected: Box::new(expected),
+            found: Box::new(found),
+        });
+    }
+
+    let expected_len = (HEADER_LEN + found.data_len()) as u64;
+    if found.encoding != Encoding::Blocks && file_len != expected_len {
+        return Err(LayerFileError::Truncated {
+            expected: expected_len,
+            found: file_len,
+        });
+    }
+
+    Ok(found)
+}
+
+// Reads the data of the layer into `data` after checking that the header is
+// the one expected, and then that the data matches the checksum. Fixed point
+// scores are turned back into f32.
+pub fn read_layer(
+    file: &mut File,
+    id: LayerId,
+    dtype: DataType,
+    shape: [usize; 3],
+    data: &mut [u8],
+) -> Result<(), LayerFileError> {
+    let file_len = file.metadata()?.len();
+
+    if file_len < HEADER_LEN as u64 {
+        return Err(LayerFileError::NoHeader);
+    }
+
+    let mut bytes = [0; HEADER_LEN];
+    file.read_exact(&mut bytes)?;
+
+    let header = check_header(&bytes, file_len, id, dtype, shape)?;
+
+    match header.encoding {
+        Encoding::Raw => {
+            file.read_exact(data)?;
+
+            if checksum(data) != header.checksum {
+                return Err(LayerFileError::Checksum);
+            }
+        }
+        Encoding::Blocks => return Err(LayerFileError::Compressed),
+        Encoding::Fixed16 => {
+            let mut fixed = vec![0; header.data_len()];
+            file.read_exact(&mut fixed)?;
+
+            if checksum(&fixed) != header.checksum {
+                return Err(LayerFileError::Checksum);
+            }
+
+            for (x, q) in data.chunks_exact_mut(4).zip(fixed.chunks_exact(2)) {
+                let q = u16::from_ne_bytes(q.try_into().unwrap());
+                x.copy_from_slice(&header.from_fixed(q).to_ne_bytes());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// Writes the scores of a layer as 16 bit fixed point, which takes half the
+// space of f32. The offset is the lowest score of the layer and the step
+// spreads the scores over all of u16, so a score is off by at most half a
+// step, which is about 0.003 points for 6 dice.
+pub fn write_fixed_layer(path: &str, id: LayerId, data: &Array3<f32>) {
+    let (min, max) = data
+        .iter()
+        .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), &x| {
+            (min.min(x), max.max(x))
+        });
+
+    let (offset, step) = if data.is_empty() {
+        (0.0, 0.0)
+    } else {
+        (min, (max - min) / u16::MAX as f32)
+    };
+
+    let mut max_error = 0.0f64;
+
+    // The fixed point values are found in f64, so that a score is only off by
+    // the rounding to the nearest step.
+    let fixed: Vec<u8> = data
+        .iter()
+        .flat_map(|&x| {
+            let q = if step > 0.0 {
+                ((x as f64 - offset as f64) / step as f64).round() as u16
+            } else {
+                0
+            };
+
+            let error = offset as f64 + q as f64 * step as f64 - x as f64;
+            max_error = max_error.max(error.abs());
+
+            q.to_ne_bytes()
+        })
+        .collect();
+
+    assert!(
+        max_error <= step as f64 * (0.5 + 1e-9),
+        "{path}: a score is off by {max_error:e} in fixed point, which is \
+         more than half the step of {step:e}!"
+    );
+
+    let header = LayerHeader {
+        id,
+        dtype: DataType::F32,
+        encoding: Encoding::Fixed16,
+        shape: data.shape().try_into().unwrap(),
+        checksum: checksum(&fixed),
+        fixed: [offset, step],
+    };
+
+    write_file(path, &[&header.to_bytes(), &fixed]);
+}
+
+// The position of a state in the order of the compressed data, [ti, ai, bi].
+// States with the same dice mostly have the same strategy, so this order has
+// far more repetition than [ai, bi, ti].
+fn block_position([la, lb, _]: [usize; 3], [ai, bi, ti]: [usize; 3]) -> usize {
+    (ti * la + ai) * lb + bi
+}
+
+fn u32_at(bytes: &[u8], i: usize) -> usize {
+    u32::from_le_bytes(bytes[i..i + 4].try_into().unwrap()) as usize
+}
+
+fn u64_at(bytes: &[u8], i: usize) -> usize {
+    u64::from_le_bytes(bytes[i..i + 8].try_into().unwrap()) as usize
+}
+
+// The index after the header of a compressed file.
+struct BlockIndex {
+    block_len: usize,
+    n_blocks: usize,
+}
+
+impl BlockIndex {
+    fn read(bytes: &[u8]) -> Self {
+        Self {
+            block_len: u32_at(bytes, HEADER_LEN),
+            n_blocks: u32_at(bytes, HEADER_LEN + 4),
+        }
+    }
+
+    fn data_start(&self) -> usize {
+        HEADER_LEN + 8 + 8 * self.n_blocks
+    }
+
+    // Where block i ends, counted from the start of the blocks.
+    fn end(&self, bytes: &[u8], i: usize) -> usize {
+        u64_at(bytes, HEADER_LEN + 8 + 8 * i)
+    }
+
+    fn inflate(
+        &self,
+        bytes: &[u8],
+        i: usize,
+    ) -> Result<Vec<u8>, LayerFileError> {
+        let start = if i == 0 { 0 } else { self.end(bytes, i - 1) };
+        let end = self.end(bytes, i);
+
+        let block = bytes
+            .get(self.data_start() + start..self.data_start() + end)
+            .ok_or(LayerFileError::Corrupt("a block is outside the file"))?;
+
+        decompress_to_vec(block)
+            .map_err(|_| LayerFileError::Corrupt("a block does not inflate"))
+    }
+}
+
+// Writes the strategies of a layer compressed, which makes the caches small
+// enough to distribute. The data is reordered to [ti, ai, bi] and cut into
+// blocks of BLOCK_LEN bytes that are deflated on their own. After the header
+// come the length of the blocks and the amount of them (2 x u32), where every
+// block ends counted from the end of this index (u64 each), and then the
+// blocks. The checksum is of the data in the usual order, so it is the same as
+// in the file the layer was compressed from. The compressed data is inflated
+// and checked before it is written.
+pub fn write_compressed_layer(path: &str, id: LayerId, data: &Array3<u8>) {
+    let shape: [usize; 3] = data.shape().try_into().unwrap();
+
+    let header = LayerHeader {
+        id,
+        dtype: DataType::U8,
+        encoding: Encoding::Blocks,
+        shape,
+        checksum: checksum(data.as_slice().unwrap()),
+        fixed: [0.0; 2],
+    };
+
+    let reordered: Vec<u8> = data
+        .view()
+        .permuted_axes([2, 0, 1])
+        .iter()
+        .copied()
+        .collect();
+
+    let n_blocks = reordered.len().div_ceil(BLOCK_LEN);
+
+    let blocks: Vec<Vec<u8>> = (0..n_blocks)
+        .into_par_iter()
+        .map(|i| {
+            let end = ((i + 1) * BLOCK_LEN).min(reordered.len());
+            compress_to_vec(&reordered[i * BLOCK_LEN..end], 6)
+        })
+        .collect();
+
+    let mut index = Vec::with_capacity(8 + 8 * n_blocks);
+    index.extend_from_slice(&(BLOCK_LEN as u32).to_le_bytes());
+    index.extend_from_slice(&(n_blocks as u32).to_le_bytes());
+
+    let mut end = 0;
+    for block in &blocks {
+        end += block.len() as u64;
+        index.extend_from_slice(&end.to_le_bytes());
+    }
+
+    let header = header.to_bytes();
+
+    let mut parts = vec![&header[..], &index];
+    parts.extend(blocks.iter().map(|block| &block[..]));
+
+    let bytes = parts.concat();
+
+    match read_compressed_layer(&bytes, id, shape) {
+        Ok(read_back) if read_back == data => {}
+        Ok(_) => panic!("{path}: the strats changed when compressed"),
+        Err(err) => panic!("{path}: {err}"),
+    }
+
+    write_file(path, &[&bytes]);
+}
+
+// Checks that the index of a compressed file fits the shape of the layer and
+// the size of the file, given all of its bytes.
+pub fn check_blocks(
+    bytes: &[u8],
+    shape: [usize; 3],
+) -> Result<(), LayerFileError> {
+    let truncated = |expected: usize| LayerFileError::Truncated {
+        expected: expected as u64,
+        found: bytes.len() as u64,
+    };
+
+    if bytes.len() < HEADER_LEN + 8 {
+        return Err(truncated(HEADER_LEN + 8));
+    }
+
+    let index = BlockIndex::read(bytes);
+
+    let len = shape.iter().product::<usize>();
+    if index.block_len == 0 || index.n_blocks != len.div_ceil(index.block_len) {
+        return Err(LayerFileError::Corrupt("the blocks do not fit the shape"));
+    }
+
+    if bytes.len() < index.data_start() {
+        return Err(truncated(index.data_start()));
+    }
+
+    let expected_len = match index.n_blocks {
+        0 => index.data_start(),
+        n => index.data_start() + index.end(bytes, n - 1),
+    };
+    if bytes.len() != expected_len {
+        return Err(truncated(expected_len));
+    }
+
+    Ok(())
+}
+
+// The block of a state in a checked compressed file, given all of its bytes,
+// and the position of the state in the block.
+pub fn block_of(
+    bytes: &[u8],
+    shape: [usize; 3],
+    index: [usize; 3],
+) -> (usize, usize) {
+    let blocks = BlockIndex::read(bytes);
+
+    let position = block_position(shape, index);
+
+    (position / blocks.block_len, position % blocks.block_len)
+}
+
+// Inflates block i of a checked compressed file, given all of its bytes.
+pub fn inflate_block(
+    bytes: &[u8],
+    i: usize,
+) -> Result<Vec<u8>, LayerFileError> {
+    BlockIndex::read(bytes).inflate(bytes, i)
+}
+
+// The strategy of a single state of a checked compressed file, given all of
+// its bytes. Only the block of the state is inflated.
+pub fn compressed_byte(
+    bytes: &[u8],
+    shape: [usize; 3],
+    index: [usize; 3],
+) -> Result<u8, LayerFileError> {
+    let (block, position) = block_of(bytes, shape, index);
+
+    inflate_block(bytes, block)?
+        .get(position)
+        .copied()
+        .ok_or(LayerFileError::Corrupt("a block is too short"))
+}
+
+// Inflates all of a compressed file, given all of its bytes, back into the
+// usual order after checking the header and then that the data matches the
+// checksum.
+pub fn read_compressed_layer(
+    bytes: &[u8],
+    id: LayerId,
+    shape: [usize; 3],
+) -> Result<Array3<u8>, LayerFileError> {
+    let header =
+        check_header(bytes, bytes.len() as u64, id, DataType::U8, shape)?;
+
+    if header.encoding != Encoding::Blocks {
+        return Err(LayerFileError::Corrupt("the file is not compressed"));
+    }
+
+    check_blocks(bytes, shape)?;
+
+    let blocks = BlockIndex::read(bytes);
+
+    let mut reordered = Vec::with_capacity(shape.iter().product());
+    for i in 0..blocks.n_blocks {
+        reordered.extend(blocks.inflate(bytes, i)?);
+    }
+
+    let [la, lb, lt] = shape;
+    let data = ArrayView3::from_shape([lt, la, lb], &reordered)
+        .map_err(|_| LayerFileError::Corrupt("the blocks are too short"))?
+        .permuted_axes([1, 2, 0])
+        .as_standard_layout()
+        .into_owned();
+
+    if checksum(data.as_slice().unwrap()) != header.checksum {
+        return Err(LayerFileError::Checksum);
+    }
+
+    Ok(data)
+}
+
+// The data of a layer file, without the header if it has one.
+pub fn layer_data(bytes: &[u8]) -> &[u8] {
+    if bytes.starts_with(MAGIC) {
+        &bytes[HEADER_LEN.min(bytes.len())..]
+    } else {
+        bytes
+    }
+}
+
+// The scores of a layer file, without the header if it has one, and turned
+// back into f32 if they are fixed point.
+pub fn layer_floats(bytes: &[u8]) -> Vec<f32> {
+    let header = bytes
+        .first_chunk::<HEADER_LEN>()
+        .and_then(|header| LayerHeader::from_bytes(header).ok())
+        .filter(|header| header.encoding == Encoding::Fixed16);
+
+    match header {
+        Some(header) => layer_data(bytes)
+            .chunks_exact(2)
+            .map(|q| {
+                header.from_fixed(u16::from_ne_bytes(q.try_into().unwrap()))
+            })
+            .collect(),
+        None => layer_data(bytes)
+            .chunks_exact(4)
+            .map(|x| f32::from_ne_bytes(x.try_into().unwrap()))
+            .collect(),
+    }
+}
+
+// The layer and shape of a file in the cache, found from its path as the
+// layers name them. None if it is not a layer file.
+fn layer_of_path(path: &Path) -> Option<(LayerId, DataType, [usize; 3])> {
+    let mut dirs = path.iter().rev().map(|x| x.to_str().unwrap_or(""));
+
+    let name = dirs.next()?.strip_suffix(".dat")?;
+    let dtype = match dirs.next()? {
+        "strats" => DataType::U8,
+        "scores" | "moment2" | "moment3" => DataType::F32,
+        _ => return None,
+    };
+    // The directories of house rules and risk have their settings after
+    // an underscore.
+    let kind = dirs.next()?.split('_').next()?;
+
+    let mut index = [0; 4];
+    for (i, x) in name.split('_').enumerate() {
+        *index.get_mut(i)? = x.parse().ok()?;
+    }
+    let [na, nb, nt, need] = index;
+
+    let (id, shape) = match kind {
+        "5" | "6" | "5x" | "6x" | "5p" | "6p" => {
+            let dice = kind[..1].parse().unwrap();
+            let game = if kind.ends_with('p') {
+                Game::Target
+            } else {
+                Game::Scandinavian
+            };
+
+            let shape = if dice == 5 {
+                layer_shape::<5>(na, nb)
+            } else {
+                layer_shape::<6>(na, nb)
+            };
+
+            let id = LayerId {
+                game,
+                dice,
+                x: kind.ends_with('x'),
+                index,
+            };
+
+            (id, shape)
+        }
+        "a" => {
+            let layer = AmericanLayer {
+                na,
+                nb,
+                nt,
+                scores: None,
+                strats: None,
+            };
+
+            (layer.id(), layer.shape())
+        }
+        "5f" => (ForcedLayer::<5>::new(na).id(), ForcedLayer::<5>::shape()),
+        "6f" => (ForcedLayer::<6>::new(na).id(), ForcedLayer::<6>::shape()),
+        _ => return None,
+    };
+
+    // Only target layers have the fourth index.
+    if need != 0 && id.game != Game::Target {
+        return None;
+    }
+
+    Some((id, dtype, shape))
+}
+
+fn upgrade_dir(dir: &Path, counts: &mut [usize; 3]) {
+    for entry in read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+
+        if path.is_dir() {
+            upgrade_dir(&path, counts);
+            continue;
+        }
+
+        let Some((id, dtype, shape)) = layer_of_path(&path) else {
+            continue;
+        };
+
+        let mut data = vec![0; shape.iter().product::<usize>() * dtype.size()];
+        let mut file = File::open(&path).unwrap();
+
+        let file_len = file.metadata().unwrap().len();
+
+        match read_layer(&mut file, id, dtype, shape, &mut data) {
+            Ok(()) => counts[1] += 1,
+            Err(LayerFileError::NoHeader) if file_len == data.len() as u64 => {
+                let mut file = File::open(&path).unwrap();
+                file.read_exact(&mut data).unwrap();
+
+                // The new file is renamed over the old one, so stopping the
+                // upgrade never loses a layer.
+                write_layer(path.to_str().unwrap(), id, dtype, shape, &data);
+
+                counts[0] += 1;
+            }
+            Err(LayerFileError::NoHeader) => {
+                println!(
+                    "{}: the file has no header, and is {file_len} bytes but \
+                     should be {}",
+                    path.display(),
+                    data.len()
+                );
+                counts[2] += 1;
+            }
+            Err(err) => {
+                println!("{}: {err}", path.display());
+                counts[2] += 1;
+            }
+        }
+    }
+}
+
+// Adds headers to the layer files of a cache made before there were headers.
+// Files that already have a header are checked, and files that are neither
+// are reported and left alone.
+pub fn upgrade_cache() {
+    let mut counts = [0; 3];
+
+    upgrade_dir(Path::new(&*PREFIX), &mut counts);
+
+    let [upgraded, fine, broken] = counts;
+    println!("Added headers to {upgraded} layer files");
+    println!("{fine} layer files already had a valid header");
+    println!("{broken} layer files could not be upgraded");
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        env::temp_dir,
+        fs::{create_dir_all, read, remove_dir_all, write},
+        path::PathBuf,
+    };
+
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use super::*;
+    use crate::macrosolver::store::MappedLayer;
+
+    const ID: LayerId = LayerId {
+        game: Game::Scandinavian,
+        dice: 5,
+        x: false,
+        index: [2, 3, 1, 0],
+    };
+    const SHAPE: [usize; 3] = [4, 5, 252];
+
+    // A directory of its own for every test, since they run at the same time.
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = temp_dir()
+            .join(format!("yatzy_layer_file_{}_{name}", std::process::id()));
+
+        let _ = remove_dir_all(&dir);
+        create_dir_all(&dir).unwrap();
+
+        dir
+    }
+
+    fn test_data() -> Vec<u8> {
+        (0..SHAPE.iter().product::<usize>())
+            .map(|i| (i * 7 % 251) as u8)
+            .collect()
+    }
+
+    fn read_test_layer(
+        path: &Path,
+        id: LayerId,
+        dtype: DataType,
+        shape: [usize; 3],
+    ) -> Result<Vec<u8>, LayerFileError> {
+        let mut data = vec![0; shape.iter().product::<usize>() * dtype.size()];
+        let mut file = File::open(path).unwrap();
+
+        read_layer(&mut file, id, dtype, shape, &mut data).map(|()| data)
+    }
+
+    // Writes the test layer to the directory of the test, and returns its
+    // path and bytes.
+    fn write_test_layer(name: &str) -> (PathBuf, Vec<u8>) {
+        let path = test_dir(name).join("layer.dat");
+        write_layer(
+            path.to_str().unwrap(),
+            ID,
+            DataType::U8,
+            SHAPE,
+            &test_data(),
+        );
+
+        let bytes = read(&path).unwrap();
+
+        (path, bytes)
+    }
+
+    #[test]
+    fn round_trip() {
+        let (path, bytes) = write_test_layer("round_trip");
+
+        assert_eq!(bytes.len(), HEADER_LEN + test_data().len());
+        assert_eq!(layer_data(&bytes), test_data());
+
+        let data = read_test_layer(&path, ID, DataType::U8, SHAPE).unwrap();
+        assert_eq!(data, test_data());
+
+        remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn bad_magic_and_version() {
+        let (path, bytes) = write_test_layer("bad_magic_and_version");
+
+        let mut changed = bytes.clone();
+        changed[0] = b'X';
+        write(&path, &changed).unwrap();
+
+        assert!(matches!(
+            read_test_layer(&path, ID, DataType::U8, SHAPE),
+            Err(LayerFileError::NoHeader)
+        ));
+
+        let mut changed = bytes;
+        changed[4..6].copy_from_slice(&(VERSION + 1).to_le_bytes());
+        write(&path, &changed).unwrap();
+
+        assert!(matches!(
+            read_test_layer(&path, ID, DataType::U8, SHAPE),
+            Err(LayerFileError::Version(version)) if version == VERSION + 1
+        ));
+
+        remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn mismatch() {
+        let (path, _) = write_test_layer("mismatch");
+
+        let other_id = LayerId {
+            index: [2, 3, 0, 0],
+            ..ID
+        };
+        let other_game = LayerId {
+            game: Game::Target,
+            ..ID
+        };
+
+        for (id, dtype, shape) in [
+            (other_id, DataType::U8, SHAPE),
+            (other_game, DataType::U8, SHAPE),
+            (ID, DataType::F32, SHAPE),
+            (ID, DataType::U8, [5, 4, 252]),
+        ] {
+            assert!(matches!(
+                read_test_layer(&path, id, dtype, shape),
+                Err(LayerFileError::Mismatch { .. })
+            ));
+        }
+
+        remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn truncated() {
+        let (path, bytes) = write_test_layer("truncated");
+
+        write(&path, &bytes[..bytes.len() - 1]).unwrap();
+
+        assert!(matches!(
+            read_test_layer(&path, ID, DataType::U8, SHAPE),
+            Err(LayerFileError::Truncated { expected, found })
+                if expected == bytes.len() as u64
+                    && found == bytes.len() as u64 - 1
+        ));
+
+        remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn flipped_byte() {
+        let (path, mut bytes) = write_test_layer("flipped_byte");
+
+        bytes[HEADER_LEN + 1000] ^= 1;
+        write(&path, &bytes).unwrap();
+
+        assert!(matches!(
+            read_test_layer(&path, ID, DataType::U8, SHAPE),
+            Err(LayerFileError::Checksum)
+        ));
+
+        remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn upgrade_legacy_file() {
+        let dir = test_dir("upgrade_legacy_file");
+
+        // A scores file of the 5 dice game from before there were headers,
+        // which is just the data.
+        let (na, nb, nt) = (1, 2, 0);
+        let shape = layer_shape::<5>(na, nb);
+        let scores: Vec<u8> = (0..shape.iter().product::<usize>())
+            .flat_map(|i| (i as f32 * 0.5).to_ne_bytes())
+            .collect();
+
+        create_dir_all(dir.join("5/scores")).unwrap();
+        let path = dir.join(format!("5/scores/{na}_{nb}_{nt}.dat"));
+        write(&path, &scores).unwrap();
+
+        // A file that is not a layer, which is left alone.
+        write(dir.join("5/notes.txt"), b"not a layer").unwrap();
+
+        let mut counts = [0; 3];
+        upgrade_dir(&dir, &mut counts);
+        assert_eq!(counts, [1, 0, 0]);
+
+        let id = LayerId {
+            game: Game::Scandinavian,
+            dice: 5,
+            x: false,
+            index: [na, nb, nt, 0],
+        };
+        let data = read_test_layer(&path, id, DataType::F32, shape).unwrap();
+        assert_eq!(data, scores);
+        assert_eq!(read(dir.join("5/notes.txt")).unwrap(), b"not a layer");
+
+        // Upgrading again only checks the header.
+        let mut counts = [0; 3];
+        upgrade_dir(&dir, &mut counts);
+        assert_eq!(counts, [0, 1, 0]);
+
+        remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn compressed_round_trip() {
+        let dir = test_dir("compressed_round_trip");
+        let path = dir.join("layer.dat");
+
+        // More than two blocks, mostly zero like real strategies so that the
+        // blocks compress to different lengths.
+        let shape = [3, 50, 252];
+        let id = LayerId { x: true, ..ID };
+
+        let mut rng = StdRng::seed_from_u64(23);
+        let data = Array3::from_shape_fn(shape, |_| {
+            if rng.gen_bool(0.8) {
+                0
+            } else {
+                rng.gen()
+            }
+        });
+
+        write_compressed_layer(path.to_str().unwrap(), id, &data);
+
+        let bytes = read(&path).unwrap();
+        check_blocks(&bytes, shape).unwrap();
+        assert_eq!(read_compressed_layer(&bytes, id, shape).unwrap(), data);
+
+        // The states on both sides of every block boundary, and the first and
+        // the last state, in the [ti, ai, bi] order of the blocks.
+        let [la, lb, _] = shape;
+        let len = data.len();
+        assert!(len > 2 * BLOCK_LEN);
+
+        let positions = (1..len.div_ceil(BLOCK_LEN))
+            .flat_map(|i| [i * BLOCK_LEN - 1, i * BLOCK_LEN])
+            .chain([0, len - 1]);
+
+        for position in positions {
+            let index =
+                [position / lb % la, position % lb, position / (la * lb)];
+
+            assert_eq!(
+                compressed_byte(&bytes, shape, index).unwrap(),
+                data[index]
+            );
+        }
+
+        // Every state through the map, in the order of the blocks so that
+        // the last block inflated is used again.
+        let layer =
+            MappedLayer::open(path.to_str().unwrap(), id, DataType::U8, shape)
+                .unwrap();
+
+        for ((ti, ai, bi), &x) in
+            data.view().permuted_axes([2, 0, 1]).indexed_iter()
+        {
+            assert_eq!(layer.byte([ai, bi, ti]), x);
+        }
+
+        remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn fixed_point_round_trip() {
+        let dir = test_dir("fixed_point_round_trip");
+        let path = dir.join("layer.dat");
+        let path = path.to_str().unwrap();
+
+        let mut rng = StdRng::seed_from_u64(24);
+        let random =
+            Array3::from_shape_fn(SHAPE, |_| rng.gen_range(-50.0..400.0));
+        let constant = Array3::from_elem(SHAPE, 123.456);
+
+        for data in [random, constant] {
+            write_fixed_layer(path, ID, &data);
+
+            let bytes = read(path).unwrap();
+            let header =
+                LayerHeader::from_bytes(bytes.first_chunk().unwrap()).unwrap();
+            let [_, step] = header.fixed;
+
+            // Only a constant layer has no step.
+            assert_eq!(step == 0.0, data.iter().all(|&x| x == data[[0, 0, 0]]));
+
+            let mut read_back = vec![0; data.len() * 4];
+            let mut file = File::open(path).unwrap();
+            read_layer(&mut file, ID, DataType::F32, SHAPE, &mut read_back)
+                .unwrap();
+
+            let layer =
+                MappedLayer::open(path, ID, DataType::F32, SHAPE).unwrap();
+
+            for (((ai, bi, ti), &x), y) in
+                data.indexed_iter().zip(read_back.chunks_exact(4))
+            {
+                let y = f32::from_ne_bytes(y.try_into().unwrap());
+
+                // The scores are turned back into f32, which rounds them once
+                // more.
+                let max_error = step / 2.0 + x.abs() * f32::EPSILON;
+
+                assert!((y - x).abs() <= max_error);
+                assert!(step > 0.0 || y == x);
+                assert_eq!(layer.float([ai, bi, ti]), y);
+            }
+        }
+
+        remove_dir_all(&dir).unwrap();
+    }
+}
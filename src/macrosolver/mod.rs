@@ -1,3 +1,8 @@
+pub mod american;
+pub mod forced;
 pub mod incore;
+pub mod layer_file;
 pub mod outcore;
 pub mod outcorex;
+pub mod store;
+pub mod target;
@@ -2,28 +2,112 @@
 // to disk and can be paused and restarted.
 
 use std::{
-    fs::{create_dir_all, OpenOptions},
-    io::{Read, Write},
-    path::Path,
+    fs::{
+        create_dir_all, metadata, read, read_dir, read_to_string, remove_file,
+        write, File,
+    },
+    path::{Path, PathBuf},
     time::{Duration, Instant},
 };
 
-use ndarray::Array3;
-use once_cell::sync::Lazy;
+use ndarray::{Array3, ArrayView3};
+use once_cell::sync::{Lazy, OnceCell};
 
 use crate::{
-    dice_distributions::DICE_DISTR,
-    level_ordering::{
-        ABOVE_LEVELS_5, ABOVE_LEVELS_6, BELOW_LEVELS_5, BELOW_LEVELS_6,
+    dice_distributions::{fair_dice, DICE_PROBS, FACE_PROBS},
+    macrosolver::{
+        layer_file::{
+            layer_data, layer_floats, read_layer, write_fixed_layer,
+            write_layer, DataType, Game, LayerId,
+        },
+        outcorex::solve_x,
     },
+    rules::RuleSet,
     solver::{
-        solve_layer_type1_5dice, solve_layer_type1_6dice,
-        solve_layer_type2_5dice, solve_layer_type2_6dice,
+        solve_layer_moments, solve_layer_type1, solve_layer_type2, CellRules,
     },
+    with_rules,
+    yatzy::cell_from_dice,
 };
 
-pub static PREFIX: Lazy<String> =
-    Lazy::new(|| std::env::var("YATZY_CACHE").unwrap_or("cache".to_owned()));
+// The root of the cache. The solutions for biased dice live in their own
+// directory, named after the face probabilities.
+pub static PREFIX: Lazy<String> = Lazy::new(|| {
+    let root = std::env::var("YATZY_CACHE").unwrap_or("cache".to_owned());
+
+    if fair_dice() {
+        root
+    } else {
+        let probs = FACE_PROBS.map(|p| format!("{p:.4}")).join("_");
+
+        format!("{root}/dice_{probs}")
+    }
+});
+
+// The face probabilities of the dice are recorded in the metadata of the cache
+// the first time anything is saved, and saving to a cache made for other dice
+// is refused.
+static METADATA: Lazy<()> = Lazy::new(|| {
+    let path = format!("{}/metadata.txt", *PREFIX);
+    let line = format!(
+        "face_probs {}\n",
+        FACE_PROBS.map(|p| p.to_string()).join(" ")
+    );
+
+    create_dir_all(&*PREFIX).unwrap();
+
+    match read_to_string(&path) {
+        Ok(metadata) => {
+            assert!(metadata == line, "The cache was made for other dice!")
+        }
+        Err(_) => write(&path, line).unwrap(),
+    }
+});
+
+// Whether the scores of the free games are saved as 16 bit fixed point, which
+// halves the disk they take, set with YATZY_SCORES=fixed16. The solvers still
+// work in f32 on the scores they load. The layers then live in a separate
+// directory, so compare-caches with the f32 cache shows how many strategies
+// change.
+pub static FIXED_SCORES: Lazy<bool> =
+    Lazy::new(|| match std::env::var("YATZY_SCORES").as_deref() {
+        Err(_) | Ok("f32") => false,
+        Ok("fixed16") => true,
+        Ok(other) => panic!("Unknown score precision {other}!"),
+    });
+
+// Whether the solvers delete the scores of a column of layers as soon as no
+// layer left to solve needs them, set with YATZY_PRUNE=1, see `prune_column`.
+pub static PRUNE_SCORES: Lazy<bool> =
+    Lazy::new(|| std::env::var("YATZY_PRUNE").is_ok_and(|x| x == "1"));
+
+// The risk coefficient λ when the strategies maximize the exponential utility
+// E[-exp(-λ·score)] instead of the expected score. It is set at most once at
+// startup, and the layers then live in a separate directory for each λ.
+pub static RISK: OnceCell<f32> = OnceCell::new();
+
+// The amount of rerolls per turn for the solvers without saved throws, when
+// playing with house rules that differ from those of the rule set. It is set
+// at most once at startup, and the layers then live in a separate directory.
+pub static REROLLS: OnceCell<usize> = OnceCell::new();
+
+pub fn rerolls<const N: usize>() -> usize {
+    REROLLS
+        .get()
+        .copied()
+        .unwrap_or(with_rules!(N, R => R::THROWS) - 1)
+}
+
+// The most throws that can be saved for later turns with saved throws, when
+// playing with house rules that cap them. It is set at most once at startup,
+// and the layers then live in a separate directory for each cap.
+pub static MAX_SAVED: OnceCell<usize> = OnceCell::new();
+
+// The throws left at the start of the next turn with saved throws, after
+// putting the dice in a cell with nt throws left.
+pub fn next_turn_throws(nt: usize) -> usize {
+    MAX_SAVED.get().map_or(nt, |&cap| nt.min(cap)) + 2
+}
 
 pub struct Layer<const N: usize, const X: bool> {
     pub na: usize,
@@ -66,76 +150,138 @@ impl<const N: usize, const X: bool> Layer<N, X> {
         format!("{}_{}_{}.dat", self.na, self.nb, self.nt)
     }
 
-    pub fn scores_path(&self) -> String {
-        if X {
-            format!("{}/{N}x/scores/{}", &*PREFIX, &self.name())
-        } else {
-            format!("{}/{N}/scores/{}", &*PREFIX, &self.name())
+    pub fn dir(&self) -> String {
+        let x = if X { "x" } else { "" };
+
+        let mut dir = format!("{}/{N}{x}", *PREFIX);
+
+        let rerolls = rerolls::<N>();
+        if !X && rerolls != with_rules!(N, R => R::THROWS) - 1 {
+            dir += &format!("_rerolls{rerolls}");
+        }
+
+        if let (true, Some(cap)) = (X, MAX_SAVED.get()) {
+            dir += &format!("_max{cap}");
+        }
+
+        if let Some(lambda) = RISK.get() {
+            dir += &format!("_risk{lambda}");
+        }
+
+        if *FIXED_SCORES {
+            dir += "_fixed16";
         }
+
+        dir
     }
 
-    pub fn strats_path(&self) -> String {
-        if X {
-            format!("{}/{N}x/strats/{}", &*PREFIX, &self.name())
-        } else {
-            format!("{}/{N}/strats/{}", &*PREFIX, &self.name())
+    pub fn id(&self) -> LayerId {
+        LayerId {
+            game: Game::Scandinavian,
+            dice: N,
+            x: X,
+            index: [self.na, self.nb, self.nt, 0],
         }
     }
 
-    pub fn save_scores(&self) {
-        let mut file = OpenOptions::new()
-            .write(true)
-            .create(true)
-            .truncate(true)
-            .open(self.scores_path())
-            .unwrap();
+    pub fn scores_path(&self) -> String {
+        format!("{}/scores/{}", self.dir(), self.name())
+    }
 
-        let data = self.scores.as_ref().unwrap().as_slice().unwrap();
-        let bytes = floats_to_bytes(data);
+    // An empty file in place of scores deleted on purpose, see `prune_column`.
+    pub fn pruned_path(&self) -> String {
+        format!(
+            "{}/scores/{}_{}_{}.pruned",
+            self.dir(),
+            self.na,
+            self.nb,
+            self.nt
+        )
+    }
 
-        file.write_all(bytes).unwrap();
+    pub fn strats_path(&self) -> String {
+        format!("{}/strats/{}", self.dir(), self.name())
     }
 
-    pub fn save_strats(&self) {
-        let mut file = OpenOptions::new()
-            .write(true)
-            .create(true)
-            .truncate(true)
-            .open(self.strats_path())
-            .unwrap();
+    // The strategies compressed for distribution, see `compress_strats`.
+    pub fn compressed_strats_path(&self) -> String {
+        format!("{}/strats_z/{}", self.dir(), self.name())
+    }
+
+    // Path to the k-th moment of the remaining score, for k = 2 or 3.
+    // The first moment is the expected score, which is in the scores file.
+    pub fn moment_path(&self, k: usize) -> String {
+        format!("{}/moment{k}/{}", self.dir(), self.name())
+    }
 
-        let data = self.strats.as_ref().unwrap().as_slice().unwrap();
+    pub fn save_scores(&self) {
+        let scores = self.scores.as_ref().unwrap();
 
-        file.write_all(data).unwrap();
+        if *FIXED_SCORES {
+            Lazy::force(&METADATA);
+            write_fixed_layer(&self.scores_path(), self.id(), scores);
+        } else {
+            save_floats(&self.scores_path(), self.id(), scores);
+        }
+    }
+
+    pub fn save_strats(&self) {
+        save_bytes(
+            &self.strats_path(),
+            self.id(),
+            self.strats.as_ref().unwrap(),
+        );
     }
 
     pub fn is_done(&self) -> bool {
-        Path::new(&self.scores_path()).exists()
+        (Path::new(&self.scores_path()).exists()
+            || Path::new(&self.pruned_path()).exists())
             && Path::new(&self.strats_path()).exists()
     }
-}
 
-impl<const X: bool> Layer<5, X> {
-    pub fn load_scores(&mut self) -> Option<()> {
-        if self.scores.is_none() {
-            let mut file = OpenOptions::new()
-                .read(true)
-                .open(self.scores_path())
-                .ok()?;
+    // The moments and the risk need the scores of every layer they depend on,
+    // so they cannot be found from a layer whose scores have been pruned.
+    pub fn assert_not_pruned(&self) {
+        assert!(
+            !Path::new(&self.pruned_path()).exists(),
+            "The scores of layer ({}, {}, {}) in {} have been pruned! Delete \
+             the .pruned files and solve the layers again without \
+             YATZY_PRUNE=1 first.",
+            self.na,
+            self.nb,
+            self.nt,
+            self.dir()
+        );
+    }
 
-            let mut scores = Array3::zeros([
-                ABOVE_LEVELS_5[self.na].len(),
-                BELOW_LEVELS_5[self.nb].len(),
-                DICE_DISTR.5.len(),
-            ]);
+    pub fn moments_done(&self) -> bool {
+        Path::new(&self.moment_path(2)).exists()
+            && Path::new(&self.moment_path(3)).exists()
+    }
 
-            let data = scores.as_slice_mut().unwrap();
+    pub fn save_moments(&self, moments: &[Array3<f32>; 3]) {
+        save_floats(&self.moment_path(2), self.id(), &moments[1]);
+        save_floats(&self.moment_path(3), self.id(), &moments[2]);
+    }
 
-            let bytes: &mut [u8] = floats_to_bytes_mut(data);
+    // Loads the first three moments of the remaining score.
+    pub fn load_moments(&self) -> Option<[Array3<f32>; 3]> {
+        let shape = layer_shape::<N>(self.na, self.nb);
 
-            file.read_exact(bytes).unwrap();
+        Some([
+            load_floats(&self.scores_path(), self.id(), shape)?,
+            load_floats(&self.moment_path(2), self.id(), shape)?,
+            load_floats(&self.moment_path(3), self.id(), shape)?,
+        ])
+    }
 
-            self.scores = Some(scores);
+    pub fn load_scores(&mut self) -> Option<()> {
+        if self.scores.is_none() {
+            self.scores = Some(load_floats(
+                &self.scores_path(),
+                self.id(),
+                layer_shape::<N>(self.na, self.nb),
+            )?);
         }
 
         Some(())
@@ -143,107 +289,330 @@ impl<const X: bool> Layer<5, X> {
 
     pub fn load_strats(&mut self) -> Option<()> {
         if self.strats.is_none() {
-            let mut file = OpenOptions::new()
-                .read(true)
-                .open(self.strats_path())
-                .ok()?;
+            self.strats = Some(load_bytes(
+                &self.strats_path(),
+                self.id(),
+                layer_shape::<N>(self.na, self.nb),
+            )?);
+        }
 
-            let mut strats = Array3::zeros([
-                ABOVE_LEVELS_5[self.na].len(),
-                BELOW_LEVELS_5[self.nb].len(),
-                DICE_DISTR.5.len(),
-            ]);
+        Some(())
+    }
+}
 
-            let data = strats.as_slice_mut().unwrap();
+// The shape of the (ai, bi, ti) array for a layer with na and nb filled cells
+// above and below the line.
+pub fn layer_shape<const N: usize>(na: usize, nb: usize) -> [usize; 3] {
+    with_rules!(N, R => {
+        let [n_ai, n_bi] = R::layer_dim(na, nb);
 
-            file.read_exact(data).unwrap();
+        [n_ai, n_bi, DICE_PROBS[R::DICE].len()]
+    })
+}
 
-            self.strats = Some(strats);
-        }
+// The most throws left in the layers of the column (na, nb).
+pub fn max_throws_left<const N: usize, const X: bool>(
+    na: usize,
+    nb: usize,
+) -> usize {
+    if X {
+        next_turn_throws((na + nb) * 2)
+    } else {
+        rerolls::<N>()
+    }
+}
 
-        Some(())
+// Deletes the scores of the column of layers (na, nb) if it is solved and so
+// are the columns (na - 1, nb) and (na, nb - 1), which are the only ones that
+// use them. The first column is kept, since it has the expected score of the
+// game and is tiny. The guide can still find the expected score of a state
+// late in the game from the strategies, see `Rescorer`, but the moments can
+// no longer be solved. Returns the bytes freed.
+pub fn prune_column<const N: usize, const X: bool>(
+    na: usize,
+    nb: usize,
+) -> u64 {
+    if (na, nb) == (0, 0) || na > 6 || nb > cell_from_dice::<N>() - 6 {
+        return 0;
+    }
+
+    let column = |na, nb| {
+        (0..=max_throws_left::<N, X>(na, nb)).map(move |nt| Layer::<N, X> {
+            na,
+            nb,
+            nt,
+            scores: None,
+            strats: None,
+        })
+    };
+
+    let solved = |na, nb| column(na, nb).all(|layer| layer.is_done());
+
+    if !solved(na, nb)
+        || (na > 0 && !solved(na - 1, nb))
+        || (nb > 0 && !solved(na, nb - 1))
+    {
+        return 0;
+    }
+
+    let mut freed = 0;
+
+    for layer in column(na, nb) {
+        let path = layer.scores_path();
+
+        let Ok(file) = metadata(&path) else {
+            continue;
+        };
+
+        // The marker is written first, so stopping halfway never leaves a
+        // layer that counts as unsolved.
+        write(layer.pruned_path(), "").unwrap();
+        remove_file(&path).unwrap();
+
+        freed += file.len();
+    }
+
+    freed
+}
+
+// Deletes the scores of every column that no layer left to solve needs, for
+// caches that are only used to play and simulate.
+pub fn prune_scores<const N: usize, const X: bool>() {
+    let mut n_columns = 0;
+    let mut freed = 0;
+
+    for na in 0..=6 {
+        for nb in 0..=cell_from_dice::<N>() - 6 {
+            let column = prune_column::<N, X>(na, nb);
+
+            if column > 0 {
+                println!("na: {na:2}, nb: {nb:2}, freed {column} bytes");
+                n_columns += 1;
+            }
+
+            freed += column;
+        }
     }
+
+    println!(
+        "\nPruned the scores of {n_columns} columns, freed {:.2} GiB",
+        freed as f64 / (1u64 << 30) as f64
+    );
 }
 
-impl<const X: bool> Layer<6, X> {
-    pub fn load_scores(&mut self) -> Option<()> {
-        if self.scores.is_none() {
-            let mut file = OpenOptions::new()
-                .read(true)
-                .open(self.scores_path())
-                .ok()?;
+pub fn moment_views(moments: &[Array3<f32>; 3]) -> [ArrayView3<'_, f32>; 3] {
+    moments.each_ref().map(|x| x.view())
+}
+
+pub fn save_floats(path: &str, id: LayerId, data: &Array3<f32>) {
+    Lazy::force(&METADATA);
+
+    write_layer(
+        path,
+        id,
+        DataType::F32,
+        data.shape().try_into().unwrap(),
+        floats_to_bytes(data.as_slice().unwrap()),
+    );
+}
 
-            let mut scores = Array3::zeros([
-                ABOVE_LEVELS_6[self.na].len(),
-                BELOW_LEVELS_6[self.nb].len(),
-                DICE_DISTR.6.len(),
-            ]);
+pub fn save_bytes(path: &str, id: LayerId, data: &Array3<u8>) {
+    Lazy::force(&METADATA);
 
-            let data = scores.as_slice_mut().unwrap();
+    write_layer(
+        path,
+        id,
+        DataType::U8,
+        data.shape().try_into().unwrap(),
+        data.as_slice().unwrap(),
+    );
+}
+
+// Loads the layer file at path, or returns None if there is none. A file that
+// is not the expected layer is an error, as is one that is cut short or
+// corrupted.
+pub fn load_floats(
+    path: &str,
+    id: LayerId,
+    shape: [usize; 3],
+) -> Option<Array3<f32>> {
+    let mut file = File::open(path).ok()?;
+
+    let mut data = Array3::zeros(shape);
+
+    let bytes = floats_to_bytes_mut(data.as_slice_mut().unwrap());
 
-            let bytes: &mut [u8] = floats_to_bytes_mut(data);
+    read_layer(&mut file, id, DataType::F32, shape, bytes)
+        .unwrap_or_else(|err| panic!("Could not load {path}: {err}"));
+
+    Some(data)
+}
+
+pub fn load_bytes(
+    path: &str,
+    id: LayerId,
+    shape: [usize; 3],
+) -> Option<Array3<u8>> {
+    let mut file = File::open(path).ok()?;
+
+    let mut data = Array3::zeros(shape);
+
+    read_layer(
+        &mut file,
+        id,
+        DataType::U8,
+        shape,
+        data.as_slice_mut().unwrap(),
+    )
+    .unwrap_or_else(|err| panic!("Could not load {path}: {err}"));
+
+    Some(data)
+}
 
-            file.read_exact(bytes).unwrap();
+fn dat_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    for entry in read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
 
-            self.scores = Some(scores);
+        // The compressed strats are checked against the strats when they are
+        // made, so only the strats are compared.
+        if path.ends_with("strats_z") {
+            continue;
         }
 
-        Some(())
+        if path.is_dir() {
+            dat_files(&path, files);
+        } else if path.extension().is_some_and(|ext| ext == "dat") {
+            files.push(path);
+        }
     }
+}
 
-    pub fn load_strats(&mut self) -> Option<()> {
-        if self.strats.is_none() {
-            let mut file = OpenOptions::new()
-                .read(true)
-                .open(self.strats_path())
-                .ok()?;
+pub const COMPARE_CACHES_USAGE: &str = r#"
+usage: compare-caches <a> <b> [tolerance]
+Compares the scores of the layers cache directories <a> and <b> have in
+common, and counts the strategies that differ. The tolerance defaults to 1e-3.
+
+The precision report for fixed point scores with 5 dice is made with
+    yatzy_solver compute-strats-5
+    YATZY_SCORES=fixed16 yatzy_solver compute-strats-5
+    yatzy_solver compare-caches cache/5 cache/5_fixed16 0.01
+"#;
+
+// Compares the layers solved in cache `a` with the same layers in cache `b`,
+// to check that changes to the solvers keep the scores within `tolerance`.
+// The strats are only counted, since close choices may go either way when
+// the scores are rounded differently. Comparing with a cache of fixed point
+// scores shows how many choices the lower precision changes. Returns whether
+// all the scores match.
+pub fn compare_caches(a: &Path, b: &Path, tolerance: f32) -> bool {
+    let mut files = Vec::new();
+    dat_files(a, &mut files);
+    files.sort();
+
+    let mut ok = true;
+    let mut n_files = 0;
+    let mut max_diff = 0.0f32;
+    let mut n_strats = 0;
+    let mut strat_diffs = 0;
+    let mut strat_layers = 0;
+
+    for path in files {
+        let name = path.strip_prefix(a).unwrap();
+
+        let Ok(other) = read(b.join(name)) else {
+            continue;
+        };
+        let this = read(&path).unwrap();
+
+        n_files += 1;
+
+        if name.iter().any(|dir| dir == "strats") {
+            let (this, other) = (layer_data(&this), layer_data(&other));
+
+            if this.len() != other.len() {
+                println!("{}: the sizes differ", name.display());
+                ok = false;
+                continue;
+            }
+
+            let diffs = this.iter().zip(other).filter(|(x, y)| x != y).count();
 
-            let mut strats = Array3::zeros([
-                ABOVE_LEVELS_6[self.na].len(),
-                BELOW_LEVELS_6[self.nb].len(),
-                DICE_DISTR.6.len(),
-            ]);
+            n_strats += this.len();
+            strat_diffs += diffs;
+            strat_layers += (diffs > 0) as usize;
+        } else {
+            // Either cache may have fixed point scores.
+            let (this, other) = (layer_floats(&this), layer_floats(&other));
 
-            let data = strats.as_slice_mut().unwrap();
+            if this.len() != other.len() {
+                println!("{}: the sizes differ", name.display());
+                ok = false;
+                continue;
+            }
 
-            file.read_exact(data).unwrap();
+            let diff = this
+                .iter()
+                .zip(&other)
+                .map(|(x, y)| (x - y).abs())
+                .fold(0.0, f32::max);
 
-            self.strats = Some(strats);
-        }
+            if diff > tolerance {
+                println!("{}: scores differ by {diff:e}", name.display());
+                ok = false;
+            }
 
-        Some(())
+            max_diff = max_diff.max(diff);
+        }
     }
+
+    println!("Compared {n_files} layer files");
+    println!("Largest score difference: {max_diff:e}");
+    println!(
+        "Differing strats: {strat_diffs} of {n_strats} ({:.4}%), in \
+         {strat_layers} layers",
+        100.0 * strat_diffs as f64 / n_strats.max(1) as f64
+    );
+
+    ok
 }
 
-pub fn make_thin_layers_5dice() -> Array3<Option<Layer<5, false>>> {
-    Array3::from_shape_fn([7, 10, 3], |(na, nb, nt)| {
-        Some(Layer {
-            na,
-            nb,
-            nt,
-            scores: None,
-            strats: None,
-        })
-    })
+pub fn make_thin_layers<const N: usize>() -> Array3<Option<Layer<N, false>>> {
+    let n_below = cell_from_dice::<N>() - 6;
+
+    Array3::from_shape_fn(
+        [7, n_below + 1, rerolls::<N>() + 1],
+        |(na, nb, nt)| {
+            Some(Layer {
+                na,
+                nb,
+                nt,
+                scores: None,
+                strats: None,
+            })
+        },
+    )
 }
 
-pub fn make_thin_layers_6dice() -> Array3<Option<Layer<6, false>>> {
-    Array3::from_shape_fn([7, 15, 3], |(na, nb, nt)| {
-        Some(Layer {
-            na,
-            nb,
-            nt,
-            scores: None,
-            strats: None,
-        })
-    })
+// Solves every layer of the game with N dice, the ones with saved throws if X.
+// The layers that are already solved are skipped, so a stopped solve can be
+// continued.
+pub fn solve<const N: usize, const X: bool>() {
+    if X {
+        solve_x::<N>();
+    } else {
+        solve_thin::<N>();
+    }
 }
 
-pub fn solve_5dice() {
-    create_dir_all(format!("{}/5/scores/", *PREFIX)).unwrap();
-    create_dir_all(format!("{}/5/strats/", *PREFIX)).unwrap();
+fn solve_thin<const N: usize>() {
+    let dir = Layer::<N, false>::empty().dir();
+    let rerolls = rerolls::<N>();
+    let n_below = cell_from_dice::<N>() - 6;
 
-    let mut layers = make_thin_layers_5dice();
+    create_dir_all(format!("{dir}/scores/")).unwrap();
+    create_dir_all(format!("{dir}/strats/")).unwrap();
+
+    let mut layers = make_thin_layers::<N>();
 
     let global_timer = Instant::now();
     let mut load_timer = Duration::ZERO;
@@ -251,23 +620,22 @@ pub fn solve_5dice() {
     let mut compute_timer = Duration::ZERO;
 
     for na in (0..7).rev() {
-        for nb in (0..10).rev() {
+        for nb in (0..=n_below).rev() {
             println!("=============================");
             println!("na: {na:2}, nb: {nb:2}, nt: 0");
 
-            let mut layer: Layer<5, false> =
-                layers[[na, nb, 0]].take().unwrap();
+            let mut layer = layers[[na, nb, 0]].take().unwrap();
 
             if layer.is_done() {
                 println!("Already done!");
             } else {
                 let mut prev_above_layer = layers
-                    .get_mut([na + 1, nb, 2])
+                    .get_mut([na + 1, nb, rerolls])
                     .unwrap_or(&mut Some(Layer::empty()))
                     .take()
                     .unwrap();
                 let mut prev_below_layer = layers
-                    .get_mut([na, nb + 1, 2])
+                    .get_mut([na, nb + 1, rerolls])
                     .unwrap_or(&mut Some(Layer::empty()))
                     .take()
                     .unwrap();
@@ -283,12 +651,14 @@ pub fn solve_5dice() {
 
                 let timer = Instant::now();
 
-                let (scores, strats) = solve_layer_type1_5dice(
-                    na,
-                    nb,
-                    &prev_above_layer.scores.unwrap(),
-                    &prev_below_layer.scores.unwrap(),
-                );
+                let (scores, strats) = with_rules!(N, R => {
+                    solve_layer_type1::<R>(
+                        na,
+                        nb,
+                        &prev_above_layer.scores.unwrap(),
+                        &prev_below_layer.scores.unwrap(),
+                    )
+                });
 
                 let t = timer.elapsed();
                 println!("Solving took {t:.2?}");
@@ -297,11 +667,11 @@ pub fn solve_5dice() {
                 prev_above_layer.scores = None;
                 prev_below_layer.scores = None;
 
-                if let Some(x) = layers.get_mut([na + 1, nb, 2]) {
+                if let Some(x) = layers.get_mut([na + 1, nb, rerolls]) {
                     *x = Some(prev_above_layer);
                 }
 
-                if let Some(x) = layers.get_mut([na, nb + 1, 2]) {
+                if let Some(x) = layers.get_mut([na, nb + 1, rerolls]) {
                     *x = Some(prev_below_layer);
                 }
 
@@ -321,7 +691,7 @@ pub fn solve_5dice() {
             layer.strats = None;
             layers[[na, nb, 0]] = Some(layer);
 
-            for nt in 1..3 {
+            for nt in 1..=rerolls {
                 println!("--------------------------------");
                 println!("na: {na:2}, nb: {nb:2}, nt: {nt}");
 
@@ -343,11 +713,8 @@ pub fn solve_5dice() {
 
                     let timer = Instant::now();
 
-                    let (scores, strats) = solve_layer_type2_5dice(
-                        na,
-                        nb,
-                        &prev_layer.scores.unwrap(),
-                    );
+                    let (scores, strats) =
+                        solve_layer_type2::<N>(&prev_layer.scores.unwrap());
 
                     let t = timer.elapsed();
                     println!("Solving took {t:.2?}");
@@ -373,6 +740,11 @@ pub fn solve_5dice() {
                 layer.strats = None;
                 layers[[na, nb, nt]] = Some(layer);
             }
+
+            if *PRUNE_SCORES {
+                prune_column::<N, false>(na + 1, nb);
+                prune_column::<N, false>(na, nb + 1);
+            }
         }
     }
 
@@ -382,11 +754,31 @@ pub fn solve_5dice() {
     println!("Saving  time: {save_timer:.2?}");
 }
 
-pub fn solve_6dice() {
-    create_dir_all(format!("{}/6/scores/", *PREFIX)).unwrap();
-    create_dir_all(format!("{}/6/strats/", *PREFIX)).unwrap();
+// Finds the second and third moments of the remaining score when following
+// the already computed strategies, going through the layers in the same order
+// as the solver.
+pub fn solve_moments<const N: usize>() {
+    let dir = Layer::<N, false>::empty().dir();
+    let rerolls = rerolls::<N>();
+
+    create_dir_all(format!("{dir}/moment2/")).unwrap();
+    create_dir_all(format!("{dir}/moment3/")).unwrap();
 
-    let mut layers = make_thin_layers_6dice();
+    let n_below = cell_from_dice::<N>() - 6;
+
+    let load_moments = |na, nb, nt| {
+        let layer = Layer::<N, false> {
+            na,
+            nb,
+            nt,
+            scores: None,
+            strats: None,
+        };
+
+        layer.assert_not_pruned();
+
+        layer.load_moments().expect("Previous layer is not solved!")
+    };
 
     let global_timer = Instant::now();
     let mut load_timer = Duration::ZERO;
@@ -394,30 +786,38 @@ pub fn solve_6dice() {
     let mut compute_timer = Duration::ZERO;
 
     for na in (0..7).rev() {
-        for nb in (0..15).rev() {
-            println!("=============================");
-            println!("na: {na:2}, nb: {nb:2}, nt: 0");
+        for nb in (0..=n_below).rev() {
+            for nt in 0..=rerolls {
+                println!("=============================");
+                println!("na: {na:2}, nb: {nb:2}, nt: {nt}");
 
-            let mut layer = layers[[na, nb, 0]].take().unwrap();
+                let mut layer = Layer::<N, false> {
+                    na,
+                    nb,
+                    nt,
+                    scores: None,
+                    strats: None,
+                };
 
-            if layer.is_done() {
-                println!("Already done!");
-            } else {
-                let mut prev_above_layer = layers
-                    .get_mut([na + 1, nb, 2])
-                    .unwrap_or(&mut Some(Layer::empty()))
-                    .take()
-                    .unwrap();
-                let mut prev_below_layer = layers
-                    .get_mut([na, nb + 1, 2])
-                    .unwrap_or(&mut Some(Layer::empty()))
-                    .take()
-                    .unwrap();
+                if layer.moments_done() {
+                    println!("Already done!");
+                    continue;
+                }
 
                 let timer = Instant::now();
 
-                prev_above_layer.load_scores();
-                prev_below_layer.load_scores();
+                layer.load_strats().expect("Strategy is not computed!");
+
+                let (prev_above, prev_below, prev_throw) = if nt == 0 {
+                    (
+                        (na < 6).then(|| load_moments(na + 1, nb, rerolls)),
+                        (nb < n_below)
+                            .then(|| load_moments(na, nb + 1, rerolls)),
+                        None,
+                    )
+                } else {
+                    (None, None, Some(load_moments(na, nb, nt - 1)))
+                };
 
                 let t = timer.elapsed();
                 println!("Loading took {t:.2?}");
@@ -425,97 +825,34 @@ pub fn solve_6dice() {
 
                 let timer = Instant::now();
 
-                let (scores, strats) = solve_layer_type1_6dice(
-                    na,
-                    nb,
-                    &prev_above_layer.scores.unwrap(),
-                    &prev_below_layer.scores.unwrap(),
-                );
+                let prev_above = prev_above.as_ref().map(moment_views);
+                let prev_below = prev_below.as_ref().map(moment_views);
+                let prev_throw = prev_throw.as_ref().map(moment_views);
+
+                let moments = with_rules!(N, R => {
+                    solve_layer_moments::<R, false>(
+                        na,
+                        nb,
+                        nt,
+                        layer.strats.as_ref().unwrap().view(),
+                        prev_above.as_ref(),
+                        prev_below.as_ref(),
+                        prev_throw.as_ref(),
+                    )
+                });
 
                 let t = timer.elapsed();
                 println!("Solving took {t:.2?}");
                 compute_timer += t;
 
-                prev_above_layer.scores = None;
-                prev_below_layer.scores = None;
-
-                if let Some(x) = layers.get_mut([na + 1, nb, 2]) {
-                    *x = Some(prev_above_layer);
-                }
-
-                if let Some(x) = layers.get_mut([na, nb + 1, 2]) {
-                    *x = Some(prev_below_layer);
-                }
-
-                layer.scores = Some(scores);
-                layer.strats = Some(strats);
-
                 let timer = Instant::now();
 
-                layer.save_scores();
-                layer.save_strats();
+                layer.save_moments(&moments);
 
                 let t = timer.elapsed();
-                println!("Saving  took {t:.2?}");
+                println!("Saving took  {t:.2?}");
                 save_timer += t;
             }
-
-            layer.strats = None;
-            layers[[na, nb, 0]] = Some(layer);
-
-            for nt in 1..3 {
-                println!("--------------------------------");
-                println!("na: {na:2}, nb: {nb:2}, nt: {nt}");
-
-                let mut layer: Layer<6, false> =
-                    layers[[na, nb, nt]].take().unwrap();
-
-                if layer.is_done() {
-                    println!("Already done!");
-                } else {
-                    let mut prev_layer =
-                        layers[[na, nb, nt - 1]].take().unwrap();
-
-                    let timer = Instant::now();
-
-                    prev_layer.load_scores();
-
-                    let t = timer.elapsed();
-                    println!("Loading took {t:.2?}");
-                    load_timer += t;
-
-                    let timer = Instant::now();
-
-                    let (scores, strats) = solve_layer_type2_6dice(
-                        na,
-                        nb,
-                        &prev_layer.scores.unwrap(),
-                    );
-
-                    let t = timer.elapsed();
-                    println!("Solving took {t:.2?}");
-                    compute_timer += t;
-
-                    prev_layer.scores = None;
-
-                    layers[[na, nb, nt - 1]] = Some(prev_layer);
-
-                    layer.scores = Some(scores);
-                    layer.strats = Some(strats);
-
-                    let timer = Instant::now();
-
-                    layer.save_scores();
-                    layer.save_strats();
-
-                    let t = timer.elapsed();
-                    println!("Saving  took {t:.2?}");
-                    save_timer += t;
-                }
-
-                layer.strats = None;
-                layers[[na, nb, nt]] = Some(layer);
-            }
         }
     }
 
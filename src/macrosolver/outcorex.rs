@@ -8,48 +8,63 @@ use crossbeam::channel::bounded;
 use ndarray::Array3;
 
 use crate::{
-    macrosolver::outcore::{Layer, PREFIX},
-    solver::{solve_layer_5dicex, solve_layer_6dicex},
+    macrosolver::outcore::{
+        moment_views, next_turn_throws, prune_column, Layer, PRUNE_SCORES, RISK,
+    },
+    solver::{solve_layer_moments, solve_layer_risk, solve_layer_x},
+    with_rules,
+    yatzy::cell_from_dice,
 };
 
-pub fn make_thin_layers_5dicex() -> Array3<Option<Layer<5, true>>> {
-    Array3::from_shape_fn([7, 10, 3 + 15 * 2], |(na, nb, nt)| {
-        Some(Layer {
-            na,
-            nb,
-            nt,
-            scores: None,
-            strats: None,
-        })
-    })
+pub fn make_thin_layers_x<const N: usize>() -> Array3<Option<Layer<N, true>>> {
+    let n_cells = cell_from_dice::<N>();
+
+    Array3::from_shape_fn(
+        [7, n_cells - 5, next_turn_throws(n_cells * 2) + 1],
+        |(na, nb, nt)| {
+            Some(Layer {
+                na,
+                nb,
+                nt,
+                scores: None,
+                strats: None,
+            })
+        },
+    )
 }
 
-pub fn make_thin_layers_6dicex() -> Array3<Option<Layer<6, true>>> {
-    Array3::from_shape_fn([7, 15, 3 + 20 * 2], |(na, nb, nt)| {
-        Some(Layer {
-            na,
-            nb,
-            nt,
-            scores: None,
-            strats: None,
-        })
-    })
-}
+// Solves every layer of the game with N dice and saved throws. The scores of
+// the next prev above and below layers are loaded in the background while a
+// layer is solved.
+pub fn solve_x<const N: usize>() {
+    let dir = Layer::<N, true>::empty().dir();
+    let n_below = cell_from_dice::<N>() - 6;
 
-pub fn solve_5dicex() {
-    create_dir_all(format!("{}/5x/scores/", *PREFIX)).unwrap();
-    create_dir_all(format!("{}/5x/strats/", *PREFIX)).unwrap();
+    create_dir_all(format!("{dir}/scores/")).unwrap();
+    create_dir_all(format!("{dir}/strats/")).unwrap();
 
-    let mut layers = make_thin_layers_5dicex();
+    let mut layers = make_thin_layers_x::<N>();
 
     let global_timer = Instant::now();
     let mut load_timer = Duration::ZERO;
     let mut save_timer = Duration::ZERO;
     let mut compute_timer = Duration::ZERO;
 
+    let (ins_send, ins_rec) = bounded::<Layer<N, true>>(2);
+    let (res_send, res_rec) = bounded::<Layer<N, true>>(2);
+
+    thread::spawn(move || {
+        for mut l in ins_rec {
+            l.load_scores();
+            res_send.send(l).unwrap();
+        }
+    });
+
+    let mut loading = 0;
+
     for na in (0..=6).rev() {
-        for nb in (0..=9).rev() {
-            let nt_max = (na + nb) * 2 + 2;
+        for nb in (0..=n_below).rev() {
+            let nt_max = next_turn_throws((na + nb) * 2);
 
             for nt in 0..=nt_max {
                 println!("=============================");
@@ -60,26 +75,55 @@ pub fn solve_5dicex() {
                 if layer.is_done() {
                     println!("Already done!");
                 } else {
+                    while loading > 0 {
+                        let l = res_rec.recv().unwrap();
+                        let na = l.na;
+                        let nb = l.nb;
+                        let nt = l.nt;
+                        layers[[na, nb, nt]] = Some(l);
+                        loading -= 1;
+                    }
+
+                    // With a cap on saved throws the last turns use the
+                    // same prev layers, which are then kept in memory.
+                    let saved = next_turn_throws(nt);
+                    let reuse =
+                        nt < nt_max && next_turn_throws(nt + 1) == saved;
+
                     let mut prev_above_layer = layers
-                        .get_mut([na + 1, nb, nt + 2])
+                        .get_mut([na + 1, nb, saved])
                         .map(|x| x.take().unwrap());
                     let mut prev_below_layer = layers
-                        .get_mut([na, nb + 1, nt + 2])
+                        .get_mut([na, nb + 1, saved])
                         .map(|x| x.take().unwrap());
                     let mut prev_throw_layer = layers
                         .get_mut([na, nb, nt.wrapping_sub(1)])
                         .map(|x| x.take().unwrap());
 
+                    if nt < nt_max && !reuse {
+                        let next_saved = next_turn_throws(nt + 1);
+
+                        let next_above_layer = layers
+                            .get_mut([na + 1, nb, next_saved])
+                            .map_or_else(Layer::empty, |l| l.take().unwrap());
+                        let next_below_layer = layers
+                            .get_mut([na, nb + 1, next_saved])
+                            .map_or_else(Layer::empty, |l| l.take().unwrap());
+
+                        ins_send.send(next_above_layer).unwrap();
+                        ins_send.send(next_below_layer).unwrap();
+
+                        loading += 2;
+                    }
+
                     let timer = Instant::now();
 
                     if let Some(l) = prev_above_layer.as_mut() {
                         l.load_scores();
                     }
-
                     if let Some(l) = prev_below_layer.as_mut() {
                         l.load_scores();
                     }
-
                     if let Some(l) = prev_throw_layer.as_mut() {
                         l.load_scores();
                     }
@@ -90,39 +134,43 @@ pub fn solve_5dicex() {
 
                     let timer = Instant::now();
 
-                    let (scores, strats) = solve_layer_5dicex(
-                        na,
-                        nb,
-                        prev_above_layer
-                            .as_ref()
-                            .map(|l| l.scores.as_ref().unwrap().view()),
-                        prev_below_layer
-                            .as_ref()
-                            .map(|l| l.scores.as_ref().unwrap().view()),
-                        prev_throw_layer
-                            .as_ref()
-                            .map(|l| l.scores.as_ref().unwrap().view()),
-                    );
+                    let (scores, strats) = with_rules!(N, R => {
+                        solve_layer_x::<R>(
+                            na,
+                            nb,
+                            prev_above_layer
+                                .as_ref()
+                                .map(|l| l.scores.as_ref().unwrap().view()),
+                            prev_below_layer
+                                .as_ref()
+                                .map(|l| l.scores.as_ref().unwrap().view()),
+                            prev_throw_layer
+                                .as_ref()
+                                .map(|l| l.scores.as_ref().unwrap().view()),
+                        )
+                    });
 
                     let t = timer.elapsed();
                     println!("Solving took {t:.2?}");
                     compute_timer += t;
 
-                    if let Some(l) = prev_above_layer.as_mut() {
-                        l.scores = None;
-                    }
-                    if let Some(l) = prev_below_layer.as_mut() {
-                        l.scores = None;
+                    if !reuse {
+                        if let Some(l) = prev_above_layer.as_mut() {
+                            l.scores = None;
+                        }
+                        if let Some(l) = prev_below_layer.as_mut() {
+                            l.scores = None;
+                        }
                     }
                     if let Some(l) = prev_throw_layer.as_mut() {
                         l.scores = None;
                     }
 
-                    if let Some(l) = layers.get_mut([na + 1, nb, nt + 2]) {
+                    if let Some(l) = layers.get_mut([na + 1, nb, saved]) {
                         *l = prev_above_layer;
                     }
 
-                    if let Some(l) = layers.get_mut([na, nb + 1, nt + 2]) {
+                    if let Some(l) = layers.get_mut([na, nb + 1, saved]) {
                         *l = prev_below_layer;
                     }
 
@@ -152,6 +200,11 @@ pub fn solve_5dicex() {
 
                 layers[[na, nb, nt]] = Some(layer);
             }
+
+            if *PRUNE_SCORES {
+                prune_column::<N, true>(na + 1, nb);
+                prune_column::<N, true>(na, nb + 1);
+            }
         }
     }
 
@@ -161,154 +214,214 @@ pub fn solve_5dicex() {
     println!("Saving  time: {save_timer:.2?}");
 }
 
-pub fn solve_6dicex() {
-    create_dir_all(format!("{}/6x/scores/", *PREFIX)).unwrap();
-    create_dir_all(format!("{}/6x/strats/", *PREFIX)).unwrap();
+// Finds the second and third moments of the remaining score when following
+// the already computed strategies with saved throws. Layers outside the
+// stack of saved throws are treated the same way as in the solver.
+pub fn solve_moments_x<const N: usize>() {
+    let dir = Layer::<N, true>::empty().dir();
+
+    create_dir_all(format!("{dir}/moment2/")).unwrap();
+    create_dir_all(format!("{dir}/moment3/")).unwrap();
+
+    let n_cells = cell_from_dice::<N>();
+    let n_below = n_cells - 6;
+    let n_throws = next_turn_throws(n_cells * 2) + 1;
+
+    let load_moments = |na, nb, nt| {
+        let layer = Layer::<N, true> {
+            na,
+            nb,
+            nt,
+            scores: None,
+            strats: None,
+        };
+
+        layer.assert_not_pruned();
 
-    let mut layers = make_thin_layers_6dicex();
+        layer.load_moments().expect("Previous layer is not solved!")
+    };
 
     let global_timer = Instant::now();
     let mut load_timer = Duration::ZERO;
     let mut save_timer = Duration::ZERO;
     let mut compute_timer = Duration::ZERO;
 
-    let (ins_send, ins_rec) = bounded::<Layer<6, true>>(2);
-    let (res_send, res_rec) = bounded::<Layer<6, true>>(2);
-
-    thread::spawn(move || {
-        for mut l in ins_rec {
-            l.load_scores();
-            res_send.send(l).unwrap();
-        }
-    });
-
-    let mut loading = 0;
-
     for na in (0..=6).rev() {
-        for nb in (0..=14).rev() {
-            let nt_max = (na + nb) * 2 + 2;
+        for nb in (0..=n_below).rev() {
+            let nt_max = next_turn_throws((na + nb) * 2);
 
             for nt in 0..=nt_max {
                 println!("=============================");
                 println!("na: {na:2}, nb: {nb:2}, nt: {nt:2}");
 
-                let mut layer = layers[[na, nb, nt]].take().unwrap();
+                let mut layer = Layer::<N, true> {
+                    na,
+                    nb,
+                    nt,
+                    scores: None,
+                    strats: None,
+                };
 
-                if layer.is_done() {
+                if layer.moments_done() {
                     println!("Already done!");
-                } else {
-                    while loading > 0 {
-                        let l = res_rec.recv().unwrap();
-                        let na = l.na;
-                        let nb = l.nb;
-                        let nt = l.nt;
-                        layers[[na, nb, nt]] = Some(l);
-                        loading -= 1;
-                    }
+                    continue;
+                }
 
-                    let mut prev_above_layer = layers
-                        .get_mut([na + 1, nb, nt + 2])
-                        .map(|x| x.take().unwrap());
-                    let mut prev_below_layer = layers
-                        .get_mut([na, nb + 1, nt + 2])
-                        .map(|x| x.take().unwrap());
-                    let mut prev_throw_layer = layers
-                        .get_mut([na, nb, nt.wrapping_sub(1)])
-                        .map(|x| x.take().unwrap());
+                let timer = Instant::now();
 
-                    if nt < nt_max {
-                        let next_above_layer = layers
-                            .get_mut([na + 1, nb, nt + 3])
-                            .map_or_else(Layer::empty, |l| l.take().unwrap());
-                        let next_below_layer = layers
-                            .get_mut([na, nb + 1, nt + 3])
-                            .map_or_else(Layer::empty, |l| l.take().unwrap());
+                layer.load_strats().expect("Strategy is not computed!");
 
-                        ins_send.send(next_above_layer).unwrap();
-                        ins_send.send(next_below_layer).unwrap();
-
-                        loading += 2;
-                    }
+                let saved = next_turn_throws(nt);
 
-                    let timer = Instant::now();
+                let prev_above = (na < 6 && saved < n_throws)
+                    .then(|| load_moments(na + 1, nb, saved));
+                let prev_below = (nb < n_below && saved < n_throws)
+                    .then(|| load_moments(na, nb + 1, saved));
+                let prev_throw = (nt > 0).then(|| load_moments(na, nb, nt - 1));
 
-                    if let Some(l) = prev_above_layer.as_mut() {
-                        l.load_scores();
-                    }
-                    if let Some(l) = prev_below_layer.as_mut() {
-                        l.load_scores();
-                    }
-                    if let Some(l) = prev_throw_layer.as_mut() {
-                        l.load_scores();
-                    }
+                let t = timer.elapsed();
+                println!("Loading took {t:.2?}");
+                load_timer += t;
 
-                    let t = timer.elapsed();
-                    println!("Loading took {t:.2?}");
-                    load_timer += t;
+                let timer = Instant::now();
 
-                    let timer = Instant::now();
+                let prev_above = prev_above.as_ref().map(moment_views);
+                let prev_below = prev_below.as_ref().map(moment_views);
+                let prev_throw = prev_throw.as_ref().map(moment_views);
 
-                    let (scores, strats) = solve_layer_6dicex(
+                let moments = with_rules!(N, R => {
+                    solve_layer_moments::<R, true>(
                         na,
                         nb,
-                        prev_above_layer
-                            .as_ref()
-                            .map(|l| l.scores.as_ref().unwrap().view()),
-                        prev_below_layer
-                            .as_ref()
-                            .map(|l| l.scores.as_ref().unwrap().view()),
-                        prev_throw_layer
-                            .as_ref()
-                            .map(|l| l.scores.as_ref().unwrap().view()),
-                    );
+                        nt,
+                        layer.strats.as_ref().unwrap().view(),
+                        prev_above.as_ref(),
+                        prev_below.as_ref(),
+                        prev_throw.as_ref(),
+                    )
+                });
 
-                    let t = timer.elapsed();
-                    println!("Solving took {t:.2?}");
-                    compute_timer += t;
+                let t = timer.elapsed();
+                println!("Solving took {t:.2?}");
+                compute_timer += t;
 
-                    if let Some(l) = prev_above_layer.as_mut() {
-                        l.scores = None;
-                    }
-                    if let Some(l) = prev_below_layer.as_mut() {
-                        l.scores = None;
-                    }
-                    if let Some(l) = prev_throw_layer.as_mut() {
-                        l.scores = None;
-                    }
+                let timer = Instant::now();
 
-                    if let Some(l) = layers.get_mut([na + 1, nb, nt + 2]) {
-                        *l = prev_above_layer;
-                    }
+                layer.save_moments(&moments);
 
-                    if let Some(l) = layers.get_mut([na, nb + 1, nt + 2]) {
-                        *l = prev_below_layer;
-                    }
+                let t = timer.elapsed();
+                println!("Saving took  {t:.2?}");
+                save_timer += t;
+            }
+        }
+    }
 
-                    if let Some(l) =
-                        layers.get_mut([na, nb, nt.wrapping_sub(1)])
-                    {
-                        *l = prev_throw_layer;
-                    }
+    println!("\n\nTotal   time: {:.2?}", global_timer.elapsed());
+    println!("Compute time: {compute_timer:.2?}");
+    println!("Loading time: {load_timer:.2?}");
+    println!("Saving  time: {save_timer:.2?}");
+}
 
-                    layer.scores = Some(scores);
-                    layer.strats = Some(strats);
+// Solves the game with saved throws for the exponential utility with the
+// risk coefficient in RISK, which has to be set beforehand. The layers are
+// loaded as they are needed, like for the moments.
+pub fn solve_risk_x<const N: usize>() {
+    let lambda = *RISK.get().expect("No risk coefficient is set!");
+    assert!(
+        lambda != 0.0,
+        "A risk coefficient of 0 is the expected score!"
+    );
 
-                    let timer = Instant::now();
+    let dir = Layer::<N, true>::empty().dir();
 
-                    layer.save_scores();
-                    layer.save_strats();
+    create_dir_all(format!("{dir}/scores/")).unwrap();
+    create_dir_all(format!("{dir}/strats/")).unwrap();
 
-                    let t = timer.elapsed();
-                    println!("Saving took  {t:.2?}");
-                    save_timer += t;
-                }
+    let n_below = cell_from_dice::<N>() - 6;
 
-                layer.strats = None;
-                if nt == nt_max {
-                    layer.scores = None;
+    let load_scores = |na, nb, nt| {
+        let mut layer = Layer::<N, true> {
+            na,
+            nb,
+            nt,
+            scores: None,
+            strats: None,
+        };
+
+        layer.assert_not_pruned();
+        layer.load_scores().expect("Previous layer is not solved!");
+
+        layer.scores.unwrap()
+    };
+
+    let global_timer = Instant::now();
+    let mut load_timer = Duration::ZERO;
+    let mut save_timer = Duration::ZERO;
+    let mut compute_timer = Duration::ZERO;
+
+    for na in (0..=6).rev() {
+        for nb in (0..=n_below).rev() {
+            let nt_max = next_turn_throws((na + nb) * 2);
+
+            for nt in 0..=nt_max {
+                println!("=============================");
+                println!("na: {na:2}, nb: {nb:2}, nt: {nt:2}");
+
+                let mut layer = Layer::<N, true> {
+                    na,
+                    nb,
+                    nt,
+                    scores: None,
+                    strats: None,
+                };
+
+                if layer.is_done() {
+                    println!("Already done!");
+                    continue;
                 }
 
-                layers[[na, nb, nt]] = Some(layer);
+                let timer = Instant::now();
+
+                let saved = next_turn_throws(nt);
+
+                let prev_above =
+                    (na < 6).then(|| load_scores(na + 1, nb, saved));
+                let prev_below =
+                    (nb < n_below).then(|| load_scores(na, nb + 1, saved));
+                let prev_throw = (nt > 0).then(|| load_scores(na, nb, nt - 1));
+
+                let t = timer.elapsed();
+                println!("Loading took {t:.2?}");
+                load_timer += t;
+
+                let timer = Instant::now();
+
+                let (scores, strats) = with_rules!(N, R => {
+                    solve_layer_risk::<R>(
+                        na,
+                        nb,
+                        lambda,
+                        prev_above.as_ref().map(|x| x.view()),
+                        prev_below.as_ref().map(|x| x.view()),
+                        prev_throw.as_ref().map(|x| x.view()),
+                    )
+                });
+
+                let t = timer.elapsed();
+                println!("Solving took {t:.2?}");
+                compute_timer += t;
+
+                layer.scores = Some(scores);
+                layer.strats = Some(strats);
+
+                let timer = Instant::now();
+
+                layer.save_scores();
+                layer.save_strats();
+
+                let t = timer.elapsed();
+                println!("Saving took  {t:.2?}");
+                save_timer += t;
             }
         }
     }
@@ -0,0 +1,466 @@
+// This is the module for looking up single states in the solved layers, for
+// the guide and the simulations. The layer files are mapped into memory
+// instead of loaded, so only the pages that are used are ever read, and the
+// operating system can drop them again when memory is short. That way the
+// strategies with saved throws for 6 dice, which are far larger than the
+// memory of a normal machine, can still be simulated.
+
+use std::{
+    cell::Cell,
+    collections::HashMap,
+    fs::{create_dir_all, metadata, read_dir, File},
+    path::Path,
+};
+
+use memmap2::Mmap;
+use ndarray::Array3;
+use thread_local::ThreadLocal;
+
+use crate::{
+    dice_throw::DiceThrow,
+    macrosolver::{
+        american::make_thin_layers_american,
+        forced::ForcedLayer,
+        layer_file::{
+            block_of, check_blocks, check_header, inflate_block,
+            write_compressed_layer, DataType, Encoding, LayerHeader, LayerId,
+            HEADER_LEN,
+        },
+        outcore::{layer_shape, next_turn_throws, rerolls, Layer},
+        target::TargetLayer,
+    },
+    yatzy::{cell_from_dice, State},
+};
+
+pub enum Strategy {
+    Rethrow(u8),
+    Cell(usize),
+}
+
+// An inflated block of compressed strategies and its index.
+type Block = (usize, Vec<u8>);
+
+// A layer file mapped read-only. The header is checked when the file is
+// mapped, but not the checksum, since that would read all of the data.
+pub struct MappedLayer {
+    map: Mmap,
+    header: LayerHeader,
+    shape: [usize; 3],
+    // The last block inflated by each thread, with its index, for compressed
+    // strategies. States looked up one after another are mostly in the same
+    // block, which then only has to be inflated once.
+    last_block: ThreadLocal<Cell<Option<Block>>>,
+}
+
+impl MappedLayer {
+    // Maps the layer file at path, or returns None if there is none. Like
+    // when loading a layer, a file that is not the expected layer is an error.
+    pub fn open(
+        path: &str,
+        id: LayerId,
+        dtype: DataType,
+        shape: [usize; 3],
+    ) -> Option<Self> {
+        let file = File::open(path).ok()?;
+
+        // The layer files are only ever replaced by renaming a new file over
+        // them, never written in place (see `write_layer`), so the map stays
+        // valid.
+        let map = unsafe { Mmap::map(&file) }
+            .unwrap_or_else(|err| panic!("Could not map {path}: {err}"));
+
+        let header = check_header(&map, map.len() as u64, id, dtype, shape)
+            .and_then(|header| match header.encoding {
+                Encoding::Raw | Encoding::Fixed16 => Ok(header),
+                Encoding::Blocks => check_blocks(&map, shape).map(|_| header),
+            })
+            .unwrap_or_else(|err| panic!("Could not load {path}: {err}"));
+
+        Some(Self {
+            map,
+            header,
+            shape,
+            last_block: ThreadLocal::new(),
+        })
+    }
+
+    fn offset(&self, [ai, bi, ti]: [usize; 3]) -> usize {
+        let [la, lb, lt] = self.shape;
+
+        assert!(ai < la && bi < lb && ti < lt, "Index out of bounds!");
+
+        (ai * lb + bi) * lt + ti
+    }
+
+    pub fn byte(&self, index: [usize; 3]) -> u8 {
+        let offset = self.offset(index);
+
+        match self.header.encoding {
+            Encoding::Raw => self.map[HEADER_LEN + offset],
+            Encoding::Blocks => self.compressed_byte(index),
+            Encoding::Fixed16 => panic!("Strategies are never fixed point!"),
+        }
+    }
+
+    fn compressed_byte(&self, index: [usize; 3]) -> u8 {
+        let (block, position) = block_of(&self.map, self.shape, index);
+
+        let last_block = self.last_block.get_or_default();
+
+        let data = match last_block.take() {
+            Some((i, data)) if i == block => data,
+            _ => inflate_block(&self.map, block)
+                .unwrap_or_else(|err| panic!("Could not read a layer: {err}")),
+        };
+
+        let byte = *data.get(position).unwrap_or_else(|| {
+            panic!("Could not read a layer: a block is too short")
+        });
+
+        last_block.set(Some((block, data)));
+
+        byte
+    }
+
+    pub fn float(&self, index: [usize; 3]) -> f32 {
+        let offset = self.offset(index);
+
+        match self.header.encoding {
+            Encoding::Raw => {
+                let i = HEADER_LEN + 4 * offset;
+
+                f32::from_ne_bytes(self.map[i..i + 4].try_into().unwrap())
+            }
+            Encoding::Blocks => panic!("Scores are never compressed!"),
+            Encoding::Fixed16 => {
+                let i = HEADER_LEN + 2 * offset;
+                let q =
+                    u16::from_ne_bytes(self.map[i..i + 2].try_into().unwrap());
+
+                self.header.from_fixed(q)
+            }
+        }
+    }
+}
+
+// All the solved layers of a free game with N dice, mapped once so that the
+// strategy and the scores of any state can be looked up directly. Layers that
+// are not solved are None, and so are the lookups in them.
+pub struct StrategyStore<const N: usize, const X: bool> {
+    strats: Array3<Option<MappedLayer>>,
+    scores: Array3<Option<MappedLayer>>,
+    moments: Array3<Option<[MappedLayer; 2]>>,
+}
+
+// All the layers of a free game with N dice, with the current settings.
+fn layer_grid<const N: usize, const X: bool>() -> Array3<Layer<N, X>> {
+    let cells = cell_from_dice::<N>();
+
+    let max_nt = if X {
+        next_turn_throws(2 * cells)
+    } else {
+        rerolls::<N>()
+    };
+
+    Array3::from_shape_fn([7, cells - 5, max_nt + 1], |(na, nb, nt)| Layer {
+        na,
+        nb,
+        nt,
+        scores: None,
+        strats: None,
+    })
+}
+
+impl<const N: usize, const X: bool> StrategyStore<N, X> {
+    pub fn open() -> Self {
+        let layers = layer_grid::<N, X>();
+
+        let map = |layer: &Layer<N, X>, path: &str, dtype| {
+            let shape = layer_shape::<N>(layer.na, layer.nb);
+
+            MappedLayer::open(path, layer.id(), dtype, shape)
+        };
+
+        Self {
+            // The compressed strategies are used when they are all there is.
+            strats: layers.map(|layer| {
+                map(layer, &layer.strats_path(), DataType::U8).or_else(|| {
+                    map(layer, &layer.compressed_strats_path(), DataType::U8)
+                })
+            }),
+            scores: layers
+                .map(|layer| map(layer, &layer.scores_path(), DataType::F32)),
+            moments: layers.map(|layer| {
+                Some([
+                    map(layer, &layer.moment_path(2), DataType::F32)?,
+                    map(layer, &layer.moment_path(3), DataType::F32)?,
+                ])
+            }),
+        }
+    }
+
+    // The index of the layer and of the state in it.
+    fn indices(
+        cells: &[bool],
+        dice: &DiceThrow,
+        points_above: usize,
+        throws_left: usize,
+    ) -> ([usize; 3], [usize; 3]) {
+        let state = State::<N>::from_dyn(cells, points_above);
+
+        (
+            [state.get_n_above(), state.get_n_below(), throws_left],
+            [
+                state.get_above_index(),
+                state.get_below_index(),
+                dice.get_index(),
+            ],
+        )
+    }
+
+    fn strat_byte(
+        &self,
+        cells: &[bool],
+        dice: &DiceThrow,
+        points_above: usize,
+        throws_left: usize,
+    ) -> Option<u8> {
+        let (layer, state) =
+            Self::indices(cells, dice, points_above, throws_left);
+
+        Some(self.strats.get(layer)?.as_ref()?.byte(state))
+    }
+
+    // The cell to put the dice in with no throws left.
+    pub fn cell(
+        &self,
+        cells: &[bool],
+        dice: &DiceThrow,
+        points_above: usize,
+    ) -> Option<usize> {
+        assert!(!X, "The cells are in the strategy with saved throws!");
+
+        self.strat_byte(cells, dice, points_above, 0)
+            .map(|x| x as usize)
+    }
+
+    // The dice to rethrow with throws left.
+    pub fn rethrow(
+        &self,
+        cells: &[bool],
+        dice: &DiceThrow,
+        points_above: usize,
+        throws_left: usize,
+    ) -> Option<u8> {
+        assert!(!X, "The rethrows are in the strategy with saved throws!");
+
+        self.strat_byte(cells, dice, points_above, throws_left)
+    }
+
+    // Whether to rethrow or put the dice in a cell, with saved throws.
+    pub fn strategy(
+        &self,
+        cells: &[bool],
+        dice: &DiceThrow,
+        points_above: usize,
+        throws_left: usize,
+    ) -> Option<Strategy> {
+        assert!(X, "Only the strategy with saved throws can be combined!");
+
+        self.strat_byte(cells, dice, points_above, throws_left)
+            .map(|byte| {
+                if (byte & 128) != 0 {
+                    Strategy::Rethrow(byte & !128)
+                } else {
+                    Strategy::Cell(byte as usize)
+                }
+            })
+    }
+
+    // The expected remaining score.
+    pub fn score(
+        &self,
+        cells: &[bool],
+        dice: &DiceThrow,
+        points_above: usize,
+        throws_left: usize,
+    ) -> Option<f32> {
+        let (layer, state) =
+            Self::indices(cells, dice, points_above, throws_left);
+
+        Some(self.scores.get(layer)?.as_ref()?.float(state))
+    }
+
+    // The first three moments of the remaining score.
+    pub fn moments(
+        &self,
+        cells: &[bool],
+        dice: &DiceThrow,
+        points_above: usize,
+        throws_left: usize,
+    ) -> Option<[f32; 3]> {
+        let (layer, state) =
+            Self::indices(cells, dice, points_above, throws_left);
+
+        let [m2, m3] = self.moments.get(layer)?.as_ref()?;
+
+        Some([
+            self.score(cells, dice, points_above, throws_left)?,
+            m2.float(state),
+            m3.float(state),
+        ])
+    }
+}
+
+// The strategies and the scores of all the solved layers of the American,
+// the forced or the target game, mapped once like in `StrategyStore`. A layer
+// is found by the index of its `LayerId`, and lookups in layers that are not
+// solved are None.
+pub struct LayerStore {
+    strats: HashMap<[usize; 4], MappedLayer>,
+    scores: HashMap<[usize; 4], MappedLayer>,
+}
+
+impl LayerStore {
+    // Maps the strategies and the scores of the layers, given as their id,
+    // shape and the paths of the two files.
+    fn open(
+        layers: impl Iterator<Item = (LayerId, [usize; 3], String, String)>,
+    ) -> Self {
+        let mut strats = HashMap::new();
+        let mut scores = HashMap::new();
+
+        for (id, shape, strats_path, scores_path) in layers {
+            if let Some(layer) =
+                MappedLayer::open(&strats_path, id, DataType::U8, shape)
+            {
+                strats.insert(id.index, layer);
+            }
+
+            if let Some(layer) =
+                MappedLayer::open(&scores_path, id, DataType::F32, shape)
+            {
+                scores.insert(id.index, layer);
+            }
+        }
+
+        Self { strats, scores }
+    }
+
+    pub fn american() -> Self {
+        Self::open(make_thin_layers_american().into_iter().flatten().map(
+            |layer| {
+                (
+                    layer.id(),
+                    layer.shape(),
+                    layer.strats_path(),
+                    layer.scores_path(),
+                )
+            },
+        ))
+    }
+
+    pub fn forced<const N: usize>() -> Self {
+        Self::open((0..cell_from_dice::<N>()).map(|cell| {
+            let layer = ForcedLayer::<N>::new(cell);
+
+            (
+                layer.id(),
+                ForcedLayer::<N>::shape(),
+                layer.strats_path(),
+                layer.scores_path(),
+            )
+        }))
+    }
+
+    // The amounts of points needed that are solved depend on the settings the
+    // layers were solved with, so the target layers are found by listing the
+    // strategies.
+    pub fn target<const N: usize>() -> Self {
+        let dir = format!("{}/strats", TargetLayer::<N>::dir());
+
+        let names: Vec<String> = read_dir(dir)
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+            .collect();
+
+        Self::open(names.iter().filter_map(|name| {
+            let mut index = name.strip_suffix(".dat")?.split('_');
+            let mut next = || index.next()?.parse().ok();
+
+            let layer = TargetLayer::<N> {
+                na: next()?,
+                nb: next()?,
+                nt: next()?,
+                need: next()?,
+                probs: None,
+                strats: None,
+            };
+
+            Some((
+                layer.id(),
+                layer_shape::<N>(layer.na, layer.nb),
+                layer.strats_path(),
+                layer.probs_path(),
+            ))
+        }))
+    }
+
+    pub fn byte(&self, layer: [usize; 4], index: [usize; 3]) -> Option<u8> {
+        Some(self.strats.get(&layer)?.byte(index))
+    }
+
+    // The score, or the probability of reaching the target for target layers.
+    pub fn float(&self, layer: [usize; 4], index: [usize; 3]) -> Option<f32> {
+        Some(self.scores.get(&layer)?.float(index))
+    }
+}
+
+// Compresses the strategies of every solved layer of a free game with N dice
+// into the strats_z directory next to strats, and reports how much smaller
+// every layer gets. Layers that are already compressed are only reported.
+pub fn compress_strats<const N: usize, const X: bool>() {
+    let layers = layer_grid::<N, X>();
+
+    create_dir_all(format!("{}/strats_z/", Layer::<N, X>::empty().dir()))
+        .unwrap();
+
+    let mut total_raw = 0;
+    let mut total_compressed = 0;
+
+    for mut layer in layers {
+        let path = layer.compressed_strats_path();
+        let shape = layer_shape::<N>(layer.na, layer.nb);
+
+        if !Path::new(&path).exists() {
+            if layer.load_strats().is_none() {
+                continue;
+            }
+
+            let strats = layer.strats.take().unwrap();
+
+            write_compressed_layer(&path, layer.id(), &strats);
+        }
+
+        let raw = shape.iter().product::<usize>();
+        let compressed = metadata(&path).unwrap().len() as usize;
+
+        println!(
+            "na: {:2}, nb: {:2}, nt: {:2}, {raw:>11} -> {compressed:>10} \
+             bytes, {:6.1}x",
+            layer.na,
+            layer.nb,
+            layer.nt,
+            raw as f64 / compressed as f64
+        );
+
+        total_raw += raw;
+        total_compressed += compressed;
+    }
+
+    println!(
+        "\nTotal: {total_raw} -> {total_compressed} bytes, {:.1}x",
+        total_raw as f64 / total_compressed as f64
+    );
+}
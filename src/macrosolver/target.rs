@@ -0,0 +1,313 @@
+// This is the module for solving the endgame with the objective of maximizing
+// the probability of reaching a target score, rather than the expected score.
+// The state is extended with the amount of points still needed, and every
+// (na, nb, nt) layer is stored once for each amount of points needed.
+// Only layers with at most `max_open` open cells are solved, as the state
+// space otherwise explodes.
+
+use std::{
+    fs::create_dir_all,
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use ndarray::Array3;
+
+use crate::{
+    macrosolver::{
+        layer_file::{Game, LayerId},
+        outcore::{
+            layer_shape, load_bytes, load_floats, rerolls, save_bytes,
+            save_floats, PREFIX,
+        },
+    },
+    rules::RuleSet,
+    solver::{solve_layer_target_type1, solve_layer_type2},
+    with_rules,
+    yatzy::cell_from_dice,
+};
+
+pub struct TargetLayer<const N: usize> {
+    pub na: usize,
+    pub nb: usize,
+    pub nt: usize,
+    pub need: usize,
+    pub probs: Option<Array3<f32>>,
+    pub strats: Option<Array3<u8>>,
+}
+
+impl<const N: usize> TargetLayer<N> {
+    pub fn empty() -> Self {
+        Self {
+            na: 0,
+            nb: 0,
+            nt: 0,
+            need: 0,
+            probs: Some(Array3::zeros([0; 3])),
+            strats: Some(Array3::zeros([0; 3])),
+        }
+    }
+
+    pub fn name(&self) -> String {
+        format!("{}_{}_{}_{}.dat", self.na, self.nb, self.nt, self.need)
+    }
+
+    pub fn id(&self) -> LayerId {
+        LayerId {
+            game: Game::Target,
+            dice: N,
+            x: false,
+            index: [self.na, self.nb, self.nt, self.need],
+        }
+    }
+
+    // The layers for house rules with another amount of rerolls live in a
+    // separate directory, like those of the free game.
+    pub fn dir() -> String {
+        let mut dir = format!("{}/{N}p", *PREFIX);
+
+        let rerolls = rerolls::<N>();
+        if rerolls != with_rules!(N, R => R::THROWS) - 1 {
+            dir += &format!("_rerolls{rerolls}");
+        }
+
+        dir
+    }
+
+    pub fn probs_path(&self) -> String {
+        format!("{}/scores/{}", Self::dir(), self.name())
+    }
+
+    pub fn strats_path(&self) -> String {
+        format!("{}/strats/{}", Self::dir(), self.name())
+    }
+
+    pub fn save_probs(&self) {
+        save_floats(
+            &self.probs_path(),
+            self.id(),
+            self.probs.as_ref().unwrap(),
+        );
+    }
+
+    pub fn save_strats(&self) {
+        save_bytes(
+            &self.strats_path(),
+            self.id(),
+            self.strats.as_ref().unwrap(),
+        );
+    }
+
+    pub fn is_done(&self) -> bool {
+        Path::new(&self.probs_path()).exists()
+            && Path::new(&self.strats_path()).exists()
+    }
+
+    pub fn load_probs(&mut self) -> Option<()> {
+        if self.probs.is_none() {
+            self.probs = Some(load_floats(
+                &self.probs_path(),
+                self.id(),
+                layer_shape::<N>(self.na, self.nb),
+            )?);
+        }
+
+        Some(())
+    }
+
+    pub fn load_strats(&mut self) -> Option<()> {
+        if self.strats.is_none() {
+            self.strats = Some(load_bytes(
+                &self.strats_path(),
+                self.id(),
+                layer_shape::<N>(self.na, self.nb),
+            )?);
+        }
+
+        Some(())
+    }
+}
+
+// Loads the probabilities for all amounts of points needed for the layer
+// (na, nb, rerolls) at the start of a turn. Layers outside the game, and the
+// final layer where all cells are filled, are returned as empty arrays, which
+// the solver treats as zero probability.
+fn load_column<const N: usize>(
+    na: usize,
+    nb: usize,
+    max_need: usize,
+) -> Vec<Array3<f32>> {
+    let n_above = 6;
+    let n_below = cell_from_dice::<N>() - 6;
+
+    (0..=max_need)
+        .map(|need| {
+            if na > n_above
+                || nb > n_below
+                || na + nb >= n_above + n_below
+                || need == 0
+            {
+                return Array3::zeros([0; 3]);
+            }
+
+            let mut layer = TargetLayer::<N> {
+                na,
+                nb,
+                nt: rerolls::<N>(),
+                need,
+                probs: None,
+                strats: None,
+            };
+
+            layer.load_probs().expect("Previous layer is not solved!");
+
+            layer.probs.unwrap()
+        })
+        .collect()
+}
+
+fn solve_target<const N: usize>(max_open: usize, max_need: usize) {
+    create_dir_all(format!("{}/scores/", TargetLayer::<N>::dir())).unwrap();
+    create_dir_all(format!("{}/strats/", TargetLayer::<N>::dir())).unwrap();
+
+    let n_cells = cell_from_dice::<N>();
+
+    let global_timer = Instant::now();
+    let mut load_timer = Duration::ZERO;
+    let mut save_timer = Duration::ZERO;
+    let mut compute_timer = Duration::ZERO;
+
+    for na in (0..=6).rev() {
+        for nb in (0..=n_cells - 6).rev() {
+            // With no open cells there is no way to get more points.
+            if n_cells - na - nb > max_open || na + nb == n_cells {
+                continue;
+            }
+
+            let timer = Instant::now();
+
+            let prev_above_layers = load_column::<N>(na + 1, nb, max_need);
+            let prev_below_layers = load_column::<N>(na, nb + 1, max_need);
+
+            let t = timer.elapsed();
+            println!("Loading took {t:.2?}");
+            load_timer += t;
+
+            for need in 1..=max_need {
+                println!("=============================");
+                println!("na: {na:2}, nb: {nb:2}, nt: 0, need: {need:3}");
+
+                let mut layer = TargetLayer::<N> {
+                    na,
+                    nb,
+                    nt: 0,
+                    need,
+                    probs: None,
+                    strats: None,
+                };
+
+                if layer.is_done() {
+                    println!("Already done!");
+                } else {
+                    let timer = Instant::now();
+
+                    let (probs, strats) = with_rules!(N, R => {
+                        solve_layer_target_type1::<R>(
+                            na,
+                            nb,
+                            need,
+                            &prev_above_layers,
+                            &prev_below_layers,
+                        )
+                    });
+
+                    let t = timer.elapsed();
+                    println!("Solving took {t:.2?}");
+                    compute_timer += t;
+
+                    layer.probs = Some(probs);
+                    layer.strats = Some(strats);
+
+                    let timer = Instant::now();
+
+                    layer.save_probs();
+                    layer.save_strats();
+
+                    let t = timer.elapsed();
+                    println!("Saving took  {t:.2?}");
+                    save_timer += t;
+                }
+
+                let mut prev_layer = layer;
+
+                for nt in 1..=rerolls::<N>() {
+                    println!("--------------------------------");
+                    println!(
+                        "na: {na:2}, nb: {nb:2}, nt: {nt}, need: {need:3}"
+                    );
+
+                    let mut layer = TargetLayer::<N> {
+                        na,
+                        nb,
+                        nt,
+                        need,
+                        probs: None,
+                        strats: None,
+                    };
+
+                    if layer.is_done() {
+                        println!("Already done!");
+                    } else {
+                        let timer = Instant::now();
+
+                        prev_layer.load_probs();
+
+                        let t = timer.elapsed();
+                        println!("Loading took {t:.2?}");
+                        load_timer += t;
+
+                        let timer = Instant::now();
+
+                        // Rerolling is independent of the objective, so the
+                        // expected score solver does the job here as well.
+                        let prev_probs = prev_layer.probs.as_ref().unwrap();
+                        let (probs, strats) =
+                            solve_layer_type2::<N>(prev_probs);
+
+                        let t = timer.elapsed();
+                        println!("Solving took {t:.2?}");
+                        compute_timer += t;
+
+                        layer.probs = Some(probs);
+                        layer.strats = Some(strats);
+
+                        let timer = Instant::now();
+
+                        layer.save_probs();
+                        layer.save_strats();
+
+                        let t = timer.elapsed();
+                        println!("Saving took  {t:.2?}");
+                        save_timer += t;
+                    }
+
+                    layer.strats = None;
+                    prev_layer = layer;
+                }
+            }
+        }
+    }
+
+    println!("\n\nTotal   time: {:.2?}", global_timer.elapsed());
+    println!("Compute time: {compute_timer:.2?}");
+    println!("Loading time: {load_timer:.2?}");
+    println!("Saving  time: {save_timer:.2?}");
+}
+
+pub fn solve_5dice_target(max_open: usize, max_need: usize) {
+    solve_target::<5>(max_open, max_need)
+}
+
+pub fn solve_6dice_target(max_open: usize, max_need: usize) {
+    solve_target::<6>(max_open, max_need)
+}
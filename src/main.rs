@@ -1,22 +1,41 @@
-#![feature(split_array, generic_const_exprs)]
-#![allow(incomplete_features)]
+use std::{env, path::Path, time::Instant};
 
-use std::{env, time::Instant};
-
-use dice_distributions::{DICE_DISTR, DICE_DIVISOR};
-use guide::start;
+use dice_distributions::{
+    check_dice_tables, check_reroll_operators, DICE_PROBS,
+};
+use distribution::{distribution_from_start, ScoreDistribution};
+use guide::{start, start_american, start_forced};
+use level_ordering::check_level_indexing;
 use macrosolver::{
-    outcore::{solve_5dice, solve_6dice, Layer},
-    outcorex::{solve_5dicex, solve_6dicex},
+    american::{solve_american, AmericanLayer},
+    forced::solve_forced,
+    layer_file::upgrade_cache,
+    outcore::{
+        compare_caches, next_turn_throws, prune_scores, rerolls, solve,
+        solve_moments, Layer, COMPARE_CACHES_USAGE, MAX_SAVED, REROLLS, RISK,
+    },
+    outcorex::{solve_moments_x, solve_risk_x},
+    store::compress_strats,
+    target::{solve_5dice_target, solve_6dice_target},
+};
+use rescore::check_rescore;
+use rules::MaxiYatzy;
+use simulation::{
+    simulate_n_5, simulate_n_5x, simulate_n_6, simulate_n_6x,
+    simulate_n_american, simulate_n_forced,
 };
-use simulation::{simulate_n_5, simulate_n_6};
-use solver::solve_layer_6dicex;
+use solver::solve_layer_x;
 
+pub mod american;
 pub mod dice_distributions;
 pub mod dice_throw;
+pub mod distribution;
+pub mod endgame;
 pub mod guide;
 pub mod level_ordering;
 pub mod macrosolver;
+pub mod rescore;
+pub mod rules;
 pub mod simulation;
 pub mod solver;
 pub mod util;
@@ -26,19 +45,177 @@ fn main() {
     let args: Vec<_> = env::args().collect();
 
     match args.get(1).unwrap_or(&"".to_owned()).as_str() {
-        "guide-5" => start::<5, false>(),
-        "guide-5x" => start::<5, true>(),
-        "guide-6" => start::<6, false>(),
-        "guide-6x" => start::<6, true>(),
-        "compute-strats-5" => solve_5dice(),
-        "compute-strats-5x" => solve_5dicex(),
-        "compute-strats-6" => solve_6dice(),
-        "compute-strats-6x" => solve_6dicex(),
+        "check-dice-tables" => {
+            check_dice_tables();
+            check_reroll_operators();
+            println!("The dice tables are fine.");
+        }
+        "check-level-indexing" => {
+            check_level_indexing();
+            println!("The level indexing is fine.");
+        }
+        "check-rescore-5" => {
+            let n = args.get(2).map_or(1000, |n| n.parse().unwrap());
+            let max_open = args.get(3).map_or(4, |n| n.parse().unwrap());
+
+            check_rescore::<5>(n, max_open);
+            println!("The rescored scores match.");
+        }
+        "compare-caches" => {
+            let (Some(a), Some(b)) = (args.get(2), args.get(3)) else {
+                println!("{COMPARE_CACHES_USAGE}");
+                return;
+            };
+            let tolerance = args.get(4).map_or(1e-3, |x| x.parse().unwrap());
+
+            assert!(
+                compare_caches(Path::new(a), Path::new(b), tolerance),
+                "The scores differ by more than {tolerance:e}!"
+            );
+            println!("The scores match.");
+        }
+        "upgrade-cache" => upgrade_cache(),
+        "guide-5" => {
+            set_rerolls(args.get(2));
+            start::<5, false>();
+        }
+        "guide-5x" => {
+            set_max_saved(args.get(2));
+            start::<5, true>();
+        }
+        "guide-6" => {
+            set_rerolls(args.get(2));
+            start::<6, false>();
+        }
+        "guide-6x" => {
+            set_max_saved(args.get(2));
+            start::<6, true>();
+        }
+        "guide-american" => {
+            set_rerolls(args.get(2));
+            start_american();
+        }
+        "guide-forced-5" => {
+            set_rerolls(args.get(2));
+            start_forced::<5>();
+        }
+        "guide-forced-6" => {
+            set_rerolls(args.get(2));
+            start_forced::<6>();
+        }
+        "guide-5x-risk" => {
+            RISK.set(args[2].parse().unwrap()).unwrap();
+            start::<5, true>();
+        }
+        "guide-6x-risk" => {
+            RISK.set(args[2].parse().unwrap()).unwrap();
+            start::<6, true>();
+        }
+        "compute-strats-5" => {
+            set_rerolls(args.get(2));
+            solve::<5, false>();
+        }
+        "compute-strats-5x" => {
+            set_max_saved(args.get(2));
+            solve::<5, true>();
+        }
+        "compute-strats-6" => {
+            set_rerolls(args.get(2));
+            solve::<6, false>();
+        }
+        "compute-strats-6x" => {
+            set_max_saved(args.get(2));
+            solve::<6, true>();
+        }
+        "compress-strats-5" => {
+            set_rerolls(args.get(2));
+            compress_strats::<5, false>();
+        }
+        "compress-strats-5x" => {
+            set_max_saved(args.get(2));
+            compress_strats::<5, true>();
+        }
+        "compress-strats-6" => {
+            set_rerolls(args.get(2));
+            compress_strats::<6, false>();
+        }
+        "compress-strats-6x" => {
+            set_max_saved(args.get(2));
+            compress_strats::<6, true>();
+        }
+        "prune-scores-5" => {
+            set_rerolls(args.get(2));
+            prune_scores::<5, false>();
+        }
+        "prune-scores-5x" => {
+            set_max_saved(args.get(2));
+            prune_scores::<5, true>();
+        }
+        "prune-scores-6" => {
+            set_rerolls(args.get(2));
+            prune_scores::<6, false>();
+        }
+        "prune-scores-6x" => {
+            set_max_saved(args.get(2));
+            prune_scores::<6, true>();
+        }
+        "compute-strats-american" => {
+            set_rerolls(args.get(2));
+            solve_american();
+        }
+        "compute-strats-forced-5" => {
+            set_rerolls(args.get(2));
+            solve_forced::<5>();
+        }
+        "compute-strats-forced-6" => {
+            set_rerolls(args.get(2));
+            solve_forced::<6>();
+        }
+        "compute-risk-5x" => {
+            RISK.set(args[2].parse().unwrap()).unwrap();
+            solve_risk_x::<5>();
+        }
+        "compute-risk-6x" => {
+            RISK.set(args[2].parse().unwrap()).unwrap();
+            solve_risk_x::<6>();
+        }
+        "compute-moments-5" => {
+            set_rerolls(args.get(2));
+            solve_moments::<5>();
+        }
+        "compute-moments-5x" => {
+            set_max_saved(args.get(2));
+            solve_moments_x::<5>();
+        }
+        "compute-moments-6" => {
+            set_rerolls(args.get(2));
+            solve_moments::<6>();
+        }
+        "compute-moments-6x" => {
+            set_max_saved(args.get(2));
+            solve_moments_x::<6>();
+        }
+        "compute-target-5" => {
+            let max_open = args[2].parse().unwrap();
+            let max_need = args[3].parse().unwrap();
+            set_rerolls(args.get(4));
+
+            solve_5dice_target(max_open, max_need);
+        }
+        "compute-target-6" => {
+            let max_open = args[2].parse().unwrap();
+            let max_need = args[3].parse().unwrap();
+            set_rerolls(args.get(4));
+
+            solve_6dice_target(max_open, max_need);
+        }
         "expected-score-5" => {
+            set_rerolls(args.get(2));
+
             let mut layer = Layer::<5, false> {
                 na: 0,
                 nb: 0,
-                nt: 2,
+                nt: rerolls::<5>(),
                 scores: None,
                 strats: None,
             };
@@ -48,15 +225,15 @@ fn main() {
 
             let mut score = 0.0;
 
-            for (i, &(_, prob)) in DICE_DISTR.5.iter().enumerate() {
-                let prob = prob as f64 / DICE_DIVISOR[5] as f64;
-
+            for (i, &prob) in DICE_PROBS[5].iter().enumerate() {
                 score += scores[[0, 0, i]] as f64 * prob;
             }
 
             println!("Expected score for 5 dice: {score:.2}");
         }
         "expected-score-5x" => {
+            set_max_saved(args.get(2));
+
             let mut layer = Layer::<5, true> {
                 na: 0,
                 nb: 0,
@@ -70,15 +247,15 @@ fn main() {
 
             let mut score = 0.0;
 
-            for (i, &(_, prob)) in DICE_DISTR.5.iter().enumerate() {
-                let prob = prob as f64 / DICE_DIVISOR[5] as f64;
-
+            for (i, &prob) in DICE_PROBS[5].iter().enumerate() {
                 score += scores[[0, 0, i]] as f64 * prob;
             }
 
             println!("Expected score for 5 dice: {score:.2}");
         }
         "simulate-5" => {
+            set_rerolls(args.get(3));
+
             let mut scores = vec![0; args[2].parse().unwrap()];
 
             let timer = Instant::now();
@@ -88,11 +265,29 @@ fn main() {
             println!("time: {t:.2?}");
             println!("{scores:?}");
         }
+        "simulate-5x-risk" | "simulate-6x-risk" => {
+            RISK.set(args[2].parse().unwrap()).unwrap();
+
+            let mut scores = vec![0; args[3].parse().unwrap()];
+
+            let timer = Instant::now();
+            if args[1].starts_with("simulate-5x") {
+                simulate_n_5x(&mut scores);
+            } else {
+                simulate_n_6x(&mut scores);
+            }
+            let t = timer.elapsed();
+
+            println!("time: {t:.2?}");
+            println!("{scores:?}");
+        }
         "expected-score-6" => {
+            set_rerolls(args.get(2));
+
             let mut layer = Layer::<6, false> {
                 na: 0,
                 nb: 0,
-                nt: 2,
+                nt: rerolls::<6>(),
                 scores: None,
                 strats: None,
             };
@@ -102,15 +297,49 @@ fn main() {
 
             let mut score = 0.0;
 
-            for (i, &(_, prob)) in DICE_DISTR.6.iter().enumerate() {
-                let prob = prob as f64 / DICE_DIVISOR[6] as f64;
-
+            for (i, &prob) in DICE_PROBS[6].iter().enumerate() {
                 score += scores[[0, 0, i]] as f64 * prob;
             }
 
             println!("Expected score for 6 dice: {score:.2}");
         }
+        "expected-score-american" => {
+            set_rerolls(args.get(2));
+
+            let mut layer = AmericanLayer {
+                na: 0,
+                nb: 0,
+                nt: rerolls::<5>(),
+                scores: None,
+                strats: None,
+            };
+
+            layer.load_scores();
+            let scores = layer.scores.unwrap();
+
+            let mut score = 0.0;
+
+            for (i, &prob) in DICE_PROBS[5].iter().enumerate() {
+                score += scores[[0, 0, i]] as f64 * prob;
+            }
+
+            println!("Expected score for Yahtzee: {score:.2}");
+        }
+        "simulate-american" => {
+            set_rerolls(args.get(3));
+
+            let mut scores = vec![0; args[2].parse().unwrap()];
+
+            let timer = Instant::now();
+            simulate_n_american(&mut scores);
+            let t = timer.elapsed();
+
+            println!("time: {t:.2?}");
+            println!("{scores:?}");
+        }
         "simulate-6" => {
+            set_rerolls(args.get(3));
+
             let mut scores = vec![0; args[2].parse().unwrap()];
 
             let timer = Instant::now();
@@ -120,11 +349,79 @@ fn main() {
             println!("time: {t:.2?}");
             println!("{scores:?}");
         }
+        "simulate-5x" | "simulate-6x" => {
+            set_max_saved(args.get(3));
+
+            let mut scores = vec![0; args[2].parse().unwrap()];
+
+            let timer = Instant::now();
+            if args[1].ends_with("5x") {
+                simulate_n_5x(&mut scores);
+            } else {
+                simulate_n_6x(&mut scores);
+            }
+            let t = timer.elapsed();
+
+            println!("time: {t:.2?}");
+            println!("{scores:?}");
+        }
+        "simulate-forced-5" | "simulate-forced-6" => {
+            set_rerolls(args.get(3));
+
+            let mut scores = vec![0; args[2].parse().unwrap()];
+
+            let timer = Instant::now();
+            if args[1].ends_with('5') {
+                simulate_n_forced::<5>(&mut scores);
+            } else {
+                simulate_n_forced::<6>(&mut scores);
+            }
+            let t = timer.elapsed();
+
+            println!("time: {t:.2?}");
+            println!("{scores:?}");
+        }
+        "distribution-5" => {
+            set_rerolls(args.get(2));
+
+            let distr = distribution_from_start::<5>();
+            distr.save(&ScoreDistribution::path::<5>());
+            print_distribution(&distr);
+        }
+        "distribution-6" => {
+            set_rerolls(args.get(2));
+
+            let distr = distribution_from_start::<6>();
+            distr.save(&ScoreDistribution::path::<6>());
+            print_distribution(&distr);
+        }
+        "query-distribution-5" => {
+            let score = args[2].parse().unwrap();
+            set_rerolls(args.get(3));
+
+            let distr =
+                ScoreDistribution::load(&ScoreDistribution::path::<5>())
+                    .unwrap();
+
+            println!("P(score >= {score}) = {:.6}", distr.prob_at_least(score));
+        }
+        "query-distribution-6" => {
+            let score = args[2].parse().unwrap();
+            set_rerolls(args.get(3));
+
+            let distr =
+                ScoreDistribution::load(&ScoreDistribution::path::<6>())
+                    .unwrap();
+
+            println!("P(score >= {score}) = {:.6}", distr.prob_at_least(score));
+        }
         "compute-strat-6x" => {
             let na = args[2].parse().unwrap();
             let nb = args[3].parse().unwrap();
             let nt = args[4].parse().unwrap();
 
+            set_max_saved(args.get(5));
+
             let mut layer = Layer::<6, true> {
                 na,
                 nb,
@@ -136,7 +433,7 @@ fn main() {
             let mut prev_above = (nb < 6).then(|| Layer::<6, true> {
                 na: na + 1,
                 nb,
-                nt: nt + 2,
+                nt: next_turn_throws(nt),
                 scores: None,
                 strats: None,
             });
@@ -144,7 +441,7 @@ fn main() {
             let mut prev_below = (nb < 14).then(|| Layer::<6, true> {
                 na,
                 nb: nb + 1,
-                nt: nt + 2,
+                nt: next_turn_throws(nt),
                 scores: None,
                 strats: None,
             });
@@ -167,7 +464,7 @@ fn main() {
                 l.load_scores();
             }
 
-            let (scores, strats) = solve_layer_6dicex(
+            let (scores, strats) = solve_layer_x::<MaxiYatzy>(
                 na,
                 nb,
                 prev_above
@@ -190,3 +487,26 @@ fn main() {
         _ => panic!(),
     }
 }
+
+// Sets the cap on saved throws if it is given.
+fn set_max_saved(arg: Option<&String>) {
+    if let Some(cap) = arg {
+        MAX_SAVED.set(cap.parse().unwrap()).unwrap();
+    }
+}
+
+// Sets the amount of rerolls per turn if it is given.
+fn set_rerolls(arg: Option<&String>) {
+    if let Some(rerolls) = arg {
+        REROLLS.set(rerolls.parse().unwrap()).unwrap();
+    }
+}
+
+fn print_distribution(distr: &ScoreDistribution) {
+    println!("Expected score: {:.2}", distr.mean());
+    println!("Standard deviation: {:.2}", distr.std());
+
+    for q in [0.01, 0.05, 0.25, 0.5, 0.75, 0.95, 0.99] {
+        println!("{:2.0}th percentile: {}", q * 100.0, distr.quantile(q));
+    }
+}
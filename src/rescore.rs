@@ -0,0 +1,221 @@
+// This is the module for finding the expected remaining score of a single
+// state of a free game when its scores have been pruned from the cache, see
+// `prune_scores`. The strategies are still there, so the expected score of a
+// turn is found by following them through the turns after it, all the way to
+// the end of the game. With only a few open cells there are few enough of
+// those turns to do this on demand, and the expected scores at the start of
+// every turn are memoized between queries.
+
+use std::collections::HashMap;
+
+use ndarray::s;
+use rand::{seq::index::sample, Rng};
+
+use crate::{
+    dice_distributions::DICE_REROLL_MATRICES,
+    dice_throw::DiceThrow,
+    distribution::throws,
+    level_ordering::points_above,
+    macrosolver::{
+        outcore::{next_turn_throws, rerolls},
+        store::{Strategy, StrategyStore},
+    },
+    solver::throw_probs,
+    yatzy::{cell_from_dice, State},
+};
+
+pub struct Rescorer<const N: usize, const X: bool> {
+    // The expected remaining score before the dice are thrown, by the state
+    // and the throws left.
+    memo: HashMap<(State<N>, usize), f64>,
+    throws: Vec<DiceThrow>,
+    probs: Vec<f64>,
+}
+
+impl<const N: usize, const X: bool> Default for Rescorer<N, X> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize, const X: bool> Rescorer<N, X> {
+    pub fn new() -> Self {
+        Self {
+            memo: HashMap::new(),
+            throws: throws::<N>(),
+            probs: throw_probs::<N>(),
+        }
+    }
+
+    fn strategy(
+        store: &StrategyStore<N, X>,
+        cells: &[bool],
+        dice: &DiceThrow,
+        points_above: usize,
+        throws_left: usize,
+    ) -> Option<Strategy> {
+        if X {
+            store.strategy(cells, dice, points_above, throws_left)
+        } else if throws_left == 0 {
+            store.cell(cells, dice, points_above).map(Strategy::Cell)
+        } else {
+            store
+                .rethrow(cells, dice, points_above, throws_left)
+                .map(Strategy::Rethrow)
+        }
+    }
+
+    // The expected remaining score for every throw with `throws_left` throws
+    // left in `state`, or None if a strategy it needs is not solved.
+    fn values(
+        &mut self,
+        store: &StrategyStore<N, X>,
+        state: State<N>,
+        throws_left: usize,
+    ) -> Option<Vec<f64>> {
+        let cells: Vec<_> = (0..cell_from_dice::<N>())
+            .map(|i| state.is_filled(i))
+            .collect();
+
+        let reroll_matrices = &DICE_REROLL_MATRICES[N - 1];
+
+        let mut values: Vec<f64> = Vec::new();
+
+        for nt in 0..=throws_left {
+            let mut new_values = Vec::with_capacity(self.throws.len());
+
+            for ti in 0..self.throws.len() {
+                let dice = self.throws[ti];
+
+                let value = match Self::strategy(
+                    store,
+                    &cells,
+                    &dice,
+                    state.points_above,
+                    nt,
+                )? {
+                    Strategy::Cell(cell) => {
+                        let (next, points) = state.set_cell(cell, dice);
+                        let next_throws = if X {
+                            next_turn_throws(nt)
+                        } else {
+                            rerolls::<N>()
+                        };
+
+                        points as f64 + self.start(store, next, next_throws)?
+                    }
+                    Strategy::Rethrow(reroll) => reroll_matrices
+                        .slice(s![ti, reroll as usize, ..])
+                        .iter()
+                        .zip(&values)
+                        .map(|(&p, &x)| p as f64 * x)
+                        .sum(),
+                };
+
+                new_values.push(value);
+            }
+
+            values = new_values;
+        }
+
+        Some(values)
+    }
+
+    // The expected remaining score at the start of a turn in `state`, before
+    // the dice are thrown.
+    fn start(
+        &mut self,
+        store: &StrategyStore<N, X>,
+        mut state: State<N>,
+        throws_left: usize,
+    ) -> Option<f64> {
+        if state.open_cells().next().is_none() {
+            return Some(0.0);
+        }
+
+        // The bonus is all that matters about the points above the line once
+        // it is reached.
+        state.points_above = state.points_above.min(points_above::<N>());
+
+        if let Some(&value) = self.memo.get(&(state, throws_left)) {
+            return Some(value);
+        }
+
+        let value = self
+            .values(store, state, throws_left)?
+            .iter()
+            .zip(&self.probs)
+            .map(|(&x, &p)| p * x)
+            .sum();
+
+        self.memo.insert((state, throws_left), value);
+
+        Some(value)
+    }
+
+    // The expected remaining score with the current dice, like
+    // `StrategyStore::score` would give it if the scores were there. This is
+    // None if a strategy it needs is not solved.
+    pub fn score(
+        &mut self,
+        store: &StrategyStore<N, X>,
+        cells: &[bool],
+        dice: &DiceThrow,
+        points_above: usize,
+        throws_left: usize,
+    ) -> Option<f32> {
+        let state = State::<N>::from_dyn(cells, points_above);
+
+        let values = self.values(store, state, throws_left)?;
+
+        Some(values[dice.get_index()] as f32)
+    }
+}
+
+// Checks the expected scores found from the strategies against the scores of
+// an unpruned cache, for n random states with at most `max_open` open cells.
+pub fn check_rescore<const N: usize>(n: usize, max_open: usize) {
+    let store = StrategyStore::<N, false>::open();
+    let mut rescorer = Rescorer::<N, false>::new();
+
+    let mut rng = rand::thread_rng();
+    let n_cells = cell_from_dice::<N>();
+
+    let mut max_diff = 0.0f32;
+
+    for _ in 0..n {
+        let n_open = rng.gen_range(1..=max_open);
+
+        let mut cells = vec![true; n_cells];
+        for i in sample(&mut rng, n_cells, n_open) {
+            cells[i] = false;
+        }
+
+        // Only points that can be scored in the filled cells above the line.
+        let points_above = (0..6)
+            .filter(|&i| cells[i])
+            .map(|i| rng.gen_range(0..=N) * (i + 1))
+            .sum();
+
+        let dice = DiceThrow::throw(N);
+        let throws_left = rng.gen_range(0..=rerolls::<N>());
+
+        let score = store
+            .score(&cells, &dice, points_above, throws_left)
+            .expect("The scores are not solved, or they are pruned!");
+        let rescore = rescorer
+            .score(&store, &cells, &dice, points_above, throws_left)
+            .expect("The strategies are not solved!");
+
+        let diff = (score - rescore).abs();
+        assert!(
+            diff < 1e-3,
+            "The scores differ by {diff:e} with cells {cells:?}, {points_above} \
+             points above, dice {dice:?} and {throws_left} throws left!"
+        );
+
+        max_diff = max_diff.max(diff);
+    }
+
+    println!("Checked {n} states, largest difference: {max_diff:e}");
+}
@@ -0,0 +1,208 @@
+// This is the module describing the rule sets the solvers can play by. A rule
+// set gives the amount of dice, the cells with their names and scoring, the
+// bonus above the line and the amount of throws per turn. The first six cells
+// are always ones to sixes above the line.
+//
+// The solvers that are generic over the amount of dice N get their rule set
+// through `with_rules!`, which is the only place mapping N to a rule set.
+
+use crate::dice_throw::DiceThrow;
+
+pub trait RuleSet {
+    const DICE: usize;
+    const CELLS: usize;
+    const THROWS: usize;
+    const BONUS_THRESHOLD: usize;
+    const BONUS: usize;
+    // The name of each cell, together with the short names it goes by in the
+    // guide.
+    const CELL_NAMES: &'static [(&'static str, &'static [&'static str])];
+
+    fn cell_score(throw: &DiceThrow, cell: usize) -> usize;
+
+    fn bonus(points_above: usize) -> usize {
+        if points_above >= Self::BONUS_THRESHOLD {
+            Self::BONUS
+        } else {
+            0
+        }
+    }
+
+    fn cell_index(name: &str) -> Option<usize> {
+        Self::CELL_NAMES
+            .iter()
+            .position(|(_, short)| short.contains(&name))
+    }
+
+    fn cell_name(cell: usize) -> &'static str {
+        Self::CELL_NAMES[cell].0
+    }
+}
+
+// Evaluates the expression with the rule set for N dice as the type R.
+#[macro_export]
+macro_rules! with_rules {
+    ($n:expr, $r:ident => $body:expr) => {
+        match $n {
+            5 => {
+                type $r = $crate::rules::Yatzy;
+                $body
+            }
+            6 => {
+                type $r = $crate::rules::MaxiYatzy;
+                $body
+            }
+            _ => panic!("Amount of dice not supported!"),
+        }
+    };
+}
+
+// The Scandinavian rules with 5 dice.
+pub struct Yatzy;
+
+impl RuleSet for Yatzy {
+    const DICE: usize = 5;
+    const CELLS: usize = 15;
+    const THROWS: usize = 3;
+    const BONUS_THRESHOLD: usize = 63;
+    const BONUS: usize = 50;
+    const CELL_NAMES: &'static [(&'static str, &'static [&'static str])] = &[
+        ("ones", &["1s"]),
+        ("twos", &["2s"]),
+        ("threes", &["3s"]),
+        ("fours", &["4s"]),
+        ("fives", &["5s"]),
+        ("sixes", &["6s"]),
+        ("1 pair", &["1p"]),
+        ("2 pairs", &["2p"]),
+        ("3 of a kind", &["3l"]),
+        ("4 of a kind", &["4l"]),
+        ("small straight", &["ls"]),
+        ("large straight", &["ss"]),
+        ("house", &["hs"]),
+        ("chance", &["ch", "sj"]),
+        ("yatzy", &["yz"]),
+    ];
+
+    fn cell_score(throw: &DiceThrow, cell: usize) -> usize {
+        match cell {
+            0 => throw.ammount_of::<1>(),
+            1 => throw.ammount_of::<2>(),
+            2 => throw.ammount_of::<3>(),
+            3 => throw.ammount_of::<4>(),
+            4 => throw.ammount_of::<5>(),
+            5 => throw.ammount_of::<6>(),
+            6 => throw.pairs::<1>(),
+            7 => throw.pairs::<2>(),
+            8 => throw.n_of_a_kind::<3>(),
+            9 => throw.n_of_a_kind::<4>(),
+            10 => throw.straight::<1, 5>(),
+            11 => throw.straight::<2, 6>(),
+            12 => throw.building::<3, 2>(),
+            13 => throw.chance(),
+            14 => throw.yatzy(),
+            _ => unreachable!(),
+        }
+    }
+}
+
+// The Scandinavian rules with 6 dice, also known as maxi yatzy.
+pub struct MaxiYatzy;
+
+impl RuleSet for MaxiYatzy {
+    const DICE: usize = 6;
+    const CELLS: usize = 20;
+    const THROWS: usize = 3;
+    const BONUS_THRESHOLD: usize = 84;
+    const BONUS: usize = 100;
+    const CELL_NAMES: &'static [(&'static str, &'static [&'static str])] = &[
+        ("ones", &["1s"]),
+        ("twos", &["2s"]),
+        ("threes", &["3s"]),
+        ("fours", &["4s"]),
+        ("fives", &["5s"]),
+        ("sixes", &["6s"]),
+        ("1 pair", &["1p"]),
+        ("2 pairs", &["2p"]),
+        ("3 pairs", &["3p"]),
+        ("3 of a kind", &["3l"]),
+        ("4 of a kind", &["4l"]),
+        ("5 of a kind", &["5l"]),
+        ("small straight", &["ls"]),
+        ("large straight", &["ss"]),
+        ("full straight", &["fs"]),
+        ("hut", &["ht"]),
+        ("house", &["hs"]),
+        ("tower", &["tr"]),
+        ("chance", &["ch", "sj"]),
+        ("yatzy", &["yz"]),
+    ];
+
+    fn cell_score(throw: &DiceThrow, cell: usize) -> usize {
+        match cell {
+            0 => throw.ammount_of::<1>(),
+            1 => throw.ammount_of::<2>(),
+            2 => throw.ammount_of::<3>(),
+            3 => throw.ammount_of::<4>(),
+            4 => throw.ammount_of::<5>(),
+            5 => throw.ammount_of::<6>(),
+            6 => throw.pairs::<1>(),
+            7 => throw.pairs::<2>(),
+            8 => throw.pairs::<3>(),
+            9 => throw.n_of_a_kind::<3>(),
+            10 => throw.n_of_a_kind::<4>(),
+            11 => throw.n_of_a_kind::<5>(),
+            12 => throw.straight::<1, 5>(),
+            13 => throw.straight::<2, 6>(),
+            14 => throw.straight::<1, 6>(),
+            15 => throw.building::<3, 2>(),
+            16 => throw.building::<3, 3>(),
+            17 => throw.building::<4, 2>(),
+            18 => throw.chance(),
+            19 => throw.yatzy(),
+            _ => unreachable!(),
+        }
+    }
+}
+
+// The American rules, Yahtzee. The scores here are without the joker rules,
+// which depend on the state and are handled by AmericanState.
+pub struct Yahtzee;
+
+impl RuleSet for Yahtzee {
+    const DICE: usize = 5;
+    const CELLS: usize = 13;
+    const THROWS: usize = 3;
+    const BONUS_THRESHOLD: usize = 63;
+    const BONUS: usize = 35;
+    const CELL_NAMES: &'static [(&'static str, &'static [&'static str])] = &[
+        ("aces", &["1s"]),
+        ("twos", &["2s"]),
+        ("threes", &["3s"]),
+        ("fours", &["4s"]),
+        ("fives", &["5s"]),
+        ("sixes", &["6s"]),
+        ("3 of a kind", &["3k"]),
+        ("4 of a kind", &["4k"]),
+        ("full house", &["fh"]),
+        ("small straight", &["ss"]),
+        ("large straight", &["ls"]),
+        ("yahtzee", &["yz"]),
+        ("chance", &["ch"]),
+    ];
+
+    fn cell_score(throw: &DiceThrow, cell: usize) -> usize {
+        match cell {
+            0..=5 => throw[cell + 1] * (cell + 1),
+            6 => throw.sum_of_a_kind::<3>(),
+            7 => throw.sum_of_a_kind::<4>(),
+            8 if throw.full_house() => 25,
+            9 if throw.longest_run() >= 4 => 30,
+            10 if throw.longest_run() >= 5 => 40,
+            8..=10 => 0,
+            11 => throw.yatzy(),
+            12 => throw.chance(),
+            _ => unreachable!(),
+        }
+    }
+}
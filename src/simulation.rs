@@ -1,260 +1,145 @@
 use arrayvec::ArrayVec;
-use ndarray::{
-    parallel::prelude::{IntoParallelRefMutIterator, ParallelIterator},
-    Array3,
+use ndarray::parallel::prelude::{
+    IntoParallelRefMutIterator, ParallelIterator,
 };
 
 use crate::{
+    american::{AmericanState, AMERICAN_CELLS},
     dice_throw::DiceThrow,
-    guide::{
-        get_state_indices5, get_state_indices6, get_total_score, Strategy,
-    },
+    guide::get_total_score,
+    level_ordering::points_above,
     macrosolver::{
-        outcore::{make_thin_layers_5dice, make_thin_layers_6dice, Layer},
-        outcorex::make_thin_layers_5dicex,
+        outcore::{next_turn_throws, rerolls},
+        store::{LayerStore, Strategy, StrategyStore},
     },
     yatzy::cell_from_dice,
 };
 
 pub fn simulate_n_5(scores: &mut [u32]) {
-    let mut layers = make_thin_layers_5dice();
-
-    for layer in &mut layers {
-        layer.as_mut().unwrap().load_strats();
-    }
+    let store = StrategyStore::<5, false>::open();
 
     scores.par_iter_mut().for_each(|score| {
-        *score = get_total_score::<5>(&simulate_5(&layers)) as u32
+        *score = get_total_score::<5>(&simulate(&store)) as u32
     });
 }
 
 pub fn simulate_n_5x(scores: &mut [u32]) {
-    let mut layers = make_thin_layers_5dicex();
-
-    for layer in &mut layers {
-        layer.as_mut().unwrap().load_strats();
-    }
+    let store = StrategyStore::<5, true>::open();
 
     scores.par_iter_mut().for_each(|score| {
-        *score = get_total_score::<5>(&simulate_5x(&layers)) as u32
+        *score = get_total_score::<5>(&simulate_x(&store)) as u32
     });
 }
 
 pub fn simulate_n_5_full(scores: &mut [[u32; 15]]) {
-    let mut layers = make_thin_layers_5dice();
-
-    for layer in &mut layers {
-        layer.as_mut().unwrap().load_strats();
-    }
+    let store = StrategyStore::<5, false>::open();
 
     scores.par_iter_mut().for_each(|score| {
-        for (score, somescore) in score.iter_mut().zip(simulate_5(&layers)) {
+        for (score, somescore) in score.iter_mut().zip(simulate(&store)) {
             *score = somescore.unwrap() as u32;
         }
     });
 }
 
 pub fn simulate_n_5x_full(scores: &mut [[u32; 15]]) {
-    let mut layers = make_thin_layers_5dicex();
-
-    for layer in &mut layers {
-        layer.as_mut().unwrap().load_strats();
-    }
+    let store = StrategyStore::<5, true>::open();
 
     scores.par_iter_mut().for_each(|score| {
-        for (score, somescore) in score.iter_mut().zip(simulate_5x(&layers)) {
+        for (score, somescore) in score.iter_mut().zip(simulate_x(&store)) {
             *score = somescore.unwrap() as u32;
         }
     });
 }
 
 pub fn simulate_n_6(scores: &mut [u32]) {
-    let mut layers = make_thin_layers_6dice();
-
-    for layer in &mut layers {
-        layer.as_mut().unwrap().load_strats();
-    }
+    let store = StrategyStore::<6, false>::open();
 
     scores.par_iter_mut().for_each(|score| {
-        *score = get_total_score::<6>(&simulate_6(&layers)) as u32
+        *score = get_total_score::<6>(&simulate(&store)) as u32
     });
 }
 
 pub fn simulate_n_6_full(scores: &mut [[u32; 20]]) {
-    let mut layers = make_thin_layers_6dice();
-
-    for layer in &mut layers {
-        layer.as_mut().unwrap().load_strats();
-    }
+    let store = StrategyStore::<6, false>::open();
 
     scores.par_iter_mut().for_each(|score| {
-        for (score, somescore) in score.iter_mut().zip(simulate_6(&layers)) {
+        for (score, somescore) in score.iter_mut().zip(simulate(&store)) {
             *score = somescore.unwrap() as u32;
         }
     });
 }
 
-fn get_rethrow_strat<const N: usize>(
-    cells: &[bool],
-    dice: &DiceThrow,
-    throws_left: usize,
-    points_above: usize,
-    layers: &Array3<Option<Layer<N, false>>>,
-) -> u8 {
-    let [na, nb, _, _, ai, bi] = match N {
-        5 => get_state_indices5(cells, points_above),
-        6 => get_state_indices6(cells, points_above),
-        _ => panic!(),
-    };
-
-    let ti = dice.get_index();
-
-    let layer = layers[[na, nb, throws_left]].as_ref().unwrap();
+pub fn simulate_n_6x(scores: &mut [u32]) {
+    let store = StrategyStore::<6, true>::open();
 
-    layer.strats.as_ref().unwrap()[[ai, bi, ti]]
-}
-
-fn get_cell_strat<const N: usize>(
-    cells: &[bool],
-    dice: &DiceThrow,
-    points_above: usize,
-    layers: &Array3<Option<Layer<N, false>>>,
-) -> usize
-where
-    [(); cell_from_dice::<N>()]:,
-    [(); cell_from_dice::<N>() - 6]:,
-{
-    let [na, nb, _, _, ai, bi] = match N {
-        5 => get_state_indices5(cells, points_above),
-        6 => get_state_indices6(cells, points_above),
-        _ => panic!(),
-    };
-
-    let ti = dice.get_index();
-
-    let layer = layers[[na, nb, 0]].as_ref().unwrap();
-
-    layer.strats.as_ref().unwrap()[[ai, bi, ti]] as usize
+    scores.par_iter_mut().for_each(|score| {
+        *score = get_total_score::<6>(&simulate_x(&store)) as u32
+    });
 }
 
-fn get_combined_strat<const N: usize>(
-    cells: &[bool],
-    dice: &DiceThrow,
-    throws_left: usize,
-    points_above: usize,
-    layers: &Array3<Option<Layer<N, true>>>,
-) -> Strategy {
-    let [na, nb, _, _, ai, bi] = match N {
-        5 => get_state_indices5(cells, points_above),
-        6 => get_state_indices6(cells, points_above),
-        _ => panic!(),
-    };
-
-    let ti = dice.get_index();
-
-    let layer = layers[[na, nb, throws_left]].as_ref().unwrap();
+pub fn simulate_n_6x_full(scores: &mut [[u32; 20]]) {
+    let store = StrategyStore::<6, true>::open();
 
-    let byte = layer.strats.as_ref().unwrap()[[ai, bi, ti]];
-
-    if (byte & 128) != 0 {
-        Strategy::Rethrow(byte & !128)
-    } else {
-        Strategy::Cell(byte as usize)
-    }
+    scores.par_iter_mut().for_each(|score| {
+        for (score, somescore) in score.iter_mut().zip(simulate_x(&store)) {
+            *score = somescore.unwrap() as u32;
+        }
+    });
 }
 
-fn simulate_5(layers: &Array3<Option<Layer<5, false>>>) -> [Option<usize>; 15] {
-    let mut points = [None; 15];
-
-    let mut dice = DiceThrow::throw(5);
+pub fn simulate_n_american(scores: &mut [u32]) {
+    let store = LayerStore::american();
 
-    for _ in 0..15 {
-        for throws_left in [2, 1] {
-            let filled_cells = points.map(|x| x.is_some());
-            let points_above =
-                points.iter().take(6).filter_map(|x| x.as_ref()).sum();
-
-            let reroll = get_rethrow_strat::<5>(
-                &filled_cells,
-                &dice,
-                throws_left,
-                points_above,
-                layers,
-            );
+    scores
+        .par_iter_mut()
+        .for_each(|score| *score = simulate_american(&store) as u32);
+}
 
-            let rethrow = DiceThrow::throw(reroll.count_ones() as usize);
+// Plays a game without saved throws and returns the points in every cell.
+fn simulate<const N: usize>(
+    store: &StrategyStore<N, false>,
+) -> Vec<Option<usize>> {
+    let mut points = vec![None; cell_from_dice::<N>()];
 
-            dice = dice.overwrite_reroll_dyn::<5>(
-                reroll,
-                &rethrow.into_ordered_dice().collect::<ArrayVec<_, 5>>(),
-            );
-        }
+    for _ in 0..cell_from_dice::<N>() {
+        let mut dice = DiceThrow::throw(N);
 
-        let filled_cells = points.map(|x| x.is_some());
+        let filled_cells: Vec<_> = points.iter().map(|x| x.is_some()).collect();
         let points_above =
             points.iter().take(6).filter_map(|x| x.as_ref()).sum();
 
-        let ind =
-            get_cell_strat::<5>(&filled_cells, &dice, points_above, layers);
-
-        let score = dice.cell_score::<5>(ind);
-
-        points[ind] = Some(score);
-
-        dice = DiceThrow::throw(5);
-    }
-
-    points
-}
-
-fn simulate_6(layers: &Array3<Option<Layer<6, false>>>) -> [Option<usize>; 20] {
-    let mut points = [None; 20];
-
-    let mut dice = DiceThrow::throw(6);
-
-    for _ in 0..20 {
-        for throws_left in [2, 1] {
-            let filled_cells = points.map(|x| x.is_some());
-            let points_above =
-                points.iter().take(6).filter_map(|x| x.as_ref()).sum();
-
-            let reroll = get_rethrow_strat::<6>(
-                &filled_cells,
-                &dice,
-                throws_left,
-                points_above,
-                layers,
-            );
+        for throws_left in (1..=rerolls::<N>()).rev() {
+            let reroll = store
+                .rethrow(&filled_cells, &dice, points_above, throws_left)
+                .expect("Strategy not available for state!");
 
             let rethrow = DiceThrow::throw(reroll.count_ones() as usize);
 
-            dice = dice.overwrite_reroll_dyn::<6>(
+            dice = dice.overwrite_reroll_dyn::<N>(
                 reroll,
-                &rethrow.into_ordered_dice().collect::<ArrayVec<_, 6>>(),
+                &rethrow.into_ordered_dice().collect::<ArrayVec<_, N>>(),
             );
         }
 
-        let filled_cells = points.map(|x| x.is_some());
-        let points_above =
-            points.iter().take(6).filter_map(|x| x.as_ref()).sum();
-
-        let ind =
-            get_cell_strat::<6>(&filled_cells, &dice, points_above, layers);
+        let ind = store
+            .cell(&filled_cells, &dice, points_above)
+            .expect("Strategy not available for state!");
 
-        let score = dice.cell_score::<6>(ind);
+        let score = dice.cell_score::<N>(ind);
 
         points[ind] = Some(score);
-
-        dice = DiceThrow::throw(6);
     }
 
     points
 }
 
-fn simulate_5x(layers: &Array3<Option<Layer<5, true>>>) -> [Option<usize>; 15] {
-    let mut points = [None; 15];
+// Plays a game with saved throws and returns the points in every cell.
+fn simulate_x<const N: usize>(
+    store: &StrategyStore<N, true>,
+) -> Vec<Option<usize>> {
+    let mut points = vec![None; cell_from_dice::<N>()];
 
-    let mut dice = DiceThrow::throw(5);
+    let mut dice = DiceThrow::throw(N);
 
     let mut throws_left = 2;
 
@@ -263,29 +148,26 @@ fn simulate_5x(layers: &Array3<Option<Layer<5, true>>>) -> [Option<usize>; 15] {
             break points;
         }
 
-        let filled_cells = points.map(|x| x.is_some());
+        let filled_cells: Vec<_> = points.iter().map(|x| x.is_some()).collect();
         let points_above =
             points.iter().take(6).filter_map(|x| x.as_ref()).sum();
 
-        match get_combined_strat::<5>(
-            &filled_cells,
-            &dice,
-            throws_left,
-            points_above,
-            layers,
-        ) {
+        match store
+            .strategy(&filled_cells, &dice, points_above, throws_left)
+            .expect("Strategy not available for state!")
+        {
             Strategy::Cell(ind) => {
-                let score = dice.cell_score::<5>(ind);
+                let score = dice.cell_score::<N>(ind);
                 points[ind] = Some(score);
-                dice = DiceThrow::throw(5);
-                throws_left += 2;
+                dice = DiceThrow::throw(N);
+                throws_left = next_turn_throws(throws_left);
             }
             Strategy::Rethrow(reroll) => {
                 let rethrow = DiceThrow::throw(reroll.count_ones() as usize);
 
-                dice = dice.overwrite_reroll_dyn::<5>(
+                dice = dice.overwrite_reroll_dyn::<N>(
                     reroll,
-                    &rethrow.into_ordered_dice().collect::<ArrayVec<_, 5>>(),
+                    &rethrow.into_ordered_dice().collect::<ArrayVec<_, N>>(),
                 );
 
                 throws_left -= 1;
@@ -293,3 +175,96 @@ fn simulate_5x(layers: &Array3<Option<Layer<5, true>>>) -> [Option<usize>; 15] {
         }
     }
 }
+
+fn get_american_strat(
+    state: &AmericanState,
+    dice: &DiceThrow,
+    throws_left: usize,
+    store: &LayerStore,
+) -> u8 {
+    let layer = [state.get_n_above(), state.get_n_below(), throws_left, 0];
+    let index = [
+        state.get_above_index(),
+        state.get_below_index(),
+        dice.get_index(),
+    ];
+
+    store
+        .byte(layer, index)
+        .expect("Strategy not available for state!")
+}
+
+// Plays a game with the American rules and returns the total score,
+// including the bonuses.
+fn simulate_american(store: &LayerStore) -> usize {
+    let mut state = AmericanState::new();
+    let mut total = 0;
+
+    for _ in 0..AMERICAN_CELLS {
+        let mut dice = DiceThrow::throw(5);
+
+        for throws_left in (1..=rerolls::<5>()).rev() {
+            let reroll = get_american_strat(&state, &dice, throws_left, store);
+
+            let rethrow = DiceThrow::throw(reroll.count_ones() as usize);
+
+            dice = dice.overwrite_reroll_dyn::<5>(
+                reroll,
+                &rethrow.into_ordered_dice().collect::<ArrayVec<_, 5>>(),
+            );
+        }
+
+        let ind = get_american_strat(&state, &dice, 0, store) as usize;
+
+        let (new_state, points) = state.set_cell(ind, dice);
+
+        state = new_state;
+        total += points;
+    }
+
+    total
+}
+
+pub fn simulate_n_forced<const N: usize>(scores: &mut [u32]) {
+    let store = LayerStore::forced::<N>();
+
+    scores.par_iter_mut().for_each(|score| {
+        *score = get_total_score::<N>(&simulate_forced::<N>(&store)) as u32
+    });
+}
+
+// Plays a game of forced yatzy, filling the cells in order.
+fn simulate_forced<const N: usize>(store: &LayerStore) -> Vec<Option<usize>> {
+    let goal = points_above::<N>();
+
+    let mut points = vec![None; cell_from_dice::<N>()];
+    let mut points_above = 0;
+
+    for (cell, cell_points) in points.iter_mut().enumerate() {
+        let mut dice = DiceThrow::throw(N);
+
+        for throws_left in (1..=rerolls::<N>()).rev() {
+            let pts = points_above.min(goal);
+            let reroll = store
+                .byte([cell, 0, 0, 0], [throws_left, pts, dice.get_index()])
+                .expect("Strategy not available for state!");
+
+            let rethrow = DiceThrow::throw(reroll.count_ones() as usize);
+
+            dice = dice.overwrite_reroll_dyn::<N>(
+                reroll,
+                &rethrow.into_ordered_dice().collect::<ArrayVec<_, N>>(),
+            );
+        }
+
+        let score = dice.cell_score::<N>(cell);
+
+        if cell < 6 {
+            points_above += score;
+        }
+
+        *cell_points = Some(score);
+    }
+
+    points
+}
@@ -5,50 +5,236 @@ use ndarray::{
     parallel::prelude::{
         IndexedParallelIterator, IntoParallelIterator, ParallelIterator,
     },
-    s, Array2, Array3, ArrayView3, Zip,
+    s, Array1, Array2, Array3, ArrayView3, ArrayViewMut3, Axis, Zip,
 };
 use once_cell::sync::Lazy;
 use thread_local::ThreadLocal;
 
 use crate::{
-    dice_distributions::{
-        dice_order_map_6, DICE_DISTR, DICE_DIVISOR, DICE_ORDER_MAP,
-        DICE_REROLL_MATRICES,
-    },
+    american::AmericanState,
+    dice_distributions::{DICE_DISTR, DICE_PROBS, DICE_REROLL_OPERATORS},
     dice_throw::DiceThrow,
+    distribution::throws,
     level_ordering::{
+        below_cells, below_index, points_above, AboveLevelsType,
         ABOVE_LEVELS_5, ABOVE_LEVELS_6, BELOW_LEVELS_5, BELOW_LEVELS_6,
+        BELOW_LEVELS_A,
     },
-    yatzy::State,
+    macrosolver::outcore::rerolls,
+    rules::{MaxiYatzy, RuleSet, Yahtzee, Yatzy},
+    util::parse_binary,
+    with_rules,
+    yatzy::{cell_from_dice, State},
 };
 
+// What the solvers need to know about a rule set beyond its dice and cells:
+// the size of the layers and which cells the dice can be put in. This is all
+// the layered solvers depend on, so new rules only need to implement this
+// next to `RuleSet`.
+pub trait CellRules: RuleSet + Sync {
+    // The amount of above and below states in layer (na, nb).
+    fn layer_dim(na: usize, nb: usize) -> [usize; 2];
+
+    // Calls `f(cell, new_ai, new_bi, points)` for every cell the throw ti can
+    // be put in from state (ai, bi) of layer (na, nb), in increasing order
+    // of the cells. The new state is in the layer with that cell filled, and
+    // the points include any bonuses.
+    fn for_each_cell(
+        na: usize,
+        nb: usize,
+        ai: usize,
+        bi: usize,
+        ti: usize,
+        f: impl FnMut(usize, usize, usize, usize),
+    );
+}
+
+// The Scandinavian rules, where any open cell can be filled. The rule sets
+// only differ in the amount of dice and cells below the line, which give them
+// their own level tables and lookups.
+pub trait ScandinavianRules: RuleSet + Sync {
+    fn above_levels() -> &'static AboveLevelsType;
+
+    fn below_level_len(nb: usize) -> usize;
+
+    // The above, below and below points lookups.
+    fn lookups() -> (
+        &'static AboveLookupType,
+        &'static [Array2<Option<usize>>],
+        &'static Array2<usize>,
+    );
+}
+
+impl ScandinavianRules for Yatzy {
+    fn above_levels() -> &'static AboveLevelsType {
+        &ABOVE_LEVELS_5
+    }
+
+    fn below_level_len(nb: usize) -> usize {
+        BELOW_LEVELS_5[nb].len()
+    }
+
+    fn lookups() -> (
+        &'static AboveLookupType,
+        &'static [Array2<Option<usize>>],
+        &'static Array2<usize>,
+    ) {
+        (&ABOVE_LOOKUP_5, &BELOW_LOOKUP_5, &BELOW_PTS_LOOKUP_5)
+    }
+}
+
+impl ScandinavianRules for MaxiYatzy {
+    fn above_levels() -> &'static AboveLevelsType {
+        &ABOVE_LEVELS_6
+    }
+
+    fn below_level_len(nb: usize) -> usize {
+        BELOW_LEVELS_6[nb].len()
+    }
+
+    fn lookups() -> (
+        &'static AboveLookupType,
+        &'static [Array2<Option<usize>>],
+        &'static Array2<usize>,
+    ) {
+        (&ABOVE_LOOKUP_6, &BELOW_LOOKUP_6, &BELOW_PTS_LOOKUP_6)
+    }
+}
+
+impl<R: ScandinavianRules> CellRules for R {
+    fn layer_dim(na: usize, nb: usize) -> [usize; 2] {
+        [R::above_levels()[na].len(), R::below_level_len(nb)]
+    }
+
+    fn for_each_cell(
+        na: usize,
+        nb: usize,
+        ai: usize,
+        bi: usize,
+        ti: usize,
+        mut f: impl FnMut(usize, usize, usize, usize),
+    ) {
+        let (above_lookup, below_lookup, below_pts_lookup) = R::lookups();
+
+        if let Some(above_lookup) = above_lookup.get(na) {
+            for (cell_i, x) in above_lookup[[ai, ti]].iter().enumerate() {
+                if let &Some([new_ai, points]) = x {
+                    f(cell_i, new_ai, bi, points);
+                }
+            }
+        }
+
+        if let Some(below_lookup) = below_lookup.get(nb) {
+            for (cell_i, x) in below_lookup.row(bi).iter().enumerate() {
+                if let &Some(new_bi) = x {
+                    f(cell_i + 6, ai, new_bi, below_pts_lookup[[ti, cell_i]]);
+                }
+            }
+        }
+    }
+}
+
+// The American rules, where the joker rules limit which cells can be chosen.
+impl CellRules for Yahtzee {
+    fn layer_dim(na: usize, nb: usize) -> [usize; 2] {
+        [ABOVE_LEVELS_5[na].len(), BELOW_LEVELS_A[nb].len()]
+    }
+
+    fn for_each_cell(
+        na: usize,
+        nb: usize,
+        ai: usize,
+        bi: usize,
+        ti: usize,
+        mut f: impl FnMut(usize, usize, usize, usize),
+    ) {
+        let (points_above, above_level) = ABOVE_LEVELS_5[na][ai];
+        let (below_level, yahtzee_bonus) = BELOW_LEVELS_A[nb][bi];
+        let throw = DiceThrow::from_index(5, ti);
+        let state = AmericanState::from((
+            above_level,
+            below_level,
+            points_above,
+            yahtzee_bonus,
+        ));
+
+        for cell_i in state.allowed_cells(&throw) {
+            let (new_state, points) = state.set_cell(cell_i, throw);
+
+            f(
+                cell_i,
+                new_state.get_above_index(),
+                new_state.get_below_index(),
+                points,
+            );
+        }
+    }
+}
+
+// The expected score of every state of a layer before the dice are thrown.
+// The cell choices only need these, so they are computed once per state of
+// the prev layers instead of once per choice leading to it.
+fn expected_before_throw(
+    layer_scores: &Array3<f32>,
+    probs: &[f64],
+) -> Array2<f64> {
+    let (n_ai, n_bi, _) = layer_scores.dim();
+
+    let mut expected = Array2::zeros([n_ai, n_bi]);
+
+    Zip::from(&mut expected)
+        .and(layer_scores.lanes(Axis(2)))
+        .par_for_each(|expected, scores| {
+            *expected = probs
+                .iter()
+                .zip(scores)
+                .fold(0.0, |score, (&prob, &x)| prob.mul_add(x as f64, score));
+        });
+
+    expected
+}
+
+// The same in f32 for the solvers with saved throws. Every lane is a dot
+// product with the probabilities, which ndarray unrolls into independent
+// sums that get vectorized.
+fn expected_before_throw_f32(
+    layer_scores: ArrayView3<f32>,
+    probs: &Array1<f32>,
+) -> Array2<f32> {
+    let (n_ai, n_bi, _) = layer_scores.dim();
+
+    let mut expected = Array2::zeros([n_ai, n_bi]);
+
+    Zip::from(&mut expected)
+        .and(layer_scores.lanes(Axis(2)))
+        .par_for_each(|expected, scores| *expected = scores.dot(probs));
+
+    expected
+}
+
 // This is the solver that finds which cell to put your points into when you
 // have no throws left
 // na and nb are the number of filled cells above and below the line.
-pub fn solve_layer_type1_5dice(
+pub fn solve_layer_type1<R: CellRules>(
     na: usize,
     nb: usize,
     prev_above_layer_scores: &Array3<f32>,
     prev_below_layer_scores: &Array3<f32>,
 ) -> (Array3<f32>, Array3<u8>) {
-    const N_DICE_THROWS: usize = DICE_DISTR.5.len();
+    let probs = &DICE_PROBS[R::DICE];
+
+    let [n_ai, n_bi] = R::layer_dim(na, nb);
 
-    let n_ai = ABOVE_LEVELS_5[na].len();
-    let n_bi = BELOW_LEVELS_5[nb].len();
+    let shape = [n_ai, n_bi, probs.len()];
 
-    let shape = [n_ai, n_bi, N_DICE_THROWS];
+    let prev_above = expected_before_throw(prev_above_layer_scores, probs);
+    let prev_below = expected_before_throw(prev_below_layer_scores, probs);
 
     let mut scores = Array3::zeros(shape);
     let mut strats = Array3::zeros(shape);
 
     Zip::indexed(&mut scores).and(&mut strats).par_for_each(
         |(ai, bi, ti), cur_score, cur_strat| {
-            let (points_above, above_level) = ABOVE_LEVELS_5[na][ai];
-            let below_level = BELOW_LEVELS_5[nb][bi];
-            let throw = DiceThrow::from(DICE_DISTR.5[ti].0);
-            let state =
-                State::<15>::from((above_level, below_level, points_above));
-
             // This is the inner loop of which states that need to
             // be "solved".
 
@@ -56,42 +242,28 @@ pub fn solve_layer_type1_5dice(
             let mut best_cell_i = 255;
 
             // Looping over the choices to make
-            for (cell_i, _) in
-                state.cells.iter().enumerate().filter(|(_, &cell)| !cell)
-            {
-                let (new_state, extra_score) = state.set_cell(cell_i, throw);
-
-                let new_ai = new_state.get_above_index();
-                let new_bi = new_state.get_below_index();
-
-                let prev_layer = if cell_i < 6 {
-                    prev_above_layer_scores
-                } else {
-                    prev_below_layer_scores
-                };
-
-                // Expected score will be the extra score (guaranteed)
-                // plus the expected score based on what you might roll next
-                let mut expected_score = extra_score as f64;
-
-                // For a given choice of cell, looping over possible
-                // throws to find it's expected score.
-                for (new_ti, &(_, prob)) in DICE_DISTR.5.iter().enumerate() {
-                    let prob = prob as f64 / DICE_DIVISOR[5] as f64;
-
-                    expected_score = prob.mul_add(
-                        *prev_layer
-                            .get([new_ai, new_bi, new_ti])
-                            .unwrap_or(&0.0) as f64,
-                        expected_score,
-                    );
-                }
-
-                if expected_score >= best_score {
-                    best_score = expected_score;
-                    best_cell_i = cell_i;
-                }
-            }
+            R::for_each_cell(
+                na,
+                nb,
+                ai,
+                bi,
+                ti,
+                |cell_i, new_ai, new_bi, extra_score| {
+                    let prev_layer =
+                        if cell_i < 6 { &prev_above } else { &prev_below };
+
+                    // Expected score will be the extra score (guaranteed)
+                    // plus the expected score based on what you might roll
+                    // next
+                    let expected_score = extra_score as f64
+                        + prev_layer.get([new_ai, new_bi]).unwrap_or(&0.0);
+
+                    if expected_score >= best_score {
+                        best_score = expected_score;
+                        best_cell_i = cell_i;
+                    }
+                },
+            );
 
             *cur_score = best_score as f32;
             *cur_strat = best_cell_i as u8;
@@ -101,111 +273,88 @@ pub fn solve_layer_type1_5dice(
     (scores, strats)
 }
 
-fn loop_rerolls_5<const M: usize, const N: usize>(
-    dice_distr: &[([u8; N], u32); M],
-    throw: &DiceThrow,
-    reroll: u8,
-    prev_layer_scores: &Array3<f32>,
-    ai: usize,
-    bi: usize,
-) -> f64 {
-    let mut expected_score = 0.0;
-    for &(rethrow, prob) in dice_distr {
-        let new_throw = throw.overwrite_reroll::<5, N>(reroll, rethrow);
+// Calls `f(score, strat, reroll, expected_score)` for every state of the
+// layer with the best reroll mask of the throw and the expected score of the
+// prev layer after it, where mask 0 is keeping all the dice. Of the rerolls
+// with the same score the lowest mask is picked. The expected scores of every
+// set of kept dice of a (ai, bi) are computed at once as a product with the
+// reroll operator:
+// x_b,t2 * A_k,t2 = b_b,k
+fn for_each_best_reroll(
+    n_dice: usize,
+    prev_layer_scores: ArrayView3<f32>,
+    mut scores: ArrayViewMut3<f32>,
+    mut strats: ArrayViewMut3<u8>,
+    f: impl Fn(&mut f32, &mut u8, u8, f32) + Sync,
+) {
+    let (_, n_bi, _) = prev_layer_scores.dim();
+
+    let operator = &DICE_REROLL_OPERATORS[n_dice - 1];
+    let a_mat = operator.matrix.t();
+    let n_kept = a_mat.ncols();
 
-        let new_ti = DICE_ORDER_MAP.5[&new_throw.collect_dice()];
+    let tls = ThreadLocal::new();
 
-        let prob = prob as f64 / DICE_DIVISOR[N] as f64;
+    scores
+        .outer_iter_mut()
+        .into_par_iter()
+        .zip(strats.outer_iter_mut())
+        .zip(prev_layer_scores.outer_iter())
+        .for_each(|((mut scores, mut strats), prev_scores)| {
+            let buf_cell = tls.get_or(|| {
+                Cell::new(Array2::from_shape_simple_fn([n_bi, n_kept], || 0.0))
+            });
 
-        expected_score = prob.mul_add(
-            prev_layer_scores[[ai, bi, new_ti]] as f64,
-            expected_score,
-        );
-    }
-    expected_score
+            let mut buf = buf_cell.take();
+
+            general_mat_mul(1.0, &prev_scores, &a_mat, 0.0, &mut buf);
+
+            Zip::from(scores.rows_mut())
+                .and(strats.rows_mut())
+                .and(buf.rows())
+                .for_each(|scores, strats, kept_scores| {
+                    for ((score, strat), rerolls) in
+                        scores.into_iter().zip(strats).zip(&operator.rerolls)
+                    {
+                        let (mut best_reroll, row) = rerolls[0];
+                        let mut best_score = kept_scores[row];
+
+                        for &(reroll, row) in &rerolls[1..] {
+                            if kept_scores[row] > best_score {
+                                best_score = kept_scores[row];
+                                best_reroll = reroll;
+                            }
+                        }
+
+                        f(score, strat, best_reroll, best_score);
+                    }
+                });
+
+            buf_cell.set(buf);
+        });
 }
 
 // This is the solver that finds dice to re-throw when having some number of
-// throws left
-pub fn solve_layer_type2_5dice(
-    na: usize,
-    nb: usize,
+// throws left. Rerolling does not depend on the rules, so the layer is the
+// same shape as the prev layer with one throw less.
+// Of the rerolls with the same dice, the mask rerolling the first of them is
+// kept, as that is the one `DiceThrow::get_mask` gives.
+pub fn solve_layer_type2<const N: usize>(
     prev_layer_scores: &Array3<f32>,
 ) -> (Array3<f32>, Array3<u8>) {
-    const N_DICE_THROWS: usize = DICE_DISTR.5.len();
-
-    let n_ai = ABOVE_LEVELS_5[na].len();
-    let n_bi = BELOW_LEVELS_5[nb].len();
-
-    let shape = [n_ai, n_bi, N_DICE_THROWS];
+    let shape = prev_layer_scores.dim();
 
     let mut scores = Array3::zeros(shape);
     let mut strats = Array3::zeros(shape);
 
-    Zip::indexed(&mut scores).and(&mut strats).par_for_each(
-        |(ai, bi, ti), cur_score, cur_strat| {
-            let throw = DiceThrow::from(DICE_DISTR.5[ti].0);
-
-            // This is the inner loop of which states that need to
-            // be "solved".
-
-            // Starting out with no rerolled
-            let mut best_score = prev_layer_scores[[ai, bi, ti]] as f64;
-            let mut best_reroll = 0;
-
-            // Looping over possible rerolls
-            for reroll in throw.into_mask_iter().skip(1) {
-                let expected_score = match reroll.count_ones() {
-                    1 => loop_rerolls_5(
-                        &DICE_DISTR.1,
-                        &throw,
-                        reroll,
-                        prev_layer_scores,
-                        ai,
-                        bi,
-                    ),
-                    2 => loop_rerolls_5(
-                        &DICE_DISTR.2,
-                        &throw,
-                        reroll,
-                        prev_layer_scores,
-                        ai,
-                        bi,
-                    ),
-                    3 => loop_rerolls_5(
-                        &DICE_DISTR.3,
-                        &throw,
-                        reroll,
-                        prev_layer_scores,
-                        ai,
-                        bi,
-                    ),
-                    4 => loop_rerolls_5(
-                        &DICE_DISTR.4,
-                        &throw,
-                        reroll,
-                        prev_layer_scores,
-                        ai,
-                        bi,
-                    ),
-                    5 => loop_rerolls_5(
-                        &DICE_DISTR.5,
-                        &throw,
-                        reroll,
-                        prev_layer_scores,
-                        ai,
-                        bi,
-                    ),
-                    _ => unreachable!(),
-                };
-                if expected_score > best_score {
-                    best_score = expected_score;
-                    best_reroll = reroll;
-                }
-            }
-
-            *cur_score = best_score as f32;
-            *cur_strat = best_reroll;
+    for_each_best_reroll(
+        N,
+        prev_layer_scores.view(),
+        scores.view_mut(),
+        strats.view_mut(),
+        |score, strat, reroll, expected_score| {
+            *score = expected_score;
+            *strat = reroll;
         },
     );
 
@@ -221,357 +370,488 @@ pub fn bottom_layer_dimensions_5dice() -> [usize; 3] {
     ]
 }
 
-// This is the solver that finds which cell to put your points into when you
-// have no throws left
-// na and nb are the number of filled cells above and below the line.
-pub fn solve_layer_type1_6dice(
+pub type AboveLookupType = [Array2<[Option<[usize; 2]>; 6]>; 6];
+
+// The new above index and the points scored for every state of the above
+// levels, throw and cell above the line.
+fn above_lookup<const N: usize>(levels: &AboveLevelsType) -> AboveLookupType {
+    array::from_fn(|na| {
+        Array2::from_shape_fn(
+            [levels[na].len(), DICE_PROBS[N].len()],
+            |(ai, ti)| {
+                let (points_above, above_level) = levels[na][ai];
+                let state = State::<N>::from_masks(
+                    parse_binary(&above_level) as u32,
+                    0,
+                    points_above,
+                );
+                let throw = DiceThrow::from_index(N, ti);
+
+                array::from_fn(|cell_i| {
+                    (!above_level[cell_i]).then(|| {
+                        let (new_state, extra_score) =
+                            state.set_cell(cell_i, throw);
+
+                        [new_state.get_above_index(), extra_score]
+                    })
+                })
+            },
+        )
+    })
+}
+
+// The new below index for every state of the below levels and cell below the
+// line.
+fn below_lookup<const B: usize>(
+    levels: &[Vec<[bool; B]>],
+) -> Vec<Array2<Option<usize>>> {
+    (0..B)
+        .map(|nb| {
+            Array2::from_shape_fn([levels[nb].len(), B], |(bi, cell_i)| {
+                let cells = below_cells(nb, bi);
+
+                (cells & 1 << cell_i == 0)
+                    .then(|| below_index(cells | 1 << cell_i))
+            })
+        })
+        .collect()
+}
+
+// The points of every throw in every cell below the line.
+fn below_pts_lookup<const N: usize>() -> Array2<usize> {
+    Array2::from_shape_fn(
+        [DICE_PROBS[N].len(), cell_from_dice::<N>() - 6],
+        |(ti, cell_i)| {
+            let throw = DiceThrow::from_index(N, ti);
+
+            throw.cell_score::<N>(cell_i + 6)
+        },
+    )
+}
+
+pub static ABOVE_LOOKUP_5: Lazy<AboveLookupType> =
+    Lazy::new(|| above_lookup::<5>(&ABOVE_LEVELS_5));
+pub static ABOVE_LOOKUP_6: Lazy<AboveLookupType> =
+    Lazy::new(|| above_lookup::<6>(&ABOVE_LEVELS_6));
+
+pub static BELOW_LOOKUP_5: Lazy<Vec<Array2<Option<usize>>>> =
+    Lazy::new(|| below_lookup(&*BELOW_LEVELS_5));
+pub static BELOW_LOOKUP_6: Lazy<Vec<Array2<Option<usize>>>> =
+    Lazy::new(|| below_lookup(&*BELOW_LEVELS_6));
+
+pub static BELOW_PTS_LOOKUP_5: Lazy<Array2<usize>> =
+    Lazy::new(below_pts_lookup::<5>);
+pub static BELOW_PTS_LOOKUP_6: Lazy<Array2<usize>> =
+    Lazy::new(below_pts_lookup::<6>);
+
+// This is the solver for a layer with saved throws, where you can put the dice
+// in a cell before the throws are used up. The prev above and below layers
+// are those with the throws left at the start of the next turn, which is
+// nt + 2 unless the saved throws are capped, see `next_turn_throws`.
+pub fn solve_layer_x<'a, R: CellRules>(
     na: usize,
     nb: usize,
-    prev_above_layer_scores: &Array3<f32>,
-    prev_below_layer_scores: &Array3<f32>,
+    prev_above_layer_scores: Option<ArrayView3<'a, f32>>,
+    prev_below_layer_scores: Option<ArrayView3<'a, f32>>,
+    prev_throw_layer_scores: Option<ArrayView3<f32>>,
 ) -> (Array3<f32>, Array3<u8>) {
-    const N_DICE_THROWS: usize = DICE_DISTR.6.len();
+    let probs: Array1<f32> =
+        DICE_PROBS[R::DICE].iter().map(|&p| p as f32).collect();
 
-    let n_ai = ABOVE_LEVELS_6[na].len();
-    let n_bi = BELOW_LEVELS_6[nb].len();
+    let n_dice_throws = probs.len();
 
-    let shape = [n_ai, n_bi, N_DICE_THROWS];
+    let [n_ai, n_bi] = R::layer_dim(na, nb);
+
+    let shape = [n_ai, n_bi, n_dice_throws];
 
     let mut scores = Array3::zeros(shape);
     let mut strats = Array3::zeros(shape);
 
-    Zip::indexed(&mut scores).and(&mut strats).par_for_each(
-        |(ai, bi, ti), cur_score, cur_strat| {
-            let (points_above, above_level) = ABOVE_LEVELS_6[na][ai];
-            let below_level = BELOW_LEVELS_6[nb][bi];
-            let throw = DiceThrow::from(DICE_DISTR.6[ti].0);
-            let state =
-                State::<20>::from((above_level, below_level, points_above));
-
-            // This is the inner loop of which states that need to
-            // be "solved".
-
-            let mut best_score = 0.0;
-            let mut best_cell_i = 255;
-
-            // Looping over the choices to make
-            for (cell_i, _) in
-                state.cells.iter().enumerate().filter(|(_, &cell)| !cell)
-            {
-                let (new_state, extra_score) = state.set_cell(cell_i, throw);
-
-                let new_ai = new_state.get_above_index();
-                let new_bi = new_state.get_below_index();
+    let timer = Instant::now();
 
-                let prev_layer = if cell_i < 6 {
-                    prev_above_layer_scores
-                } else {
-                    prev_below_layer_scores
-                };
+    // There is no layer above the last row or below the last column of the
+    // grid, but then no cell of that kind is open either.
+    let prev_above = prev_above_layer_scores
+        .map(|scores| expected_before_throw_f32(scores, &probs));
+    let prev_below = prev_below_layer_scores
+        .map(|scores| expected_before_throw_f32(scores, &probs));
 
-                // Expected score will be the extra score (guaranteed)
-                // plus the expected score based on what you might roll next
-                let mut expected_score = extra_score as f64;
+    if prev_above.is_some() || prev_below.is_some() {
+        Zip::indexed(&mut scores).and(&mut strats).par_for_each(
+            |(ai, bi, ti), cur_score, cur_strat| {
+                R::for_each_cell(
+                    na,
+                    nb,
+                    ai,
+                    bi,
+                    ti,
+                    |cell_i, new_ai, new_bi, extra_score| {
+                        let prev_layer =
+                            if cell_i < 6 { &prev_above } else { &prev_below };
+
+                        let expected_score = extra_score as f32
+                            + prev_layer.as_ref().unwrap()[[new_ai, new_bi]];
+
+                        if expected_score >= *cur_score {
+                            *cur_score = expected_score;
+                            *cur_strat = cell_i as u8;
+                        }
+                    },
+                );
+            },
+        );
+    }
 
-                // For a given choice of cell, looping over possible
-                // throws to find it's expected score.
-                for (new_ti, &(_, prob)) in DICE_DISTR.6.iter().enumerate() {
-                    let prob = prob as f64 / DICE_DIVISOR[6] as f64;
+    println!("Cells took {:.2?}", timer.elapsed());
 
-                    expected_score = prob.mul_add(
-                        *prev_layer
-                            .get([new_ai, new_bi, new_ti])
-                            .unwrap_or(&0.0) as f64,
-                        expected_score,
-                    );
-                }
+    let timer = Instant::now();
 
-                if expected_score >= best_score {
-                    best_score = expected_score;
-                    best_cell_i = cell_i;
+    if let Some(prev_scores) = prev_throw_layer_scores {
+        for_each_best_reroll(
+            R::DICE,
+            prev_scores,
+            scores.view_mut(),
+            strats.view_mut(),
+            |score, strat, reroll, expected_score| {
+                if expected_score > *score {
+                    *score = expected_score;
+                    *strat = reroll | 128;
                 }
-            }
+            },
+        );
+    }
 
-            *cur_score = best_score as f32;
-            *cur_strat = best_cell_i as u8;
-        },
-    );
+    println!("Rerolls took {:.2?}", timer.elapsed());
 
     (scores, strats)
 }
 
-// Major bottleneck for computing strats
-fn loop_rerolls_6<const M: usize, const N: usize>(
-    dice_distr: &[([u8; N], u32); M],
-    throw: &DiceThrow,
-    reroll: u8,
-    prev_layer_scores: &Array3<f32>,
-    ai: usize,
-    bi: usize,
-) -> f64 {
-    let mut expected_score = 0.0;
-    for &(rethrow, prob) in dice_distr {
-        let new_throw = throw.overwrite_reroll::<6, N>(reroll, rethrow);
-
-        let new_ti = dice_order_map_6(new_throw.collect_dice());
-
-        let prob = prob as f64 / DICE_DIVISOR[N] as f64;
-
-        expected_score = prob.mul_add(
-            prev_layer_scores[[ai, bi, new_ti]] as f64,
-            expected_score,
-        );
-    }
-    expected_score
-}
-
-// This is the solver that finds dice to re-throw when having some number of
-// throws left
-pub fn solve_layer_type2_6dice(
+// This is the solver that finds which cell to put your points into when you
+// have no throws left and the objective is to maximize the probability of
+// getting at least `need` more points.
+// The prev layers are indexed by the amount of points still needed after
+// filling the cell. Index 0 is never used since reaching the target is then
+// guaranteed.
+pub fn solve_layer_target_type1<R: CellRules>(
     na: usize,
     nb: usize,
-    prev_layer_scores: &Array3<f32>,
+    need: usize,
+    prev_above_layers_probs: &[Array3<f32>],
+    prev_below_layers_probs: &[Array3<f32>],
 ) -> (Array3<f32>, Array3<u8>) {
-    const N_DICE_THROWS: usize = DICE_DISTR.6.len();
+    let throw_probs = &DICE_PROBS[R::DICE];
 
-    let n_ai = ABOVE_LEVELS_6[na].len();
-    let n_bi = BELOW_LEVELS_6[nb].len();
+    let [n_ai, n_bi] = R::layer_dim(na, nb);
 
-    let shape = [n_ai, n_bi, N_DICE_THROWS];
+    let shape = [n_ai, n_bi, throw_probs.len()];
 
-    let mut scores = Array3::zeros(shape);
+    let mut probs = Array3::zeros(shape);
     let mut strats = Array3::zeros(shape);
 
-    Zip::indexed(&mut scores).and(&mut strats).par_for_each(
-        |(ai, bi, ti), cur_score, cur_strat| {
-            let throw = DiceThrow::from(DICE_DISTR.6[ti].0);
+    Zip::indexed(&mut probs).and(&mut strats).par_for_each(
+        |(ai, bi, ti), cur_prob, cur_strat| {
+            let mut best_prob = 0.0;
+            let mut best_cell_i = 255;
 
-            // This is the inner loop of which states that need to
-            // be "solved".
+            R::for_each_cell(
+                na,
+                nb,
+                ai,
+                bi,
+                ti,
+                |cell_i, new_ai, new_bi, extra_score| {
+                    let new_need = need.saturating_sub(extra_score);
+
+                    let prob = if new_need == 0 {
+                        1.0
+                    } else {
+                        let prev_layer = if cell_i < 6 {
+                            &prev_above_layers_probs[new_need]
+                        } else {
+                            &prev_below_layers_probs[new_need]
+                        };
+
+                        let mut prob = 0.0;
+
+                        for (new_ti, &p) in throw_probs.iter().enumerate() {
+                            prob = p.mul_add(
+                                *prev_layer
+                                    .get([new_ai, new_bi, new_ti])
+                                    .unwrap_or(&0.0)
+                                    as f64,
+                                prob,
+                            );
+                        }
 
-            // Starting out with no rerolled
-            let mut best_score = prev_layer_scores[[ai, bi, ti]] as f64;
-            let mut best_reroll = 0;
-
-            // Looping over possible rerolls
-            for reroll in throw.into_mask_iter().skip(1) {
-                let expected_score = match reroll.count_ones() {
-                    1 => loop_rerolls_6(
-                        &DICE_DISTR.1,
-                        &throw,
-                        reroll,
-                        prev_layer_scores,
-                        ai,
-                        bi,
-                    ),
-                    2 => loop_rerolls_6(
-                        &DICE_DISTR.2,
-                        &throw,
-                        reroll,
-                        prev_layer_scores,
-                        ai,
-                        bi,
-                    ),
-                    3 => loop_rerolls_6(
-                        &DICE_DISTR.3,
-                        &throw,
-                        reroll,
-                        prev_layer_scores,
-                        ai,
-                        bi,
-                    ),
-                    4 => loop_rerolls_6(
-                        &DICE_DISTR.4,
-                        &throw,
-                        reroll,
-                        prev_layer_scores,
-                        ai,
-                        bi,
-                    ),
-                    5 => loop_rerolls_6(
-                        &DICE_DISTR.5,
-                        &throw,
-                        reroll,
-                        prev_layer_scores,
-                        ai,
-                        bi,
-                    ),
-                    6 => loop_rerolls_6(
-                        &DICE_DISTR.6,
-                        &throw,
-                        reroll,
-                        prev_layer_scores,
-                        ai,
-                        bi,
-                    ),
-                    _ => unreachable!(),
-                };
-                if expected_score > best_score {
-                    best_score = expected_score;
-                    best_reroll = reroll;
-                }
-            }
+                        prob
+                    };
 
-            *cur_score = best_score as f32;
-            *cur_strat = best_reroll;
+                    if prob >= best_prob {
+                        best_prob = prob;
+                        best_cell_i = cell_i;
+                    }
+                },
+            );
+
+            *cur_prob = best_prob as f32;
+            *cur_strat = best_cell_i as u8;
         },
     );
 
-    (scores, strats)
+    (probs, strats)
 }
 
-static ABOVE_LOOKUP_5: Lazy<AboveLookupType> = Lazy::new(|| {
-    let mut res = array::from_fn(|na| {
-        Array2::from_shape_simple_fn(
-            [ABOVE_LEVELS_5[na].len(), DICE_DISTR.5.len()],
-            || [None; 6],
-        )
-    });
-
-    for na in 0..6 {
-        for (ai, &(points_above, above_level)) in
-            ABOVE_LEVELS_5[na].iter().enumerate()
-        {
-            let state =
-                State::<15>::from((above_level, [false; 9], points_above));
+// The probability of each throw of N dice, in the order of DICE_DISTR.
+pub fn throw_probs<const N: usize>() -> Vec<f64> {
+    DICE_PROBS[N].clone()
+}
 
-            for (ti, &(tr, _)) in DICE_DISTR.5.iter().enumerate() {
-                let throw = DiceThrow::from(tr);
+// The above, below and below points lookups for N dice.
+pub fn lookups<const N: usize>() -> (
+    &'static AboveLookupType,
+    &'static [Array2<Option<usize>>],
+    &'static Array2<usize>,
+) {
+    with_rules!(N, R => R::lookups())
+}
 
-                for cell_i in (0..6).filter(|&i| !above_level[i]) {
-                    let (new_state, extra_score) =
-                        state.set_cell(cell_i, throw);
+// This finds the first three moments of the remaining score when following
+// the strategy `strats` for layer (na, nb, nt), given the moments of the
+// layers it depends on. Missing layers are treated as the end of the game.
+// If X is false the strategy is a cell when nt is 0 and a reroll mask
+// otherwise, and if X is true rerolls are marked by the highest bit.
+pub fn solve_layer_moments<'a, R: CellRules, const X: bool>(
+    na: usize,
+    nb: usize,
+    nt: usize,
+    strats: ArrayView3<u8>,
+    prev_above_layer_moments: Option<&'a [ArrayView3<'a, f32>; 3]>,
+    prev_below_layer_moments: Option<&'a [ArrayView3<'a, f32>; 3]>,
+    prev_throw_layer_moments: Option<&'a [ArrayView3<'a, f32>; 3]>,
+) -> [Array3<f32>; 3] {
+    let operator = &DICE_REROLL_OPERATORS[R::DICE - 1];
+    let probs = &DICE_PROBS[R::DICE];
+
+    let shape = strats.dim();
+
+    let mut m1 = Array3::zeros(shape);
+    let mut m2 = Array3::zeros(shape);
+    let mut m3 = Array3::zeros(shape);
+
+    Zip::indexed(&mut m1)
+        .and(&mut m2)
+        .and(&mut m3)
+        .and(&strats)
+        .par_for_each(|(ai, bi, ti), m1, m2, m3, &strat| {
+            let reroll = if X {
+                (strat & 128 != 0).then_some(strat & !128)
+            } else {
+                (nt > 0).then_some(strat)
+            };
+
+            let moments = if let Some(reroll) = reroll {
+                // Rerolling gives no points, so the moments are just averaged
+                // over the possible outcomes.
+                let Some(prev) = prev_throw_layer_moments else {
+                    return;
+                };
 
-                    let new_ai = new_state.get_above_index();
+                let row =
+                    operator.matrix.row(operator.row(R::DICE, ti, reroll));
+
+                prev.each_ref().map(|prev| {
+                    row.iter()
+                        .zip(prev.slice(s![ai, bi, ..]))
+                        .map(|(&r, &x)| r as f64 * x as f64)
+                        .sum::<f64>()
+                })
+            } else {
+                let cell = strat as usize;
+
+                let mut next = None;
+
+                R::for_each_cell(
+                    na,
+                    nb,
+                    ai,
+                    bi,
+                    ti,
+                    |cell_i, new_ai, new_bi, points| {
+                        if cell_i == cell {
+                            next = Some((new_ai, new_bi, points));
+                        }
+                    },
+                );
 
-                    res[na][[ai, ti]][cell_i] = Some([new_ai, extra_score]);
-                }
-            }
-        }
-    }
+                // No cell to fill means the game is over.
+                let Some((new_ai, new_bi, points)) = next else {
+                    return;
+                };
 
-    res
-});
+                let prev = if cell < 6 {
+                    prev_above_layer_moments
+                } else {
+                    prev_below_layer_moments
+                };
 
-static BELOW_LOOKUP_5: Lazy<[Array2<Option<usize>>; 9]> = Lazy::new(|| {
-    let mut res = array::from_fn(|nb| {
-        Array2::from_shape_simple_fn([BELOW_LEVELS_5[nb].len(), 9], || None)
-    });
+                let e = prev.map_or([0.0; 3], |prev| {
+                    prev.each_ref().map(|prev| {
+                        probs
+                            .iter()
+                            .zip(prev.slice(s![new_ai, new_bi, ..]))
+                            .map(|(&p, &x)| p * x as f64)
+                            .sum::<f64>()
+                    })
+                });
 
-    for nb in 0..9 {
-        for (bi, &below_level) in BELOW_LEVELS_5[nb].iter().enumerate() {
-            for cell_i in (6..9 + 6).filter(|&i| !below_level[i - 6]) {
-                let mut new_level = below_level;
-                new_level[cell_i - 6] = true;
-                let state = State::<15>::from(([false; 6], new_level, 0));
+                // Moments of points + R, where R is the remaining score
+                // after filling the cell.
+                let p = points as f64;
 
-                let new_bi = state.get_below_index();
+                [
+                    p + e[0],
+                    p * p + 2.0 * p * e[0] + e[1],
+                    p * p * p + 3.0 * p * p * e[0] + 3.0 * p * e[1] + e[2],
+                ]
+            };
 
-                res[nb][[bi, cell_i - 6]] = Some(new_bi);
-            }
-        }
-    }
+            *m1 = moments[0] as f32;
+            *m2 = moments[1] as f32;
+            *m3 = moments[2] as f32;
+        });
 
-    res
-});
+    [m1, m2, m3]
+}
 
-static BELOW_PTS_LOOKUP_5: Lazy<Array2<usize>> = Lazy::new(|| {
-    Array2::from_shape_fn([DICE_DISTR.5.len(), 9], |(ti, cell_i)| {
-        let throw = DiceThrow::from(DICE_DISTR.5[ti].0);
+// The smallest exponent of exp(-λ·(x - shift)) in the reroll stage of the risk
+// solver. exp(-80) is about 1.8e-35, which is still a normal f32, and even
+// times the smallest probability of a throw with 6 dice it does not round to
+// zero, so the sums of a row always have a finite log. The exponents are never
+// positive because of the shift, and a value is only clamped when it is more
+// than 80 / |λ| points from the best or worst value of its row, which is more
+// than the highest final score of 483 points unless |λ| is above 0.16.
+const MIN_RISK_EXPONENT: f32 = -80.0;
+
+// The value to shift by before taking exp(-λ·x) of the values, such that
+// no exponent is positive and the sums cannot overflow.
+fn risk_shift<'a>(
+    lambda: f32,
+    values: impl IntoIterator<Item = &'a f32>,
+) -> f32 {
+    if lambda > 0.0 {
+        values.into_iter().copied().fold(f32::MAX, f32::min)
+    } else {
+        values.into_iter().copied().fold(f32::MIN, f32::max)
+    }
+}
 
-        throw.cell_score::<5>(cell_i + 6)
-    })
-});
+// The certainty equivalent -ln(E[exp(-λ·X)]) / λ of X, where X takes the
+// values `values` with the probabilities `probs`.
+fn certainty_equivalent<'a>(
+    lambda: f32,
+    probs: &[f64],
+    values: impl IntoIterator<Item = &'a f32> + Clone,
+) -> f32 {
+    let shift = risk_shift(lambda, values.clone());
+    let lambda = lambda as f64;
+
+    let sum: f64 = probs
+        .iter()
+        .zip(values)
+        .map(|(&p, &x)| p * (-lambda * (x - shift) as f64).exp())
+        .sum();
+
+    (shift as f64 - sum.ln() / lambda) as f32
+}
 
-pub fn solve_layer_5dicex(
+// This is the same as solve_layer_x, but instead of the expected score it
+// maximizes the exponential utility E[-exp(-λ·score)], which is risk averse
+// for λ > 0 and risk seeking for λ < 0. The points already scored only scale
+// the utility by a positive factor, so the best choice does not depend on
+// them, and the scores are the certainty equivalents -ln(E[exp(-λ·R)]) / λ of
+// the remaining score R. Unlike the expected score solvers, a missing prev
+// above or below layer only removes the cells on that side.
+pub fn solve_layer_risk<R: CellRules>(
     na: usize,
     nb: usize,
+    lambda: f32,
     prev_above_layer_scores: Option<ArrayView3<f32>>,
     prev_below_layer_scores: Option<ArrayView3<f32>>,
     prev_throw_layer_scores: Option<ArrayView3<f32>>,
 ) -> (Array3<f32>, Array3<u8>) {
-    const N_DICE_THROWS: usize = DICE_DISTR.5.len();
+    let probs = &DICE_PROBS[R::DICE];
+
+    let n_dice_throws = probs.len();
 
-    let n_ai = ABOVE_LEVELS_5[na].len();
-    let n_bi = BELOW_LEVELS_5[nb].len();
+    let [n_ai, n_bi] = R::layer_dim(na, nb);
 
-    let shape = [n_ai, n_bi, N_DICE_THROWS];
+    let shape = [n_ai, n_bi, n_dice_throws];
 
     let mut scores = Array3::zeros(shape);
     let mut strats = Array3::zeros(shape);
 
     let timer = Instant::now();
 
-    if let (Some(prev_above_layer_scores), Some(prev_below_layer_scores)) =
-        (prev_above_layer_scores, prev_below_layer_scores)
-    {
-        let above_lookup = ABOVE_LOOKUP_5[na].view();
-        let below_lookup = BELOW_LOOKUP_5[nb].view();
+    // The certainty equivalent of the remaining score right after filling a
+    // cell only depends on the state it leads to, so it is found once for
+    // every state of the prev layers.
+    let next_scores = |prev: ArrayView3<f32>| {
+        let mut next = Array2::zeros([prev.dim().0, prev.dim().1]);
 
-        Zip::indexed(&mut scores).and(&mut strats).par_for_each(
-            |(ai, bi, ti), cur_score, cur_strat| {
-                for (cell_i, [new_ai, extra_score]) in above_lookup[[ai, ti]]
-                    .iter()
-                    .enumerate()
-                    .filter_map(|(i, x)| x.map(|x| (i, x)))
-                {
-                    let prev_scores =
-                        prev_above_layer_scores.slice(s![new_ai, bi, ..]);
-
-                    let expected_score =
-                        DICE_DISTR.5.iter().zip(&prev_scores).fold(
-                            extra_score as f32 * DICE_DIVISOR[5] as f32,
-                            |score, (&(_, prob), x)| {
-                                x.mul_add(prob as f32, score)
-                            },
-                        ) / DICE_DIVISOR[5] as f32;
-
-                    if expected_score > *cur_score {
-                        *cur_score = expected_score;
-                        *cur_strat = cell_i as u8;
-                    }
-                }
-
-                let extra_scores = BELOW_PTS_LOOKUP_5.slice(s![ti, ..]);
-
-                for (cell_i_below, new_bi, extra_score) in below_lookup
-                    .slice(s![bi, ..])
-                    .iter()
-                    .zip(extra_scores)
-                    .enumerate()
-                    .filter_map(|(i, (x, &y))| x.map(|x| (i, x, y)))
-                {
-                    let prev_scores =
-                        prev_below_layer_scores.slice(s![ai, new_bi, ..]);
-
-                    let expected_score =
-                        DICE_DISTR.5.iter().zip(&prev_scores).fold(
-                            extra_score as f32 * DICE_DIVISOR[5] as f32,
-                            |score, (&(_, prob), x)| {
-                                x.mul_add(prob as f32, score)
-                            },
-                        ) / DICE_DIVISOR[5] as f32;
-
-                    if expected_score > *cur_score {
-                        *cur_score = expected_score;
-                        *cur_strat = (cell_i_below + 6) as u8;
-                    }
-                }
+        Zip::from(&mut next).and(prev.lanes(Axis(2))).par_for_each(
+            |next, lane| {
+                *next = certainty_equivalent(lambda, probs, lane);
             },
         );
-    }
+
+        next
+    };
+
+    let above_scores = prev_above_layer_scores.map(next_scores);
+    let below_scores = prev_below_layer_scores.map(next_scores);
+
+    Zip::indexed(&mut scores).and(&mut strats).par_for_each(
+        |(ai, bi, ti), cur_score, cur_strat| {
+            R::for_each_cell(
+                na,
+                nb,
+                ai,
+                bi,
+                ti,
+                |cell_i, new_ai, new_bi, extra_score| {
+                    let prev_scores = if cell_i < 6 {
+                        &above_scores
+                    } else {
+                        &below_scores
+                    };
+
+                    if let Some(prev_scores) = prev_scores {
+                        let score =
+                            extra_score as f32 + prev_scores[[new_ai, new_bi]];
+
+                        if score >= *cur_score {
+                            *cur_score = score;
+                            *cur_strat = cell_i as u8;
+                        }
+                    }
+                },
+            );
+        },
+    );
 
     println!("Cells took {:.2?}", timer.elapsed());
 
     let timer = Instant::now();
 
-    // x_b,t2 * A_t1r,t2 = b_b,t1r
-
-    let a_mat = DICE_REROLL_MATRICES[4]
-        .view()
-        .into_shape([N_DICE_THROWS * 32, N_DICE_THROWS])
-        .unwrap()
-        .reversed_axes();
+    // Same as for the expected score, but on exp(-λ·(x - shift)), where the
+    // shift is taken per row so that no exponent is positive. The exponents
+    // are clamped at MIN_RISK_EXPONENT so that the sums never underflow to
+    // zero. This does not go through `for_each_best_reroll`, since the scores
+    // are only known after taking the log with the shift of each row, but it
+    // uses the same reroll operator.
+    let operator = &DICE_REROLL_OPERATORS[R::DICE - 1];
+    let a_mat = operator.matrix.t();
+    let n_kept = a_mat.ncols();
 
     if let Some(prev_scores) = prev_throw_layer_scores {
         scores
@@ -581,41 +861,55 @@ pub fn solve_layer_5dicex(
             .zip(prev_scores.outer_iter())
             .for_each_init(
                 || {
-                    Array2::from_shape_simple_fn(
-                        [n_bi, N_DICE_THROWS * 32],
-                        || 0.0,
+                    (
+                        Array2::zeros([n_bi, n_dice_throws]),
+                        Array2::zeros([n_bi, n_kept]),
                     )
                 },
-                |buf, ((mut scores, mut strats), prev_scores)| {
-                    general_mat_mul(1.0, &prev_scores, &a_mat, 0.0, buf);
-
-                    let buf3 = buf
-                        .view()
-                        .into_shape([n_bi, N_DICE_THROWS, 32])
-                        .unwrap();
-
-                    for ((score, strat), row) in scores
-                        .iter_mut()
-                        .zip(strats.iter_mut())
-                        .zip(buf3.rows())
-                    {
-                        let mut best_score = *score;
-                        let mut do_reroll = false;
-                        let mut best_reroll = 0;
-
-                        for (i, &sc) in row.iter().enumerate() {
-                            if sc > best_score {
-                                best_score = sc;
-                                do_reroll = true;
-                                best_reroll = i;
+                |(exps, buf), ((mut scores, mut strats), prev_scores)| {
+                    let shifts: Vec<f32> = prev_scores
+                        .rows()
+                        .into_iter()
+                        .map(|row| risk_shift(lambda, row))
+                        .collect();
+
+                    Zip::from(exps.rows_mut())
+                        .and(prev_scores.rows())
+                        .and(&shifts)
+                        .for_each(|mut exps, prev, &shift| {
+                            Zip::from(&mut exps).and(&prev).for_each(
+                                |e, &x| {
+                                    *e = (-lambda * (x - shift))
+                                        .max(MIN_RISK_EXPONENT)
+                                        .exp()
+                                },
+                            );
+                        });
+
+                    general_mat_mul(1.0, exps, &a_mat, 0.0, buf);
+
+                    Zip::indexed(&mut scores).and(&mut strats).for_each(
+                        |(bi, ti), score, strat| {
+                            let shift = shifts[bi];
+
+                            let mut best_score = *score;
+                            let mut best_reroll = None;
+
+                            for &(reroll, row) in &operator.rerolls[ti] {
+                                let sc = shift - buf[[bi, row]].ln() / lambda;
+
+                                if sc > best_score {
+                                    best_score = sc;
+                                    best_reroll = Some(reroll);
+                                }
                             }
-                        }
 
-                        if do_reroll {
-                            *score = best_score;
-                            *strat = best_reroll as u8 | 128;
-                        }
-                    }
+                            if let Some(reroll) = best_reroll {
+                                *score = best_score;
+                                *strat = reroll | 128;
+                            }
+                        },
+                    );
                 },
             );
     }
@@ -625,210 +919,206 @@ pub fn solve_layer_5dicex(
     (scores, strats)
 }
 
-type AboveLookupType = [Array2<[Option<[usize; 2]>; 6]>; 6];
+// This is the solver for forced yatzy, where the cells have to be filled in
+// order, so a state is just the next cell and the points above the line,
+// capped at the bonus goal. It solves every state of `cell` at once, given
+// the scores of the next cell, or None if it is the last one. The scores and
+// strats are indexed by (nt, points above, ti), and the strats are reroll
+// masks, which are zero when nt is 0 since the cell is forced.
+pub fn solve_layer_forced<const N: usize>(
+    cell: usize,
+    next_cell_scores: Option<ArrayView3<f32>>,
+) -> (Array3<f32>, Array3<u8>) {
+    let probs = throw_probs::<N>();
+    let throws = throws::<N>();
+    let goal = points_above::<N>();
+    let rerolls = rerolls::<N>();
 
-static ABOVE_LOOKUP_6: Lazy<AboveLookupType> = Lazy::new(|| {
-    let mut res = array::from_fn(|na| {
-        Array2::from_shape_simple_fn(
-            [ABOVE_LEVELS_6[na].len(), DICE_DISTR.6.len()],
-            || [None; 6],
-        )
-    });
+    let shape = [rerolls + 1, goal + 1, probs.len()];
 
-    for na in 0..6 {
-        for (ai, &(points_above, above_level)) in
-            ABOVE_LEVELS_6[na].iter().enumerate()
-        {
-            let state =
-                State::<20>::from((above_level, [false; 14], points_above));
+    let mut scores = Array3::zeros(shape);
+    let mut strats = Array3::zeros(shape);
 
-            for (ti, &(tr, _)) in DICE_DISTR.6.iter().enumerate() {
-                let throw = DiceThrow::from(tr);
+    // The expected remaining score before the first throw of the next cell.
+    let next_scores: Vec<f32> = (0..=goal)
+        .map(|pts| match &next_cell_scores {
+            Some(next) => probs
+                .iter()
+                .zip(next.slice(s![rerolls, pts, ..]))
+                .map(|(&p, &x)| p * x as f64)
+                .sum::<f64>() as f32,
+            None => 0.0,
+        })
+        .collect();
+
+    Zip::indexed(scores.index_axis_mut(Axis(0), 0)).for_each(
+        |(pts, ti), score| {
+            let points = throws[ti].cell_score::<N>(cell);
+
+            let new_pts = if cell < 6 {
+                (pts + points).min(goal)
+            } else {
+                pts
+            };
+
+            let bonus = with_rules!(N, R => R::bonus(new_pts) - R::bonus(pts));
+
+            *score = (points + bonus) as f32 + next_scores[new_pts];
+        },
+    );
 
-                for cell_i in (0..6).filter(|&i| !above_level[i]) {
-                    let (new_state, extra_score) =
-                        state.set_cell(cell_i, throw);
+    // Every reroll layer is a layer of the free game with a single above
+    // state, where the below states are the points above the line.
+    for nt in 1..=rerolls {
+        let (prev_scores, scores) = scores.view_mut().split_at(Axis(0), nt);
+
+        for_each_best_reroll(
+            N,
+            prev_scores.slice(s![nt - 1.., .., ..]),
+            scores.slice_move(s![..1, .., ..]),
+            strats.slice_mut(s![nt..nt + 1, .., ..]),
+            |score, strat, reroll, expected_score| {
+                *score = expected_score;
+                *strat = reroll;
+            },
+        );
+    }
 
-                    let new_ai = new_state.get_above_index();
+    (scores, strats)
+}
 
-                    res[na][[ai, ti]][cell_i] = Some([new_ai, extra_score]);
-                }
-            }
-        }
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::dice_distributions::DICE_REROLL_MATRICES;
+
+    // The best expected score of rerolling each throw, found by looping over
+    // the dice to keep and the throws of the rest, the way the reroll layers
+    // were solved before the reroll matrices.
+    fn best_rerolls<const N: usize>(prev_scores: &[f32]) -> Vec<f32> {
+        let mut kept_scores = HashMap::new();
+
+        (0..prev_scores.len())
+            .map(|ti| {
+                let dice = DiceThrow::from_index(N, ti);
+
+                dice.into_sub_throw_iter()
+                    .map(|kept| {
+                        *kept_scores.entry(kept).or_insert_with(|| {
+                            let n_rerolled = N - kept.amt_dice();
+
+                            DICE_PROBS[n_rerolled]
+                                .iter()
+                                .enumerate()
+                                .map(|(ri, &prob)| {
+                                    let rethrow =
+                                        DiceThrow::from_index(n_rerolled, ri);
+                                    let new_throw =
+                                        DiceThrow::from(array::from_fn::<
+                                            _,
+                                            6,
+                                            _,
+                                        >(
+                                            |i| kept[i + 1] + rethrow[i + 1],
+                                        ));
+
+                                    prob * prev_scores[new_throw.get_index()]
+                                        as f64
+                                })
+                                .sum::<f64>() as f32
+                        })
+                    })
+                    .fold(f32::NEG_INFINITY, f32::max)
+            })
+            .collect()
     }
 
-    res
-});
+    fn check_type2<const N: usize>() {
+        let n_throws = DICE_PROBS[N].len();
+        let prev_scores =
+            Array3::from_shape_fn([2, 2, n_throws], |(a, b, t)| {
+                ((a * 1000 + b * 100 + t) as f32 * 0.618).sin() * 50.0
+            });
 
-static BELOW_LOOKUP_6: Lazy<[Array2<Option<usize>>; 14]> = Lazy::new(|| {
-    let mut res = array::from_fn(|nb| {
-        Array2::from_shape_simple_fn([BELOW_LEVELS_6[nb].len(), 14], || None)
-    });
+        let (scores, strats) = solve_layer_type2::<N>(&prev_scores);
+        let matrix = &DICE_REROLL_MATRICES[N - 1];
 
-    for nb in 0..14 {
-        for (bi, &below_level) in BELOW_LEVELS_6[nb].iter().enumerate() {
-            for cell_i in (6..14 + 6).filter(|&i| !below_level[i - 6]) {
-                let mut new_level = below_level;
-                new_level[cell_i - 6] = true;
-                let state = State::<20>::from(([false; 6], new_level, 0));
+        for a in 0..2 {
+            for b in 0..2 {
+                let prev = prev_scores.slice(s![a, b, ..]);
+                let expected = best_rerolls::<N>(prev.as_slice().unwrap());
 
-                let new_bi = state.get_below_index();
+                for ti in 0..n_throws {
+                    let score = scores[[a, b, ti]];
+                    assert!((score - expected[ti]).abs() < 1e-3);
 
-                res[nb][[bi, cell_i - 6]] = Some(new_bi);
+                    // Close choices may go either way, but the chosen reroll
+                    // has to give the score.
+                    let reroll = strats[[a, b, ti]] as usize;
+                    let strat_score =
+                        matrix.slice(s![ti, reroll, ..]).dot(&prev);
+                    assert!((score - strat_score).abs() < 1e-3);
+                }
             }
         }
     }
 
-    res
-});
-
-static BELOW_PTS_LOOKUP_6: Lazy<Array2<usize>> = Lazy::new(|| {
-    Array2::from_shape_fn([DICE_DISTR.6.len(), 14], |(ti, cell_i)| {
-        let throw = DiceThrow::from(DICE_DISTR.6[ti].0);
-
-        throw.cell_score::<6>(cell_i + 6)
-    })
-});
-
-pub fn solve_layer_6dicex(
-    na: usize,
-    nb: usize,
-    prev_above_layer_scores: Option<ArrayView3<f32>>,
-    prev_below_layer_scores: Option<ArrayView3<f32>>,
-    prev_throw_layer_scores: Option<ArrayView3<f32>>,
-) -> (Array3<f32>, Array3<u8>) {
-    const N_DICE_THROWS: usize = DICE_DISTR.6.len();
-
-    let n_ai = ABOVE_LEVELS_6[na].len();
-    let n_bi = BELOW_LEVELS_6[nb].len();
-
-    let shape = [n_ai, n_bi, N_DICE_THROWS];
-
-    let mut scores = Array3::zeros(shape);
-    let mut strats = Array3::zeros(shape);
-
-    let timer = Instant::now();
-
-    if let (Some(prev_above_layer_scores), Some(prev_below_layer_scores)) =
-        (prev_above_layer_scores, prev_below_layer_scores)
-    {
-        let above_lookup = ABOVE_LOOKUP_6[na].view();
-        let below_lookup = BELOW_LOOKUP_6[nb].view();
-
-        Zip::indexed(&mut scores).and(&mut strats).par_for_each(
-            |(ai, bi, ti), cur_score, cur_strat| {
-                for (cell_i, [new_ai, extra_score]) in above_lookup[[ai, ti]]
-                    .iter()
-                    .enumerate()
-                    .filter_map(|(i, x)| x.map(|x| (i, x)))
-                {
-                    let prev_scores =
-                        prev_above_layer_scores.slice(s![new_ai, bi, ..]);
-
-                    let expected_score =
-                        DICE_DISTR.6.iter().zip(&prev_scores).fold(
-                            extra_score as f32 * DICE_DIVISOR[6] as f32,
-                            |score, (&(_, prob), x)| {
-                                x.mul_add(prob as f32, score)
-                            },
-                        ) / DICE_DIVISOR[6] as f32;
-
-                    if expected_score > *cur_score {
-                        *cur_score = expected_score;
-                        *cur_strat = cell_i as u8;
-                    }
-                }
-
-                let extra_scores = BELOW_PTS_LOOKUP_6.slice(s![ti, ..]);
-
-                for (cell_i_below, new_bi, extra_score) in below_lookup
-                    .slice(s![bi, ..])
-                    .iter()
-                    .zip(extra_scores)
-                    .enumerate()
-                    .filter_map(|(i, (x, &y))| x.map(|x| (i, x, y)))
-                {
-                    let prev_scores =
-                        prev_below_layer_scores.slice(s![ai, new_bi, ..]);
-
-                    let expected_score =
-                        DICE_DISTR.6.iter().zip(&prev_scores).fold(
-                            extra_score as f32 * DICE_DIVISOR[6] as f32,
-                            |score, (&(_, prob), x)| {
-                                x.mul_add(prob as f32, score)
-                            },
-                        ) / DICE_DIVISOR[6] as f32;
-
-                    if expected_score > *cur_score {
-                        *cur_score = expected_score;
-                        *cur_strat = (cell_i_below + 6) as u8;
-                    }
-                }
-            },
-        );
+    #[test]
+    fn type2_5dice() {
+        check_type2::<5>();
     }
 
-    println!("Cells took {:.2?}", timer.elapsed());
-
-    let timer = Instant::now();
-
-    // x_b,t2 * A_t1r,t2 = b_b,t1r
-
-    let a_mat = DICE_REROLL_MATRICES[5]
-        .view()
-        .into_shape([N_DICE_THROWS * 64, N_DICE_THROWS])
-        .unwrap()
-        .reversed_axes();
-
-    let tls = ThreadLocal::new();
-
-    if let Some(prev_scores) = prev_throw_layer_scores {
-        scores
-            .outer_iter_mut()
-            .into_par_iter()
-            .zip(strats.outer_iter_mut())
-            .zip(prev_scores.outer_iter())
-            .for_each(|((mut scores, mut strats), prev_scores)| {
-                let buf_cell = tls.get_or(|| {
-                    Cell::new(Array2::from_shape_simple_fn(
-                        [n_bi, N_DICE_THROWS * 64],
-                        || 0.0,
-                    ))
-                });
-
-                let mut buf = buf_cell.take();
+    #[test]
+    fn type2_6dice() {
+        check_type2::<6>();
+    }
 
-                general_mat_mul(1.0, &prev_scores, &a_mat, 0.0, &mut buf);
+    // In the last row and the last column of the grid there is no layer above
+    // or below, and every state still has to get one of its open cells, even
+    // when all of them give 0 points.
+    fn check_x_cells<R: CellRules>(na: usize, nb: usize) {
+        let max_nb = R::CELLS - 6;
+        let n_throws = DICE_PROBS[R::DICE].len();
 
-                let buf3 =
-                    buf.view().into_shape([n_bi, N_DICE_THROWS, 64]).unwrap();
+        let zeros = |na, nb| {
+            let [n_ai, n_bi] = R::layer_dim(na, nb);
 
-                for ((score, strat), row) in
-                    scores.iter_mut().zip(strats.iter_mut()).zip(buf3.rows())
-                {
-                    let mut best_score = *score;
-                    let mut do_reroll = false;
-                    let mut best_reroll = 0;
+            Array3::zeros([n_ai, n_bi, n_throws])
+        };
 
-                    for (i, &sc) in row.iter().enumerate() {
-                        if sc > best_score {
-                            best_score = sc;
-                            do_reroll = true;
-                            best_reroll = i;
-                        }
-                    }
+        let above = (na < 6).then(|| zeros(na + 1, nb));
+        let below = (nb < max_nb).then(|| zeros(na, nb + 1));
 
-                    if do_reroll {
-                        *score = best_score;
-                        *strat = best_reroll as u8 | 128;
-                    }
-                }
+        let (_, strats) = solve_layer_x::<R>(
+            na,
+            nb,
+            above.as_ref().map(|x| x.view()),
+            below.as_ref().map(|x| x.view()),
+            None,
+        );
 
-                buf_cell.set(buf);
+        for ((ai, bi, ti), &strat) in strats.indexed_iter() {
+            let mut open = Vec::new();
+            R::for_each_cell(na, nb, ai, bi, ti, |cell_i, _, _, _| {
+                open.push(cell_i)
             });
+
+            assert!(
+                open.contains(&(strat as usize)),
+                "Cell {strat} is not open in layer ({na}, {nb})!"
+            );
+        }
     }
 
-    println!("Rerolls took {:.2?}", timer.elapsed());
+    #[test]
+    fn x_cells_in_last_row_and_column() {
+        let max_nb = Yatzy::CELLS - 6;
 
-    (scores, strats)
+        check_x_cells::<Yatzy>(6, max_nb - 1);
+        check_x_cells::<Yatzy>(5, max_nb);
+        check_x_cells::<Yatzy>(6, max_nb - 2);
+        check_x_cells::<Yatzy>(4, max_nb);
+    }
 }
@@ -12,6 +12,9 @@ pub fn parse_binary(bits: &[bool]) -> usize {
     x
 }
 
-pub fn count_true<const N: usize>(bits: [bool; N]) -> usize {
-    bits.into_iter().filter(|&b| b).count()
+// The 64 bit FNV-1a hash of the bytes, which is used as a checksum.
+pub fn fnv1a(bytes: impl IntoIterator<Item = u8>) -> u64 {
+    bytes.into_iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
 }
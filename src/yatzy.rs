@@ -2,51 +2,50 @@
 
 use crate::{
     dice_throw::DiceThrow,
-    level_ordering::{
-        points_above, ABOVE_LEVELS_5_MAP, ABOVE_LEVELS_6_MAP,
-        BELOW_LEVELS_5_MAP, BELOW_LEVELS_6_MAP,
-    },
-    util::count_true,
+    level_ordering::{above_index, below_index, points_above},
+    rules::RuleSet,
+    util::parse_binary,
+    with_rules,
 };
 
-#[derive(Clone, Debug)]
-pub struct State<const CELLS: usize> {
-    pub cells: [bool; CELLS],
+// The filled cells are the bits of `cells`, with the cells above the line
+// being the lowest 6 bits.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct State<const N: usize> {
+    cells: u32,
     pub points_above: usize,
 }
 
-pub type YatzyState5 = State<15>;
-pub type YatzyState6 = State<20>;
+pub type YatzyState5 = State<5>;
+pub type YatzyState6 = State<6>;
 
-impl<const CELLS: usize> From<([bool; 6], [bool; CELLS - 6], usize)>
-    for State<CELLS>
+impl<const N: usize, const B: usize> From<([bool; 6], [bool; B], usize)>
+    for State<N>
 {
     fn from(
-        (above, below, points_above): ([bool; 6], [bool; CELLS - 6], usize),
+        (above, below, points_above): ([bool; 6], [bool; B], usize),
     ) -> Self {
-        let mut cells = [false; CELLS];
-        *cells.split_array_mut().0 = above;
-        *cells.rsplit_array_mut().1 = below;
+        assert!(B + 6 == cell_from_dice::<N>(), "Wrong amount of cells!");
+
         Self {
-            cells,
+            cells: (parse_binary(&below) << 6 | parse_binary(&above)) as u32,
             points_above,
         }
     }
 }
 
 pub const fn cell_from_dice<const N: usize>() -> usize {
-    match N {
-        5 => 15,
-        6 => 20,
-        _ => panic!(),
-    }
+    with_rules!(N, R => R::CELLS)
 }
 
+// The amount of dice of the game with CELLS cells, from when states were
+// generic over the amount of cells.
+#[deprecated(note = "State is generic over the amount of dice")]
 pub const fn dice_from_cells<const CELLS: usize>() -> usize {
     match CELLS {
         15 => 5,
         20 => 6,
-        _ => panic!(),
+        _ => panic!("Amount of cells not supported!"),
     }
 }
 
@@ -56,66 +55,76 @@ impl<const N: usize> Default for State<N> {
     }
 }
 
-impl<const CELLS: usize> State<CELLS> {
+impl<const N: usize> State<N> {
     pub fn new() -> Self {
         Self {
-            cells: [false; CELLS],
+            cells: 0,
             points_above: 0,
         }
     }
 
-    pub fn get_above_cells(&self) -> [bool; 6] {
-        *self.cells.split_array_ref().0
+    // The state with the filled cells above and below the line given as
+    // bitmasks, as returned by `get_above_cells` and `get_below_cells`.
+    pub fn from_masks(above: u32, below: u32, points_above: usize) -> Self {
+        Self {
+            cells: below << 6 | above,
+            points_above,
+        }
     }
 
-    pub fn get_below_cells(&self) -> [bool; CELLS - 6] {
-        *self.cells.rsplit_array_ref().1
+    pub fn is_filled(&self, i: usize) -> bool {
+        self.cells & 1 << i != 0
     }
 
-    pub fn split_cells(&self) -> ([bool; 6], [bool; CELLS - 6]) {
-        (self.get_above_cells(), self.get_below_cells())
+    // Whether each cell is filled, in the order of the cells, as in the
+    // `cells` array states used to have.
+    pub fn cells(&self) -> Vec<bool> {
+        (0..cell_from_dice::<N>())
+            .map(|i| self.is_filled(i))
+            .collect()
+    }
+
+    // The filled cells above and below the line, as `get_above_cells` and
+    // `get_below_cells` used to give them.
+    #[deprecated(note = "use get_above_cells and get_below_cells for masks")]
+    pub fn split_cells(&self) -> ([bool; 6], Vec<bool>) {
+        let cells = self.cells();
+
+        (cells[..6].try_into().unwrap(), cells[6..].to_vec())
+    }
+
+    pub fn open_cells(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..cell_from_dice::<N>()).filter(|&i| !self.is_filled(i))
+    }
+
+    // The filled cells above the line as a bitmask.
+    pub fn get_above_cells(&self) -> u32 {
+        self.cells & 0b111111
+    }
+
+    // The filled cells below the line as a bitmask, starting from bit 0.
+    pub fn get_below_cells(&self) -> u32 {
+        self.cells >> 6
     }
 
     pub fn get_n_cells(&self) -> usize {
-        count_true(self.cells)
+        self.cells.count_ones() as usize
     }
 
     pub fn get_n_above(&self) -> usize {
-        count_true(self.get_above_cells())
+        self.get_above_cells().count_ones() as usize
     }
 
-    pub fn get_n_below(&self) -> usize
-    where
-        [(); CELLS - 6]:,
-    {
-        count_true(self.get_below_cells())
+    pub fn get_n_below(&self) -> usize {
+        self.get_below_cells().count_ones() as usize
     }
 
     fn get_bonus(&self) -> usize {
-        match CELLS {
-            15 => {
-                if self.points_above >= 63 {
-                    50
-                } else {
-                    0
-                }
-            }
-            20 => {
-                if self.points_above >= 84 {
-                    100
-                } else {
-                    0
-                }
-            }
-            _ => unreachable!(),
-        }
+        with_rules!(N, R => R::bonus(self.points_above))
     }
 
-    pub fn modify_cell(&mut self, i: usize, throw: DiceThrow) -> usize
-    where
-        [(); dice_from_cells::<CELLS>()]:,
-    {
-        let points = throw.cell_score::<{ dice_from_cells::<CELLS>() }>(i);
+    pub fn modify_cell(&mut self, i: usize, throw: DiceThrow) -> usize {
+        let points = throw.cell_score::<N>(i);
 
         let old_bonus = self.get_bonus();
 
@@ -125,58 +134,35 @@ impl<const CELLS: usize> State<CELLS> {
 
         let new_bonus = self.get_bonus();
 
-        self.cells[i] = true;
+        self.cells |= 1 << i;
 
         points + new_bonus - old_bonus
     }
 
-    pub fn set_cell(&self, i: usize, throw: DiceThrow) -> (Self, usize)
-    where
-        [(); dice_from_cells::<CELLS>()]:,
-    {
-        let mut state = self.clone();
+    pub fn set_cell(&self, i: usize, throw: DiceThrow) -> (Self, usize) {
+        let mut state = *self;
 
         let score = state.modify_cell(i, throw);
 
         (state, score)
     }
 
-    pub fn from_dyn(dyn_cells: &[bool], points_above: usize) -> Self
-    where
-        [(); CELLS - 6]:,
-    {
-        assert!(dyn_cells.len() == CELLS);
-        let mut cells = [false; CELLS];
-        *cells.split_array_mut().0 = *dyn_cells.split_array_ref::<6>().0;
-        *cells.rsplit_array_mut().1 =
-            *dyn_cells.rsplit_array_ref::<{ CELLS - 6 }>().1;
+    pub fn from_dyn(dyn_cells: &[bool], points_above: usize) -> Self {
+        assert!(dyn_cells.len() == cell_from_dice::<N>());
+
         Self {
-            cells,
+            cells: parse_binary(dyn_cells) as u32,
             points_above,
         }
     }
-}
-
-impl YatzyState5 {
-    pub fn get_above_index(&self) -> usize {
-        let pts = points_above::<5>().min(self.points_above);
-
-        ABOVE_LEVELS_5_MAP[&(pts, self.get_above_cells())]
-    }
-
-    pub fn get_below_index(&self) -> usize {
-        BELOW_LEVELS_5_MAP[&self.get_below_cells()]
-    }
-}
 
-impl YatzyState6 {
     pub fn get_above_index(&self) -> usize {
-        let pts = points_above::<6>().min(self.points_above);
+        let pts = points_above::<N>().min(self.points_above);
 
-        ABOVE_LEVELS_6_MAP[&(pts, self.get_above_cells())]
+        above_index::<N>(pts, self.get_above_cells())
     }
 
     pub fn get_below_index(&self) -> usize {
-        BELOW_LEVELS_6_MAP[&self.get_below_cells()]
+        below_index(self.get_below_cells())
     }
 }